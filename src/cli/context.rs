@@ -0,0 +1,108 @@
+use crate::error::{ProfileError, Result};
+use crate::git::config::GitConfigManager;
+use crate::profile::manager::ProfileManager;
+use crate::ssh::config::SSHConfigManager;
+use crate::storage::service::StorageService;
+use crate::storage::Settings;
+use crate::switcher::ProfileSwitcher;
+use crate::utils::color::ColorMode;
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+/// Global options shared by every command handler
+#[derive(Debug, Default, Clone)]
+pub struct Context {
+    /// Log intended changes instead of writing anything
+    pub dry_run: bool,
+    /// When to print ANSI colors
+    pub color: ColorMode,
+    /// Write command output to this file instead of stdout
+    pub output: Option<PathBuf>,
+}
+
+impl Context {
+    /// Create a new Context
+    pub fn new(dry_run: bool, color: ColorMode) -> Self {
+        Self { dry_run, color, output: None }
+    }
+
+    /// Redirect the output of read-only commands to a file
+    pub fn with_output(mut self, output: Option<PathBuf>) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Whether output may contain ANSI colors; with `auto`, only when
+    /// writing to a terminal rather than a file or pipe
+    pub fn use_color(&self) -> bool {
+        let is_terminal = self.output.is_none() && io::stdout().is_terminal();
+        self.color.enabled(is_terminal)
+    }
+
+    /// Open the sink command output should be written to
+    pub fn out(&self) -> Result<Box<dyn Write>> {
+        match &self.output {
+            Some(path) => {
+                let file = File::create(path).map_err(|e| {
+                    ProfileError::PermissionDenied(format!("{}: {}", path.display(), e))
+                })?;
+                Ok(Box::new(io::BufWriter::new(file)))
+            }
+            None => Ok(Box::new(io::stdout())),
+        }
+    }
+
+    /// Create a StorageService that respects the context's options
+    pub fn storage(&self) -> Result<StorageService> {
+        let mut storage = StorageService::new()?;
+        storage.set_dry_run(self.dry_run);
+        Ok(storage)
+    }
+
+    /// Create a ProfileManager that respects the context's options
+    pub fn profile_manager(&self) -> Result<ProfileManager> {
+        let mut manager = ProfileManager::new()?;
+        manager.set_dry_run(self.dry_run);
+        Ok(manager)
+    }
+
+    /// Create a GitConfigManager that respects the context's options
+    pub fn git_config(&self) -> GitConfigManager {
+        let mut git_config = GitConfigManager::new();
+        git_config.set_dry_run(self.dry_run);
+        git_config
+    }
+
+    /// Create an SSHConfigManager that respects the context's options
+    pub fn ssh_config(&self) -> Result<SSHConfigManager> {
+        let settings = self.settings()?;
+        let mut ssh_config = SSHConfigManager::new()?;
+        ssh_config.set_dry_run(self.dry_run);
+        ssh_config.set_auto_create(settings.auto_create_ssh_config);
+        ssh_config.set_section(settings.ssh_config_section);
+        ssh_config.set_identities_only(settings.ssh_identities_only);
+        Ok(ssh_config)
+    }
+
+    /// Create a ProfileSwitcher that respects the context's options
+    pub fn switcher(&self) -> Result<ProfileSwitcher> {
+        let settings = self.settings()?;
+        let mut switcher = ProfileSwitcher::new()?;
+        switcher.set_dry_run(self.dry_run);
+        switcher.set_auto_create_ssh_config(settings.auto_create_ssh_config);
+        switcher.set_ssh_config_section(settings.ssh_config_section);
+        switcher.set_ssh_identities_only(settings.ssh_identities_only);
+        Ok(switcher)
+    }
+
+    /// Read the stored settings without creating the profiles file, falling
+    /// back to defaults when it's missing or unreadable
+    fn settings(&self) -> Result<Settings> {
+        let storage = self.storage()?;
+        if !storage.config_path.exists() {
+            return Ok(Settings::default());
+        }
+        Ok(storage.load().map(|data| data.settings).unwrap_or_default())
+    }
+}
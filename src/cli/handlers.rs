@@ -1,18 +1,96 @@
+use crate::cli::context::Context;
+use crate::doctor::{self, CheckStatus, Fix};
 use crate::error::Result;
-use crate::git::ConfigScope;
-use crate::profile::manager::ProfileManager;
-use crate::profile::Profile;
-use crate::switcher::ProfileSwitcher;
+use crate::git::config::GitConfigManager;
+use crate::git::executor::execute_git;
+use crate::git::{gh, remote};
+use crate::git::{ConfigScope, HeadState};
+use crate::profile::{Profile, SigningMode};
+use crate::selftest;
+use crate::switcher::{self, SwitchOptions};
+use crate::tui::theme::Theme;
+use crate::utils::color::{paint, COLOR_NAMES};
+use crate::utils::table::{truncate, Cell, Table};
+use crate::utils::time;
 use crate::utils::validator::Validator;
-use dialoguer::{Confirm, Input};
+use crate::ssh::{agent, keygen};
+use crate::ssh::config::{HostState, SSHConfigManager};
+use crate::ssh::verify::{self, ProbeResult};
+use crate::storage::context::ContextStore;
+use crate::storage::history::{self, HistoryEntry, SwitchHistory};
+use crate::storage::service::StorageService;
+use crate::storage::snapshot::Snapshot;
+use crate::storage::{export, recover, Settings, StorageData};
+use clap::{Args, ValueEnum};
+use dialoguer::{Confirm, Input, Password};
+use serde::Serialize;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Arguments for the 'add' command
+#[derive(Debug, Args)]
+pub struct AddArgs {
+    /// Profile name
+    pub name: String,
+    /// GitHub username
+    #[arg(short, long)]
+    pub username: String,
+    /// Email address (defaults to the comment in the key's .pub file)
+    #[arg(short, long)]
+    pub email: Option<String>,
+    /// SSH key name (e.g., id_rsa_personal); defaults to id_ed25519_<name>,
+    /// with an offer to generate it
+    #[arg(short, long)]
+    pub ssh_key: Option<String>,
+    /// Color used to display the profile (e.g., red, cyan)
+    #[arg(long = "profile-color", value_name = "COLOR")]
+    pub color: Option<String>,
+    /// Sign commits with the profile's SSH key
+    #[arg(long, value_enum, default_value_t = SigningMode::None)]
+    pub signing_mode: SigningMode,
+    /// Set git's pull.rebase when switching to this profile
+    #[arg(long)]
+    pub pull_rebase: Option<bool>,
+    /// Set git's init.defaultBranch when switching to this profile
+    #[arg(long)]
+    pub default_branch: Option<String>,
+    /// Add `AddKeysToAgent yes` to the profile's SSH host block
+    #[arg(long)]
+    pub add_keys_to_agent: bool,
+    /// Add `UseKeychain yes` to the profile's SSH host block (macOS only)
+    #[arg(long)]
+    pub use_keychain: bool,
+    /// Only rewrite https://github.com/<org>/ URLs to this profile's SSH host
+    #[arg(long)]
+    pub org: Option<String>,
+    /// Overwrite the profile if it already exists instead of failing
+    #[arg(long)]
+    pub force: bool,
+    /// Never touch SSH when switching to this profile, only the commit identity
+    #[arg(long)]
+    pub commit_identity_only: bool,
+    /// SSH port for the host block; 443 connects through ssh.github.com
+    /// for networks that block port 22
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub port: Option<u16>,
+    /// Free-text note shown in 'list --verbose', status and the TUI
+    #[arg(long)]
+    pub description: Option<String>,
+    /// Reminder printed on every switch to this profile and in status,
+    /// e.g. "squash-merge only on this account"
+    #[arg(long)]
+    pub note: Option<String>,
+    /// Extra git config set on every switch to this profile, e.g.
+    /// 'pull.rebase=true' (repeatable)
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub git_config: Vec<String>,
+}
 
 /// Handle the 'add' command to create a new profile
-pub fn handle_add(
-    name: String,
-    username: String,
-    email: String,
-    ssh_key: String,
-) -> Result<()> {
+pub fn handle_add(ctx: &Context, args: AddArgs) -> Result<()> {
+    let name = args.name;
     println!("Creating new profile '{}'...", name);
 
     // Validate inputs
@@ -23,79 +101,726 @@ pub fn handle_add(
         ));
     }
 
-    if !Validator::validate_username(&username) {
+    if !Validator::validate_username(&args.username) {
         return Err(crate::error::ProfileError::InvalidInput(
             "Invalid GitHub username format".to_string(),
         ));
     }
 
-    if !Validator::validate_email(&email) {
+    let derived_key = args.ssh_key.is_none();
+    let ssh_key = match args.ssh_key {
+        Some(ssh_key) => ssh_key,
+        None => {
+            let ssh_key = keygen::default_key_name(&name);
+            println!("Using SSH key name '{}'", ssh_key);
+            ssh_key
+        }
+    };
+
+    if !Validator::validate_ssh_key_name(&ssh_key) {
         return Err(crate::error::ProfileError::InvalidInput(
-            "Invalid email format".to_string(),
+            "Invalid SSH key name".to_string(),
         ));
     }
 
-    if !Validator::validate_ssh_key_name(&ssh_key) {
+    let key_email = keygen::email_from_key(&ssh_key);
+    let email = match args.email {
+        Some(email) => {
+            // A different email in the key comment often means the wrong key
+            if let Some(key_email) = key_email.filter(|k| !k.eq_ignore_ascii_case(&email)) {
+                println!("⚠ {}.pub was generated for '{}', not '{}'", ssh_key, key_email, email);
+            }
+            email
+        }
+        None => prompt_email(key_email)?,
+    };
+
+    if !Validator::validate_email(&email) {
         return Err(crate::error::ProfileError::InvalidInput(
-            "Invalid SSH key name".to_string(),
+            "Invalid email format".to_string(),
         ));
     }
 
+    if let Some(color) = &args.color {
+        if !Validator::validate_color(color) {
+            return Err(invalid_color(color));
+        }
+    }
+
+    if let Some(branch) = &args.default_branch {
+        if !Validator::validate_branch_name(branch) {
+            return Err(invalid_branch(branch));
+        }
+    }
+
     // Create the profile
-    let mut manager = ProfileManager::new()?;
-    let profile = Profile {
-        name: name.clone(),
-        username,
-        email,
-        ssh_key_name: ssh_key,
+    let mut manager = ctx.profile_manager()?;
+    let mut profile = Profile::new(name.clone(), args.username, email, ssh_key);
+    profile.color = args.color;
+    profile.signing_mode = args.signing_mode;
+    profile.pull_rebase = args.pull_rebase;
+    profile.default_branch = args.default_branch;
+    profile.add_keys_to_agent = args.add_keys_to_agent;
+    profile.use_keychain = args.use_keychain;
+    profile.org = args.org;
+    profile.commit_identity_only = args.commit_identity_only;
+    profile.ssh_port = args.port;
+    profile.description = match &args.description {
+        Some(text) => Profile::clean_description(text)?,
+        None => None,
     };
+    profile.note = match &args.note {
+        Some(text) => Profile::clean_note(text)?,
+        None => None,
+    };
+    profile.git_config = args
+        .git_config
+        .iter()
+        .map(|entry| Profile::parse_git_config(entry))
+        .collect::<Result<_>>()?;
+    profile.validate()?;
+
+    // An existing hand-written block for the same alias would shadow ours
+    let mut ssh_config = ctx.ssh_config()?;
+    ssh_config.check_host_conflict(&profile)?;
+
+    let profile = if !args.force {
+        let profile = manager.create_profile(profile)?;
+        println!("✓ Profile '{}' created successfully!", profile.name);
+        profile
+    } else {
+        let refresh_host = ssh_config.list_managed_hosts()?.contains(&name);
+        let (profile, created) = manager.upsert_profile(profile)?;
+        if created {
+            println!("✓ Profile '{}' created successfully!", profile.name);
+        } else {
+            // Keep an installed host block in step with the new values
+            if refresh_host {
+                ssh_config.add_or_update_host(&profile)?;
+            }
+            println!("✓ Profile '{}' updated", profile.name);
+        }
+        profile
+    };
+
+    if derived_key {
+        offer_key_generation(ctx, &profile.ssh_key_name, &profile.email)?;
+    }
+    Ok(())
+}
+
+/// Offer to create a missing key with `ssh-keygen`; without a terminal,
+/// print the command instead
+fn offer_key_generation(ctx: &Context, ssh_key: &str, email: &str) -> Result<()> {
+    let key_path = SSHConfigManager::get_ssh_key_path(ssh_key);
+    if key_path.exists() {
+        return Ok(());
+    }
+
+    if !std::io::stdin().is_terminal() {
+        println!("  {} doesn't exist yet; create it with:", key_path.display());
+        println!("    ssh-keygen -t ed25519 -C {} -f {}", email, key_path.display());
+        return Ok(());
+    }
+
+    let generate = Confirm::new()
+        .with_prompt(format!("{} doesn't exist. Generate a new ed25519 key?", key_path.display()))
+        .default(true)
+        .interact()
+        .unwrap_or(false);
+
+    if generate && ctx.dry_run {
+        println!("[dry-run] Would run: ssh-keygen -t ed25519 -C {} -f {}", email, key_path.display());
+    } else if generate {
+        keygen::generate_key(&key_path, email)?;
+        println!("✓ Generated {}", key_path.display());
+    }
+    Ok(())
+}
+
+/// Emails longer than this are truncated in table output
+const TABLE_EMAIL_MAX_WIDTH: usize = 32;
+
+/// Output formats for the 'list' command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// One block per profile
+    List,
+    /// Column-aligned table
+    Table,
+}
+
+/// Values the hidden '__complete' command can suggest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionKind {
+    /// Stored profile names
+    Profiles,
+    /// Private key files in ~/.ssh
+    SshKeys,
+}
 
-    manager.create_profile(profile)?;
+/// Handle the hidden '__complete' command: print candidates starting with
+/// `prefix`, one per line, for shell completion scripts. Errors print
+/// nothing so a broken config never garbles the shell.
+pub fn handle_complete(ctx: &Context, kind: CompletionKind, prefix: Option<String>) -> Result<()> {
+    let candidates = match kind {
+        CompletionKind::Profiles => ctx
+            .storage()
+            .and_then(|storage| storage.profile_names())
+            .unwrap_or_default(),
+        CompletionKind::SshKeys => match crate::utils::paths::home_dir() {
+            Some(home) => keygen::list_private_keys(&home.join(".ssh")),
+            None => Vec::new(),
+        },
+    };
 
-    println!("✓ Profile '{}' created successfully!", name);
+    let prefix = prefix.unwrap_or_default();
+    for candidate in candidates.iter().filter(|c| c.starts_with(&prefix)) {
+        println!("{}", candidate);
+    }
     Ok(())
 }
 
 /// Handle the 'list' command to display all profiles
-pub fn handle_list() -> Result<()> {
-    let manager = ProfileManager::new()?;
-    let profiles = manager.get_all_profiles()?;
+pub fn handle_list(
+    ctx: &Context,
+    format: ListFormat,
+    installed_keys: bool,
+    remote_match: bool,
+    verbose: bool,
+    sort_by_usage: bool,
+) -> Result<()> {
+    let data = ctx.storage()?.load()?;
+    let profiles = data.profiles;
+    let mut out = ctx.out()?;
+
+    // Numbers stay those of the stored order, which 'gex switch <#>' uses
+    let mut numbered: Vec<(usize, &Profile)> = (1..).zip(&profiles).collect();
+    if sort_by_usage {
+        let last_used = history::last_used(&SwitchHistory::new()?.entries()?);
+        numbered.sort_by(|(_, a), (_, b)| history::cmp_by_usage(a, b, &last_used));
+    }
 
     if profiles.is_empty() {
-        println!("No profiles found.");
-        println!("\nCreate a profile with: gex add <name> --username <user> --email <email> --ssh-key <key>");
+        writeln!(out, "No profiles found.")?;
+        writeln!(out, "\nCreate a profile with: gex add <name> --username <user> --email <email> --ssh-key <key>")?;
         return Ok(());
     }
 
-    println!("Available profiles:\n");
-    for profile in profiles {
-        println!("  {} {}", "●".to_string(), profile.name);
-        println!("    Username: {}", profile.username);
-        println!("    Email: {}", profile.email);
-        println!("    SSH Key: {}", profile.ssh_key_name);
-        println!();
+    // With --installed-keys the key name carries a ✓/✗ presence marker
+    let key_label = |profile: &Profile| {
+        if !installed_keys {
+            return profile.ssh_key_name.clone();
+        }
+        let present = SSHConfigManager::validate_ssh_key(&profile.ssh_key_name).unwrap_or(false);
+        format!("{} {}", profile.ssh_key_name, if present { "✓" } else { "✗" })
+    };
+
+    // With --remote-match, parse origin once and compare it to each host alias
+    let origin_host = if remote_match {
+        let host = remote::origin_url()?.and_then(|url| remote::parse_host(&url));
+        if host.is_none() {
+            writeln!(out, "No origin remote (or not in a git repository); nothing to match.\n")?;
+        }
+        host
+    } else {
+        None
+    };
+    let matches_origin = |profile: &Profile| origin_host.as_deref() == Some(profile.ssh_host().as_str());
+
+    if format == ListFormat::Table {
+        let status = ctx.switcher()?.get_current_status()?;
+        let is_active = |scope: &Option<Profile>, name: &str| scope.as_ref().is_some_and(|p| p.name == name);
+
+        let mut headers = vec!["#", "NAME", "USERNAME", "EMAIL", "SSH KEY", "ACTIVE"];
+        if remote_match {
+            headers.push("ORIGIN");
+        }
+        if verbose {
+            headers.push("DESCRIPTION");
+        }
+        let mut table = Table::new(&headers);
+        for &(number, profile) in &numbered {
+            let active = match (is_active(&status.global, &profile.name), is_active(&status.local, &profile.name)) {
+                (true, true) => "global, local",
+                (true, false) => "global",
+                (false, true) => "local",
+                (false, false) => "",
+            };
+            let mut row = vec![
+                Cell::new(number.to_string()),
+                Cell::colored(&profile.name, profile.color.as_deref()),
+                Cell::new(&profile.username),
+                Cell::new(truncate(&profile.email, TABLE_EMAIL_MAX_WIDTH)),
+                Cell::new(key_label(profile)),
+                Cell::new(active),
+            ];
+            if remote_match {
+                row.push(Cell::new(if matches_origin(profile) { "match" } else { "" }));
+            }
+            if verbose {
+                row.push(Cell::new(profile.description.as_deref().unwrap_or("")));
+            }
+            table.add_row(row);
+        }
+
+        write!(out, "{}", table.render(ctx.use_color()))?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    writeln!(out, "Available profiles:\n")?;
+    for &(number, profile) in &numbered {
+        if matches_origin(profile) {
+            writeln!(out, "  {}. ● {}  ← matches this repo's origin", number, profile.name)?;
+        } else {
+            writeln!(out, "  {}. ● {}", number, profile.name)?;
+        }
+        writeln!(out, "    Username: {}", profile.username)?;
+        writeln!(out, "    Email: {}", profile.email)?;
+        writeln!(out, "    SSH Key: {}", key_label(profile))?;
+        if verbose {
+            if let Some(description) = &profile.description {
+                writeln!(out, "    Description: {}", description)?;
+            }
+        }
+        writeln!(out)?;
+    }
+    writeln!(out, "Switch by number with: gex switch <#>")?;
+    writeln!(out, "Last modified: {}", time::format_stored(&data.last_modified))?;
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Handle 'list --check': report only the profiles that can't be used
+pub fn handle_list_check(ctx: &Context) -> Result<()> {
+    let profiles = ctx.profile_manager()?.get_all_profiles()?;
+    let mut out = ctx.out()?;
+    let mut broken = 0;
+
+    for profile in &profiles {
+        let problems = doctor::profile_problems(profile);
+        if problems.is_empty() {
+            continue;
+        }
+
+        broken += 1;
+        writeln!(out, "  ✗ {}", profile.name)?;
+        for problem in problems {
+            writeln!(out, "      {}", problem)?;
+        }
     }
 
+    if broken > 0 {
+        out.flush()?;
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "{} of {} profile(s) are broken",
+            broken,
+            profiles.len()
+        )));
+    }
+
+    writeln!(out, "✓ All {} profile(s) OK", profiles.len())?;
+    out.flush()?;
     Ok(())
 }
 
+/// Arguments for the 'switch' command
+#[derive(Debug, Default, Args)]
+pub struct SwitchArgs {
+    /// Profile name (or its number in 'gex list') to switch to
+    #[arg(required_unless_present = "profile_file", conflicts_with = "profile_file")]
+    pub name: Option<String>,
+    /// Apply a profile from a JSON/YAML file without storing it (e.g. in CI)
+    #[arg(long)]
+    pub profile_file: Option<PathBuf>,
+    /// Apply globally (default is local to current repository)
+    #[arg(short, long)]
+    pub global: bool,
+    /// In a linked worktree, apply only to this worktree's config
+    #[arg(long, conflicts_with_all = ["global", "main"])]
+    pub worktree: bool,
+    /// Apply to the system-wide git config (usually needs sudo/administrator)
+    #[arg(long, conflicts_with_all = ["global", "worktree", "main"])]
+    pub system: bool,
+    /// In a linked worktree, apply to the config shared with the main repository
+    #[arg(long, conflicts_with = "global")]
+    pub main: bool,
+    /// Skip checking that the SSH key file exists (agent/hardware token keys)
+    #[arg(long)]
+    pub no_verify_key: bool,
+    /// Print the `git remote set-url` command that points origin at the profile's host
+    #[arg(long)]
+    pub announce: bool,
+    /// Only set user.name, user.email and signing; skip the SSH key check and host block
+    #[arg(long)]
+    pub commit_identity_only: bool,
+    /// Note recorded with this switch in 'gex history' (e.g. a ticket number)
+    #[arg(long)]
+    pub comment: Option<String>,
+    /// Don't ask before switching when origin uses another profile's host
+    #[arg(short, long)]
+    pub yes: bool,
+    /// Don't print progress steps or notes; warnings go to stderr
+    #[arg(short, long)]
+    pub quiet: bool,
+    /// Print only `switched<TAB><profile><TAB><scope>` on success, for scripts
+    #[arg(long, conflicts_with = "announce")]
+    pub porcelain: bool,
+    /// Point origin at the profile's host alias (`git remote set-url`) if it is a github.com URL
+    #[arg(long, alias = "set-upstream-remote", conflicts_with_all = ["global", "system", "announce", "porcelain"])]
+    pub fix_remote: bool,
+}
+
 /// Handle the 'switch' command to switch to a profile
-pub fn handle_switch(name: String, global: bool) -> Result<()> {
-    let scope = if global {
+pub fn handle_switch(ctx: &Context, args: SwitchArgs) -> Result<()> {
+    if let Some(comment) = &args.comment {
+        history::validate_comment(comment)?;
+    }
+
+    let scope = if args.global {
         ConfigScope::Global
+    } else if args.worktree {
+        ConfigScope::Worktree
+    } else if args.system {
+        ConfigScope::System
     } else {
         ConfigScope::Local
     };
 
-    let mut switcher = ProfileSwitcher::new()?;
-    switcher.switch_profile(&name, scope)?;
+    let quiet = args.quiet || args.porcelain;
+    if scope == ConfigScope::System && !quiet {
+        println!("Note: system scope affects every user on this machine and usually requires");
+        println!("      elevated privileges (sudo or an administrator shell).\n");
+    }
+
+    // In a linked worktree, local config is shared with the main repository
+    if scope == ConfigScope::Local && !args.main && !quiet && GitConfigManager::is_linked_worktree()? {
+        println!("Note: this is a linked worktree; local config is shared with the main repository.");
+        println!("      Use --worktree to apply to this worktree only, or --main to silence this note.\n");
+    }
+
+    let options = SwitchOptions {
+        no_verify_key: args.no_verify_key,
+        commit_identity_only: args.commit_identity_only,
+        quiet,
+    };
+
+    let profile = match (&args.profile_file, &args.name) {
+        (Some(path), _) => Profile::from_file(path)?,
+        (None, Some(name)) => ctx.profile_manager()?.resolve_profile(name)?,
+        (None, None) => {
+            return Err(crate::error::ProfileError::InvalidInput(
+                "Provide a profile name or --profile-file".to_string(),
+            ))
+        }
+    };
+
+    // Right identity, wrong key (or the reverse) is easy to miss until a
+    // push. --fix-remote resolves it by rewriting origin.
+    if matches!(scope, ConfigScope::Local | ConfigScope::Worktree) && !args.fix_remote {
+        if let Some(warning) = switcher::origin_host_mismatch(&profile)? {
+            if quiet {
+                eprintln!("⚠ Warning: {}", warning);
+            } else {
+                println!("⚠ Warning: {}\n", warning);
+            }
+            let proceed = args.yes
+                || Confirm::new()
+                    .with_prompt(format!("Switch to '{}' anyway?", profile.name))
+                    .default(false)
+                    .interact()
+                    .unwrap_or(false);
+            if !proceed {
+                return Err(crate::error::ProfileError::InvalidInput(
+                    "Switch cancelled; pass --yes to switch anyway".to_string(),
+                ));
+            }
+        }
+    }
+
+    let mut switcher = ctx.switcher()?;
+    match args.profile_file {
+        Some(_) => switcher.switch_to_ephemeral(&profile, scope, &options)?,
+        None => switcher.switch_profile_with_options(&profile.name, scope, &options)?,
+    }
+
+    record_switch(ctx, &profile, scope, args.comment)?;
+
+    if args.porcelain {
+        println!("switched\t{}\t{}", profile.name, scope);
+    }
+    if args.announce {
+        announce_remote(&profile)?;
+    }
+    if args.fix_remote {
+        fix_remote(ctx, &profile)?;
+    }
+
+    Ok(())
+}
+
+/// Append a successful switch to the history log
+fn record_switch(ctx: &Context, profile: &Profile, scope: ConfigScope, comment: Option<String>) -> Result<()> {
+    if ctx.dry_run {
+        return Ok(());
+    }
+    SwitchHistory::new()?.record(profile, scope, comment)?;
+    Ok(())
+}
+
+/// Point origin at the profile's host alias (switch --fix-remote)
+fn fix_remote(ctx: &Context, profile: &Profile) -> Result<()> {
+    let Some(url) = remote::origin_url()? else {
+        println!("\nNote: no 'origin' remote found; nothing to fix.");
+        return Ok(());
+    };
+
+    let Some(new_url) = switcher::remote_url_for(profile, &url) else {
+        println!("\nNote: origin ({}) is not a github.com URL; left unchanged.", url);
+        return Ok(());
+    };
+    if new_url == url {
+        println!("\nOrigin already uses the '{}' host.", profile.ssh_host());
+        return Ok(());
+    }
+
+    if ctx.dry_run {
+        println!("[dry-run] Would run: git remote set-url origin {}", new_url);
+        return Ok(());
+    }
+    execute_git(&["remote", "set-url", "origin", &new_url])?;
+    println!("\nUpdated origin:");
+    println!("  before: {}", url);
+    println!("  after:  {}", new_url);
+    Ok(())
+}
+
+/// Handle the 'run' command: run a command with the profile's identity and
+/// key in its environment, without changing any git or SSH config
+pub fn handle_run(ctx: &Context, name: String, command: Vec<String>) -> Result<()> {
+    let profile = ctx
+        .profile_manager()?
+        .get_profile(&name)?
+        .ok_or(crate::error::ProfileError::ProfileNotFound(name))?;
+    let Some((program, args)) = command.split_first() else {
+        return Err(crate::error::ProfileError::InvalidInput(
+            "No command given; usage: gex run <profile> -- <command> [args...]".to_string(),
+        ));
+    };
+
+    let key_path = SSHConfigManager::get_ssh_key_path(&profile.ssh_key_name);
+    crate::utils::paths::require_utf8(&key_path, "SSH key")?;
+    let config_count = std::env::var("GIT_CONFIG_COUNT")
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(0);
+    let env = profile.git_env(&key_path, config_count);
+
+    if ctx.dry_run {
+        for (key, value) in &env {
+            println!("[dry-run] Would set {}={}", key, value);
+        }
+        println!("[dry-run] Would run: {}", command.join(" "));
+        return Ok(());
+    }
+
+    let status = std::process::Command::new(program)
+        .args(args)
+        .envs(env)
+        .status()
+        .map_err(|e| crate::error::ProfileError::InvalidInput(format!("Failed to run '{}': {}", program, e)))?;
+
+    // Pass the command's own exit status through
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// Handle the 'history' command to show recent switches, optionally only
+/// those newer than `since` (e.g. "7d") or for one profile
+pub fn handle_history(limit: usize, since: Option<String>, profile: Option<String>) -> Result<()> {
+    let cutoff = match since {
+        Some(since) => Some(chrono::Utc::now() - time::parse_duration(&since)?),
+        None => None,
+    };
+
+    let entries: Vec<HistoryEntry> = SwitchHistory::new()?
+        .entries()?
+        .into_iter()
+        .filter(|entry| cutoff.is_none_or(|cutoff| entry.timestamp >= cutoff))
+        .filter(|entry| profile.as_ref().is_none_or(|name| &entry.profile == name))
+        .collect();
+    if entries.is_empty() {
+        println!("No matching switches recorded.");
+        return Ok(());
+    }
+
+    for entry in &entries[entries.len().saturating_sub(limit)..] {
+        println!("{}", entry.display());
+    }
+    Ok(())
+}
+
+/// Print (without running) the command that points origin at the profile's host alias
+fn announce_remote(profile: &Profile) -> Result<()> {
+    let Some(url) = remote::origin_url()? else {
+        println!("\nNo 'origin' remote found; nothing to announce.");
+        return Ok(());
+    };
+
+    let Some((owner, repo)) = remote::parse_owner_repo(&url) else {
+        println!("\nCould not read owner/repo from origin URL '{}'.", url);
+        return Ok(());
+    };
+
+    let new_url = profile.ssh_clone_url(&format!("{}/{}", owner, repo));
+    if new_url == url {
+        println!("\nOrigin already uses the '{}' host.", profile.ssh_host());
+        return Ok(());
+    }
+
+    println!("\nTo push and pull with this profile's key, run:\n");
+    println!("  git remote set-url origin {}", new_url);
+    Ok(())
+}
+
+/// Handle the 'url' command to print a profile's clone URL for a repository
+pub fn handle_url(ctx: &Context, name: String, repo: String, https: bool) -> Result<()> {
+    let profile = ctx
+        .profile_manager()?
+        .get_profile(&name)?
+        .ok_or(crate::error::ProfileError::ProfileNotFound(name))?;
+
+    // Accept "owner/repo" or any existing GitHub URL
+    let parsed = if repo.contains(':') {
+        remote::parse_owner_repo(&repo)
+    } else {
+        remote::parse_owner_repo(&format!("git@github.com:{}", repo.trim()))
+    };
+    let (owner, repo_name) = parsed.ok_or_else(|| {
+        crate::error::ProfileError::InvalidInput(format!(
+            "Expected 'owner/repo' or a GitHub URL, got '{}'",
+            repo
+        ))
+    })?;
+    let path = format!("{}/{}", owner, repo_name);
+
+    if https {
+        println!("{}", profile.https_clone_url(&path));
+    } else {
+        println!("{}", profile.ssh_clone_url(&path));
+    }
+    Ok(())
+}
+
+/// Handle 'clone --all-from': clone every repository of an org through the
+/// profile's SSH host alias, with the profile's identity set in each clone
+pub fn handle_clone_all(ctx: &Context, name: String, org: String, dir: Option<PathBuf>, limit: usize) -> Result<()> {
+    let profile = ctx
+        .profile_manager()?
+        .get_profile(&name)?
+        .ok_or(crate::error::ProfileError::ProfileNotFound(name))?;
+    if !Validator::validate_username(&org) {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "Invalid GitHub organization '{}'",
+            org
+        )));
+    }
+
+    let repos = gh::list_repos(&org, limit)?;
+    if repos.is_empty() {
+        println!("No repositories found for '{}'.", org);
+        return Ok(());
+    }
+    if repos.len() >= limit {
+        println!("Warning: gh stopped at --limit {}; raise it to clone the rest.", limit);
+    }
+
+    // The host alias has to resolve before cloning through it
+    if !profile.commit_identity_only {
+        ctx.ssh_config()?.add_or_update_host(&profile)?;
+    }
+
+    let dir = dir.unwrap_or_else(|| PathBuf::from("."));
+    let name_config = format!("user.name={}", profile.username);
+    let email_config = format!("user.email={}", profile.email);
+    let (mut cloned, mut would_clone, mut skipped, mut failed) = (0, 0, 0, Vec::new());
+
+    for repo in &repos {
+        let path = format!("{}/{}", org, repo);
+        let url = if profile.commit_identity_only {
+            profile.https_clone_url(&path)
+        } else {
+            profile.ssh_clone_url(&path)
+        };
+        let target = dir.join(repo);
+        // git takes the target as a string; a lossy conversion would clone
+        // somewhere other than asked
+        let target_str = target.to_str().ok_or_else(|| {
+            crate::error::ProfileError::InvalidInput(format!(
+                "The clone directory {:?} is not valid UTF-8; pass a UTF-8 path with --dir",
+                dir
+            ))
+        })?;
+
+        if target.exists() {
+            println!("- {} (already exists)", repo);
+            skipped += 1;
+            continue;
+        }
+        if ctx.dry_run {
+            println!("[dry-run] Would clone {} into {}", url, target_str);
+            would_clone += 1;
+            continue;
+        }
 
+        // `clone -c` writes the identity into the new repository's config
+        match execute_git(&["clone", "-q", "-c", &name_config, "-c", &email_config, &url, target_str]) {
+            Ok(_) => {
+                println!("✓ {}", repo);
+                cloned += 1;
+            }
+            Err(e) => {
+                println!("✗ {}: {}", repo, e);
+                failed.push(repo.clone());
+            }
+        }
+    }
+
+    if ctx.dry_run {
+        println!(
+            "\nWould clone {}, skipped {} already present (of {} in '{}')",
+            would_clone,
+            skipped,
+            repos.len(),
+            org
+        );
+    } else {
+        println!(
+            "\nCloned {}, skipped {} already present, {} failed (of {} in '{}')",
+            cloned,
+            skipped,
+            failed.len(),
+            repos.len(),
+            org
+        );
+    }
+    if !failed.is_empty() {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "{} repositories failed to clone: {}",
+            failed.len(),
+            failed.join(", ")
+        )));
+    }
     Ok(())
 }
 
 /// Handle the 'delete' command to remove a profile
-pub fn handle_delete(name: String) -> Result<()> {
-    let mut manager = ProfileManager::new()?;
+pub fn handle_delete(ctx: &Context, name: String, yes: bool) -> Result<()> {
+    let mut manager = ctx.profile_manager()?;
 
     // Check if profile exists
     if !manager.profile_exists(&name)? {
@@ -103,11 +828,12 @@ pub fn handle_delete(name: String) -> Result<()> {
     }
 
     // Confirm deletion
-    let confirm = Confirm::new()
-        .with_prompt(format!("Are you sure you want to delete profile '{}'?", name))
-        .default(false)
-        .interact()
-        .unwrap_or(false);
+    let confirm = yes
+        || Confirm::new()
+            .with_prompt(format!("Are you sure you want to delete profile '{}'?", name))
+            .default(false)
+            .interact()
+            .unwrap_or(false);
 
     if !confirm {
         println!("Deletion cancelled.");
@@ -120,15 +846,75 @@ pub fn handle_delete(name: String) -> Result<()> {
     Ok(())
 }
 
+/// Arguments for the 'edit' command
+#[derive(Debug, Args)]
+pub struct EditArgs {
+    /// Profile name to edit interactively
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    pub name: Option<String>,
+    /// Apply one field change to every profile (see --domain to narrow it)
+    #[arg(long, requires = "field")]
+    pub all: bool,
+    /// With --all, only change profiles whose email is at this domain
+    #[arg(long, requires = "all")]
+    pub domain: Option<String>,
+    /// New email address
+    #[arg(long, group = "field", requires = "all")]
+    pub email: Option<String>,
+    /// Replace the domain of each email, keeping the local part
+    #[arg(long, group = "field", requires = "all")]
+    pub email_domain: Option<String>,
+    /// New display color, or 'none' to clear it
+    #[arg(long = "profile-color", value_name = "COLOR", group = "field", requires = "all")]
+    pub color: Option<String>,
+    /// Set the profile's description without the interactive prompts
+    /// ('' clears it)
+    #[arg(long, conflicts_with = "all")]
+    pub description: Option<String>,
+    /// Set the reminder shown on every switch without the interactive
+    /// prompts ('' clears it)
+    #[arg(long, conflicts_with = "all")]
+    pub note: Option<String>,
+    /// Set git's pull.rebase for this profile: true, false or 'none'
+    #[arg(long, value_name = "true|false|none", conflicts_with = "all")]
+    pub pull_rebase: Option<String>,
+    /// Set git's init.defaultBranch for this profile, or 'none'
+    #[arg(long, value_name = "BRANCH", conflicts_with = "all")]
+    pub default_branch: Option<String>,
+    /// Turn `AddKeysToAgent yes` in the SSH host block on or off
+    #[arg(long, value_name = "true|false", conflicts_with = "all")]
+    pub add_keys_to_agent: Option<bool>,
+    /// Turn `UseKeychain yes` in the SSH host block on or off (macOS only)
+    #[arg(long, value_name = "true|false", conflicts_with = "all")]
+    pub use_keychain: Option<bool>,
+    /// Set the SSH port for the host block (443 for ssh.github.com), or 'none'
+    #[arg(long, value_name = "PORT", conflicts_with = "all")]
+    pub port: Option<String>,
+}
+
 /// Handle the 'edit' command to update a profile
-pub fn handle_edit(name: String) -> Result<()> {
-    let mut manager = ProfileManager::new()?;
+pub fn handle_edit(ctx: &Context, args: EditArgs) -> Result<()> {
+    let Some(name) = args.name.clone() else {
+        return handle_bulk_edit(ctx, args);
+    };
+    let mut manager = ctx.profile_manager()?;
 
     // Get existing profile
     let existing = manager
         .get_profile(&name)?
         .ok_or_else(|| crate::error::ProfileError::ProfileNotFound(name.clone()))?;
 
+    let mut updated = existing.clone();
+    if apply_edit_flags(&args, &mut updated)? {
+        if manager.update_profile(&name, updated.clone())? {
+            println!("✓ Profile '{}' updated", name);
+            refresh_host_block(ctx, &updated)?;
+        } else {
+            println!("No changes to profile '{}'", name);
+        }
+        return Ok(());
+    }
+
     println!("Editing profile '{}'", name);
     println!("Press Enter to keep current value\n");
 
@@ -136,20 +922,72 @@ pub fn handle_edit(name: String) -> Result<()> {
     let username: String = Input::new()
         .with_prompt("Username")
         .default(existing.username.clone())
-        .interact_text()
-        .unwrap();
+        .interact_text()?;
 
     let email: String = Input::new()
         .with_prompt("Email")
         .default(existing.email.clone())
-        .interact_text()
-        .unwrap();
+        .interact_text()?;
 
     let ssh_key: String = Input::new()
         .with_prompt("SSH Key")
         .default(existing.ssh_key_name.clone())
-        .interact_text()
-        .unwrap();
+        .interact_text()?;
+
+    let color: String = Input::new()
+        .with_prompt("Color (or 'none')")
+        .default(existing.color.clone().unwrap_or_else(|| "none".to_string()))
+        .interact_text()?;
+
+    let signing_mode: String = Input::new()
+        .with_prompt("Commit signing (none/ssh)")
+        .default(if existing.signing_mode == SigningMode::Ssh { "ssh" } else { "none" }.to_string())
+        .interact_text()?;
+
+    let pull_rebase: String = Input::new()
+        .with_prompt("pull.rebase (true/false/none)")
+        .default(existing.pull_rebase.map_or("none".to_string(), |v| v.to_string()))
+        .interact_text()?;
+
+    let default_branch: String = Input::new()
+        .with_prompt("init.defaultBranch (or 'none')")
+        .default(existing.default_branch.clone().unwrap_or_else(|| "none".to_string()))
+        .interact_text()?;
+
+    let add_keys_to_agent = Confirm::new()
+        .with_prompt("Add 'AddKeysToAgent yes' to the SSH host block?")
+        .default(existing.add_keys_to_agent)
+        .interact()?;
+
+    let use_keychain = Confirm::new()
+        .with_prompt("Add 'UseKeychain yes' to the SSH host block (macOS only)?")
+        .default(existing.use_keychain)
+        .interact()?;
+
+    let commit_identity_only = Confirm::new()
+        .with_prompt("Only manage the commit identity when switching (leave SSH alone)?")
+        .default(existing.commit_identity_only)
+        .interact()?;
+
+    let org: String = Input::new()
+        .with_prompt("GitHub organization to route through this profile (or 'none')")
+        .default(existing.org.clone().unwrap_or_else(|| "none".to_string()))
+        .interact_text()?;
+
+    let description: String = Input::new()
+        .with_prompt("Description (or 'none')")
+        .default(existing.description.clone().unwrap_or_else(|| "none".to_string()))
+        .interact_text()?;
+
+    let note: String = Input::new()
+        .with_prompt("Reminder shown on every switch (or 'none')")
+        .default(existing.note.clone().unwrap_or_else(|| "none".to_string()))
+        .interact_text()?;
+
+    let ssh_port: String = Input::new()
+        .with_prompt("SSH port, 443 for ssh.github.com (or 'none')")
+        .default(existing.ssh_port.map_or("none".to_string(), |port| port.to_string()))
+        .interact_text()?;
 
     // Validate inputs
     if !Validator::validate_username(&username) {
@@ -170,76 +1008,1595 @@ pub fn handle_edit(name: String) -> Result<()> {
         ));
     }
 
+    let color = if color == "none" {
+        None
+    } else if Validator::validate_color(&color) {
+        Some(color)
+    } else {
+        return Err(invalid_color(&color));
+    };
+
+    let signing_mode = SigningMode::from_str(&signing_mode, true).map_err(|_| {
+        crate::error::ProfileError::InvalidInput(format!(
+            "Unknown signing mode '{}'. Use 'none' or 'ssh'",
+            signing_mode
+        ))
+    })?;
+
+    let pull_rebase = parse_pull_rebase(&pull_rebase)?;
+    let default_branch = parse_default_branch(&default_branch)?;
+
+    let org = if org == "none" {
+        None
+    } else if Validator::validate_username(&org) {
+        Some(org)
+    } else {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "Invalid GitHub organization '{}'",
+            org
+        )));
+    };
+
+    let description = if description == "none" {
+        None
+    } else {
+        Profile::clean_description(&description)?
+    };
+
+    let note = if note == "none" { None } else { Profile::clean_note(&note)? };
+
+    let ssh_port = parse_ssh_port(&ssh_port)?;
+
     // Update the profile
     let updated_profile = Profile {
         name: name.clone(),
         username,
         email,
         ssh_key_name: ssh_key,
+        color,
+        signing_mode,
+        pull_rebase,
+        default_branch,
+        add_keys_to_agent,
+        use_keychain,
+        org,
+        commit_identity_only,
+        ssh_port,
+        description,
+        note,
+        git_config: existing.git_config,
     };
 
-    manager.update_profile(&name, updated_profile)?;
-    println!("\n✓ Profile '{}' updated successfully!", name);
+    if manager.update_profile(&name, updated_profile.clone())? {
+        println!("\n✓ Profile '{}' updated successfully!", name);
+        // A new key or port has to reach the installed host block too
+        refresh_host_block(ctx, &updated_profile)?;
+    } else {
+        println!("\nNo changes to profile '{}'", name);
+    }
 
     Ok(())
 }
 
-/// Handle the 'status' command to show current profile information
-pub fn handle_status() -> Result<()> {
-    let switcher = ProfileSwitcher::new()?;
-    let status = switcher.get_current_status()?;
+/// Apply the non-interactive field flags of 'edit' to `profile`, returning
+/// false when none were given
+fn apply_edit_flags(args: &EditArgs, profile: &mut Profile) -> Result<bool> {
+    let mut given = false;
+    if let Some(text) = &args.description {
+        profile.description = Profile::clean_description(text)?;
+        given = true;
+    }
+    if let Some(text) = &args.note {
+        profile.note = Profile::clean_note(text)?;
+        given = true;
+    }
+    if let Some(value) = &args.pull_rebase {
+        profile.pull_rebase = parse_pull_rebase(value)?;
+        given = true;
+    }
+    if let Some(value) = &args.default_branch {
+        profile.default_branch = parse_default_branch(value)?;
+        given = true;
+    }
+    if let Some(value) = args.add_keys_to_agent {
+        profile.add_keys_to_agent = value;
+        given = true;
+    }
+    if let Some(value) = args.use_keychain {
+        profile.use_keychain = value;
+        given = true;
+    }
+    if let Some(value) = &args.port {
+        profile.ssh_port = parse_ssh_port(value)?;
+        given = true;
+    }
+    Ok(given)
+}
 
-    println!("Current Profile Status:\n");
+/// Parse an SSH port between 1 and 65535, 'none' meaning the default
+fn parse_ssh_port(value: &str) -> Result<Option<u16>> {
+    match value {
+        "none" => Ok(None),
+        value => Ok(Some(value.parse::<u16>().ok().filter(|port| *port != 0).ok_or_else(|| {
+            crate::error::ProfileError::InvalidInput(format!(
+                "Invalid SSH port '{}'. Use a port between 1 and 65535 or 'none'",
+                value
+            ))
+        })?)),
+    }
+}
 
-    // Global profile
-    println!("Global:");
-    if let Some(profile) = status.global {
-        println!("  Profile: {}", profile.name);
-        println!("  Username: {}", profile.username);
-        println!("  Email: {}", profile.email);
-        println!("  SSH Key: {}", profile.ssh_key_name);
+/// Rewrite the profile's SSH host block after an edit, if it is installed
+fn refresh_host_block(ctx: &Context, profile: &Profile) -> Result<()> {
+    let mut ssh_config = ctx.ssh_config()?;
+    if profile.commit_identity_only || !ssh_config.list_managed_hosts()?.contains(&profile.name) {
+        return Ok(());
+    }
+    if ssh_config.add_or_update_host(profile)? {
+        println!("✓ Updated the SSH host block for '{}'", profile.name);
+    }
+    Ok(())
+}
+
+/// Parse a pull.rebase setting: true, false or 'none' to leave it unset
+fn parse_pull_rebase(value: &str) -> Result<Option<bool>> {
+    match value {
+        "none" => Ok(None),
+        value => Ok(Some(value.parse::<bool>().map_err(|_| {
+            crate::error::ProfileError::InvalidInput(format!(
+                "Invalid pull.rebase '{}'. Use 'true', 'false' or 'none'",
+                value
+            ))
+        })?)),
+    }
+}
+
+/// Parse an init.defaultBranch setting, 'none' leaving it unset
+fn parse_default_branch(value: &str) -> Result<Option<String>> {
+    if value == "none" {
+        Ok(None)
+    } else if Validator::validate_branch_name(value) {
+        Ok(Some(value.to_string()))
     } else {
-        println!("  No profile set");
+        Err(invalid_branch(value))
     }
+}
 
-    println!();
+/// Handle 'edit --all': apply one field change across profiles
+fn handle_bulk_edit(ctx: &Context, args: EditArgs) -> Result<()> {
+    if let Some(color) = args.color.as_deref().filter(|c| *c != "none") {
+        if !Validator::validate_color(color) {
+            return Err(invalid_color(color));
+        }
+    }
 
-    // Local profile
-    println!("Local (current repository):");
-    if let Some(profile) = status.local {
-        println!("  Profile: {}", profile.name);
-        println!("  Username: {}", profile.username);
-        println!("  Email: {}", profile.email);
-        println!("  SSH Key: {}", profile.ssh_key_name);
-    } else {
-        println!("  No profile set or not in a git repository");
-    }
-
-    Ok(())
-}
-
-/// Handle the 'help' command to display usage information
-pub fn handle_help() {
-    println!("gex - Git profile switcher for managing multiple GitHub accounts\n");
-    println!("USAGE:");
-    println!("    gex <COMMAND> [OPTIONS]\n");
-    println!("COMMANDS:");
-    println!("    add       Add a new profile");
-    println!("    list      List all profiles");
-    println!("    switch    Switch to a profile");
-    println!("    delete    Delete a profile");
-    println!("    edit      Edit a profile");
-    println!("    status    Show current profile status");
-    println!("    tui       Launch interactive TUI");
-    println!("    help      Print this message\n");
-    println!("EXAMPLES:");
-    println!("    gex add personal --username john-doe --email john@personal.com --ssh-key id_rsa_personal");
-    println!("    gex list");
-    println!("    gex switch personal --global");
-    println!("    gex switch work --local");
-    println!("    gex status");
-    println!("    gex delete old-profile");
-    println!("    gex edit personal");
-    println!("    gex tui\n");
-    println!("For more information, visit: https://github.com/FriezaForce/gex");
+    let domain_suffix = args.domain.as_ref().map(|d| format!("@{}", d.to_lowercase()));
+    let matches = |p: &Profile| {
+        domain_suffix
+            .as_ref()
+            .is_none_or(|suffix| p.email.to_lowercase().ends_with(suffix))
+    };
+
+    let edit = |p: &mut Profile| {
+        if let Some(email) = &args.email {
+            p.email = email.clone();
+        }
+        if let Some(domain) = &args.email_domain {
+            let local = p.email.split('@').next().unwrap_or_default();
+            p.email = format!("{}@{}", local, domain);
+        }
+        if let Some(color) = &args.color {
+            p.color = (color != "none").then(|| color.clone());
+        }
+    };
+
+    let mut manager = ctx.profile_manager()?;
+    let changed = manager.update_matching(matches, edit)?;
+
+    if changed.is_empty() {
+        println!("No profiles changed");
+        return Ok(());
+    }
+
+    // Host blocks are keyed on the profile, so rewrite the ones already installed
+    let mut ssh_config = ctx.ssh_config()?;
+    let managed = ssh_config.list_managed_hosts()?;
+    for name in changed.iter().filter(|name| managed.contains(name)) {
+        if let Some(profile) = manager.get_profile(name)? {
+            ssh_config.add_or_update_host(&profile)?;
+        }
+    }
+
+    for name in &changed {
+        println!("  ~ {}", name);
+    }
+    println!("\n✓ Updated {} profile(s)", changed.len());
+
+    Ok(())
+}
+
+/// Config scopes 'status' can be limited to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatusScope {
+    Local,
+    Global,
+}
+
+/// Handle the 'status' command to show current profile information
+pub fn handle_status(ctx: &Context, show_remotes: bool, scope: Option<StatusScope>) -> Result<()> {
+    let switcher = ctx.switcher()?;
+    let status = switcher.get_current_status()?;
+    let mut out = ctx.out()?;
+    let show = |wanted: StatusScope| scope.is_none_or(|s| s == wanted);
+
+    writeln!(out, "Current Profile Status:\n")?;
+
+    // Global profile
+    if show(StatusScope::Global) {
+        writeln!(out, "Global:")?;
+        if let Some(path) = GitConfigManager::global_config_override() {
+            writeln!(out, "  Config: {} (GIT_CONFIG_GLOBAL)", path.display())?;
+        }
+        if let Some(profile) = status.global {
+            write_profile_summary(&mut out, &profile)?;
+        } else {
+            writeln!(out, "  No profile set")?;
+        }
+    }
+
+    if scope.is_none() {
+        writeln!(out)?;
+    }
+
+    // Local profile
+    if show(StatusScope::Local) {
+        writeln!(out, "Local (current repository):")?;
+        let has_local = status.local.is_some();
+        if let Some(profile) = status.local {
+            write_profile_summary(&mut out, &profile)?;
+        } else {
+            writeln!(out, "  No profile set or not in a git repository")?;
+        }
+        match status.local_head {
+            Some(HeadState::Unborn) if has_local => {
+                writeln!(out, "  Note: repo has no commits; local identity set but unused until the first commit")?
+            }
+            Some(HeadState::Unborn) => writeln!(out, "  Note: repo has no commits yet")?,
+            Some(HeadState::Detached) => {
+                writeln!(out, "  Note: HEAD is detached; new commits won't be on any branch")?
+            }
+            Some(HeadState::Branch) | None => {}
+        }
+    }
+
+    if show_remotes {
+        let profiles = ctx.profile_manager()?.get_all_profiles()?;
+        write_remote_status(&mut out, &profiles)?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Write which profile and key each remote's host alias maps to
+fn write_remote_status(out: &mut dyn Write, profiles: &[Profile]) -> Result<()> {
+    writeln!(out, "\nRemotes:")?;
+
+    let remotes = remote::list_remotes()?;
+    if remotes.is_empty() {
+        writeln!(out, "  No remotes (or not in a git repository)")?;
+        return Ok(());
+    }
+
+    for (name, url) in remotes {
+        let host = remote::parse_host(&url).unwrap_or_default();
+        match profiles.iter().find(|p| p.ssh_host() == host) {
+            Some(profile) => writeln!(
+                out,
+                "  {} → profile '{}' (key {})\n    {}",
+                name, profile.name, profile.ssh_key_name, url
+            )?,
+            None if host.starts_with("github.com-") => writeln!(
+                out,
+                "  {} → ⚠ '{}' matches no profile\n    {}",
+                name, host, url
+            )?,
+            None => writeln!(out, "  {} → not gex-managed\n    {}", name, url)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the indented profile fields shown by 'status'
+fn write_profile_summary(out: &mut dyn Write, profile: &Profile) -> Result<()> {
+    writeln!(out, "  Profile: {}", profile.name)?;
+    writeln!(out, "  Username: {}", profile.username)?;
+    writeln!(out, "  Email: {}", profile.email)?;
+    writeln!(out, "  SSH Key: {}", profile.ssh_key_name)?;
+    if let Some(port) = profile.ssh_port.filter(|port| *port != 22) {
+        writeln!(out, "  SSH Port: {} (via {})", port, profile.ssh_hostname())?;
+    }
+    if let Some(description) = &profile.description {
+        writeln!(out, "  Description: {}", description)?;
+    }
+    if let Some(note) = &profile.note {
+        writeln!(out, "  Note: {}", note)?;
+    }
+    Ok(())
+}
+
+/// Handle the 'ssh-sync' command to rebuild all managed SSH host blocks
+pub fn handle_ssh_sync(ctx: &Context, check: bool) -> Result<()> {
+    let manager = ctx.profile_manager()?;
+    let profiles = manager.get_all_profiles()?;
+
+    if check {
+        return check_ssh_sync(ctx, &profiles);
+    }
+
+    let mut ssh_config = ctx.ssh_config()?;
+    let report = ssh_config.sync_hosts(&profiles)?;
+
+    for name in &report.added {
+        println!("  + Added block for '{}'", name);
+    }
+    for name in &report.updated {
+        println!("  ~ Updated block for '{}'", name);
+    }
+    for name in &report.removed {
+        println!("  - Removed orphaned block for '{}'", name);
+    }
+
+    println!(
+        "\n✓ SSH config synced: {} added, {} updated, {} removed",
+        report.added.len(),
+        report.updated.len(),
+        report.removed.len()
+    );
+
+    Ok(())
+}
+
+/// Print every SSH host block that differs from what 'ssh-sync' would write
+fn check_ssh_sync(ctx: &Context, profiles: &[Profile]) -> Result<()> {
+    let drift = ctx.ssh_config()?.check_hosts(profiles)?;
+
+    for entry in &drift {
+        match &entry.state {
+            HostState::InSync => {}
+            HostState::Missing { .. } => println!("  + Missing block for '{}'", entry.name),
+            HostState::Orphaned => println!("  - Orphaned block for '{}'", entry.name),
+            HostState::Differs { installed, expected } => {
+                println!("  ~ Block for '{}' differs:", entry.name);
+                for line in installed.lines().filter(|l| !expected.lines().any(|e| e == *l)) {
+                    println!("      - {}", line);
+                }
+                for line in expected.lines().filter(|l| !installed.lines().any(|i| i == *l)) {
+                    println!("      + {}", line);
+                }
+            }
+        }
+    }
+
+    if !drift.is_empty() {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "{} SSH host block(s) out of sync; run 'gex ssh-sync' to fix",
+            drift.len()
+        )));
+    }
+
+    println!("✓ SSH config is in sync with {} profile(s)", profiles.len());
+    Ok(())
+}
+
+/// Handle the 'rename-key' command: rename `~/.ssh/<old>` (and `.pub`),
+/// point every profile using it at the new name and rewrite their host blocks
+pub fn handle_rename_key(ctx: &Context, old: String, new: String) -> Result<()> {
+    if !Validator::validate_ssh_key_name(&new) || new.ends_with(".pub") {
+        return Err(crate::error::ProfileError::InvalidInput(format!("Invalid SSH key name '{}'", new)));
+    }
+
+    let pub_name = |name: &str| format!("{}.pub", name);
+    let moves: Vec<(PathBuf, PathBuf)> = [(old.clone(), new.clone()), (pub_name(&old), pub_name(&new))]
+        .into_iter()
+        .map(|(from, to)| (SSHConfigManager::get_ssh_key_path(&from), SSHConfigManager::get_ssh_key_path(&to)))
+        .filter(|(from, _)| from.exists())
+        .collect();
+
+    if moves.is_empty() {
+        return Err(crate::error::ProfileError::SshKeyNotFound(
+            SSHConfigManager::get_ssh_key_path(&old).display().to_string(),
+        ));
+    }
+    // Check both targets before touching anything
+    for name in [&new, &pub_name(&new)] {
+        let target = SSHConfigManager::get_ssh_key_path(name);
+        if target.exists() {
+            return Err(crate::error::ProfileError::InvalidInput(format!(
+                "{} already exists; nothing was renamed",
+                target.display()
+            )));
+        }
+    }
+
+    let mut manager = ctx.profile_manager()?;
+    let affected = manager.profiles_using_key(&old)?;
+
+    if ctx.dry_run {
+        for (from, to) in &moves {
+            println!("[dry-run] Would rename {} to {}", from.display(), to.display());
+        }
+        for profile in &affected {
+            println!("[dry-run] Would point profile '{}' at '{}'", profile.name, new);
+        }
+        return Ok(());
+    }
+
+    // Rename the files, undoing the earlier renames if a later step fails
+    let undo = |done: &[(PathBuf, PathBuf)]| {
+        for (from, to) in done.iter().rev() {
+            let _ = fs::rename(to, from);
+        }
+    };
+    for (i, (from, to)) in moves.iter().enumerate() {
+        if let Err(e) = fs::rename(from, to) {
+            undo(&moves[..i]);
+            return Err(crate::error::ProfileError::PermissionDenied(format!(
+                "Failed to rename {}: {}",
+                from.display(),
+                e
+            )));
+        }
+        println!("  ✓ Renamed {} → {}", from.display(), to.display());
+    }
+
+    let renamed = match manager.rename_key(&old, &new) {
+        Ok(renamed) => renamed,
+        Err(e) => {
+            undo(&moves);
+            return Err(e);
+        }
+    };
+
+    let mut ssh_config = ctx.ssh_config()?;
+    let managed = ssh_config.list_managed_hosts()?;
+    for profile in &renamed {
+        println!("  ✓ Profile '{}' now uses '{}'", profile.name, new);
+        if managed.contains(&profile.name) {
+            ssh_config.add_or_update_host(profile)?;
+            println!("  ✓ Rewrote SSH host block for '{}'", profile.name);
+        }
+    }
+
+    println!("\n✓ Renamed key '{}' to '{}' ({} profile(s) updated)", old, new, renamed.len());
+    Ok(())
+}
+
+/// Handle the 'prune --ssh' command to remove all gex-managed host blocks
+pub fn handle_prune_ssh(ctx: &Context, yes: bool) -> Result<()> {
+    let mut ssh_config = ctx.ssh_config()?;
+    let managed = ssh_config.list_managed_hosts()?;
+
+    if managed.is_empty() {
+        println!("No gex-managed host blocks found.");
+        return Ok(());
+    }
+
+    println!("gex-managed host blocks in your SSH config:\n");
+    for name in &managed {
+        println!("  - {}", name);
+    }
+    println!();
+
+    if !yes && !ctx.dry_run {
+        let confirm = Confirm::new()
+            .with_prompt(format!("Remove {} block(s)? Your own blocks are kept", managed.len()))
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+
+        if !confirm {
+            println!("Prune cancelled.");
+            return Ok(());
+        }
+    }
+
+    let removed = ssh_config.prune_managed_hosts()?;
+    println!("✓ Removed {} gex-managed block(s) (backup saved as config.bak)", removed.len());
+
+    Ok(())
+}
+
+/// Handle the 'uninstall' command to remove gex's config, SSH blocks and git identity
+pub fn handle_uninstall(
+    ctx: &Context,
+    config: bool,
+    ssh: bool,
+    git_identity: bool,
+    yes: bool,
+) -> Result<()> {
+    let everything = !(config || ssh || git_identity);
+    let (config, ssh, git_identity) = (config || everything, ssh || everything, git_identity || everything);
+
+    // Gather everything up front so the plan is shown before anything changes.
+    // Loading profiles may create the config file, so this happens first.
+    let identity = if git_identity {
+        ctx.switcher()?.get_current_status()?.global
+    } else {
+        None
+    };
+
+    // Only gex's own files are deleted: GEX_CONFIG_DIR may point at a
+    // directory that holds other things too
+    let mut config_dirs: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+    if config {
+        for dir in [StorageService::get_config_path()?.parent().map(PathBuf::from), Some(ContextStore::new()?.root)]
+            .into_iter()
+            .flatten()
+        {
+            let owned: Vec<PathBuf> = OWNED_CONFIG_ENTRIES
+                .iter()
+                .map(|name| dir.join(name))
+                .filter(|path| path.symlink_metadata().is_ok())
+                .collect();
+            if !owned.is_empty() && !config_dirs.iter().any(|(seen, _)| *seen == dir) {
+                config_dirs.push((dir, owned));
+            }
+        }
+    }
+
+    let mut ssh_config = ctx.ssh_config()?;
+    let managed = if ssh { ssh_config.list_managed_hosts()? } else { Vec::new() };
+
+    if config_dirs.is_empty() && managed.is_empty() && identity.is_none() {
+        println!("Nothing to remove.");
+        return Ok(());
+    }
+
+    println!("gex uninstall will touch:\n");
+    for (dir, owned) in &config_dirs {
+        for path in owned {
+            println!("  • Delete {}", path.display());
+        }
+        println!("  • Remove {} if nothing else is left in it", dir.display());
+    }
+    if !managed.is_empty() {
+        println!("  • Remove {} gex-managed block(s) from {}", managed.len(), ssh_config.config_path.display());
+    }
+    if let Some(profile) = &identity {
+        println!("  • Unset global user.name/user.email (profile '{}')", profile.name);
+    }
+    println!();
+
+    let confirm = |prompt: &str| {
+        yes || ctx.dry_run
+            || Confirm::new()
+                .with_prompt(prompt)
+                .default(false)
+                .interact()
+                .unwrap_or(false)
+    };
+
+    if !managed.is_empty() && confirm("Remove gex-managed SSH host blocks?") {
+        let removed = ssh_config.prune_managed_hosts()?;
+        println!("✓ Removed {} SSH host block(s)", removed.len());
+    }
+
+    if identity.is_some() && confirm("Unset the global git identity?") {
+        let git_config = ctx.git_config();
+        git_config.unset_config(ConfigScope::Global, "user.name")?;
+        git_config.unset_config(ConfigScope::Global, "user.email")?;
+        println!("✓ Unset global git identity");
+    }
+
+    for (dir, owned) in &config_dirs {
+        if !confirm(&format!("Delete gex's files in {}?", dir.display())) {
+            continue;
+        }
+        if ctx.dry_run {
+            for path in owned {
+                println!("[dry-run] Would delete {}", path.display());
+            }
+            continue;
+        }
+
+        for path in owned {
+            if path.is_dir() && !path.is_symlink() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+        if fs::read_dir(dir)?.next().is_none() {
+            fs::remove_dir(dir)?;
+            println!("✓ Deleted {}", dir.display());
+        } else {
+            println!("✓ Deleted gex's files; kept {} since it holds other files", dir.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Files and directories gex keeps in its config directories, the only
+/// entries 'uninstall --config' deletes
+const OWNED_CONFIG_ENTRIES: [&str; 6] =
+    ["profiles.json", "profiles.json.corrupt", "history.jsonl", "backups", "active_context", "contexts"];
+
+/// Handle the 'verify' command to probe GitHub with one or all profiles' keys
+pub fn handle_verify(ctx: &Context, name: Option<String>, timeout: u64) -> Result<()> {
+    let manager = ctx.profile_manager()?;
+
+    let profiles = match name {
+        Some(name) => vec![manager
+            .get_profile(&name)?
+            .ok_or(crate::error::ProfileError::ProfileNotFound(name))?],
+        None => manager.get_all_profiles()?,
+    };
+
+    if profiles.is_empty() {
+        println!("No profiles found.");
+        return Ok(());
+    }
+
+    println!("Verifying {} profile(s)...\n", profiles.len());
+    let timeout = Duration::from_secs(timeout);
+    let results = verify::probe_all(&profiles, verify::DEFAULT_WORKERS, |profile| {
+        verify::probe_profile(profile, timeout)
+    });
+
+    let mut table = Table::new(&["PROFILE", "SSH KEY", "RESULT"]);
+    for (profile, result) in profiles.iter().zip(&results) {
+        let result = match result {
+            ProbeResult::Authenticated(login) => Cell::colored(format!("✓ authenticated as {}", login), Some("green")),
+            ProbeResult::Failed(reason) => Cell::colored(format!("✗ {}", reason), Some("red")),
+        };
+        table.add_row(vec![Cell::new(&profile.name), Cell::new(&profile.ssh_key_name), result]);
+    }
+    print!("{}", table.render(ctx.use_color()));
+
+    let failed = results.iter().filter(|r| !r.is_ok()).count();
+    println!("\n{} passed, {} failed", results.len() - failed, failed);
+
+    if failed > 0 {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "{} profile(s) failed verification",
+            failed
+        )));
+    }
+
+    Ok(())
+}
+
+/// Keep only the named profiles (all of them when `names` is empty), in
+/// stored order, failing on a name that doesn't exist
+fn select_profiles(profiles: Vec<Profile>, names: &[String]) -> Result<Vec<Profile>> {
+    if names.is_empty() {
+        return Ok(profiles);
+    }
+    if let Some(missing) = names.iter().find(|name| !profiles.iter().any(|p| &p.name == *name)) {
+        return Err(crate::error::ProfileError::ProfileNotFound(missing.clone()));
+    }
+    Ok(profiles.into_iter().filter(|p| names.contains(&p.name)).collect())
+}
+
+/// Handle the 'export' command to write profiles to a portable file, or one
+/// file per profile with `--output-dir`
+pub fn handle_export(
+    ctx: &Context,
+    file: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    encrypt: bool,
+    select: Vec<String>,
+) -> Result<()> {
+    let manager = ctx.profile_manager()?;
+    let profiles = select_profiles(manager.get_all_profiles()?, &select)?;
+    let passphrase = if encrypt { Some(prompt_passphrase(true)?) } else { None };
+
+    let seal = |contents: Vec<u8>| match &passphrase {
+        Some(passphrase) => export::encrypt(&contents, passphrase),
+        None => Ok(contents),
+    };
+    let encrypted_note = if encrypt { " (encrypted)" } else { "" };
+
+    if let Some(dir) = output_dir {
+        if !ctx.dry_run {
+            fs::create_dir_all(&dir)?;
+        }
+        for profile in &profiles {
+            let path = dir.join(format!("{}.json", profile.name));
+            let contents = seal(serde_json::to_vec_pretty(profile)?)?;
+            if ctx.dry_run {
+                println!("[dry-run] Would write {}", path.display());
+            } else {
+                fs::write(&path, &contents)?;
+                println!("  ✓ {}", path.display());
+            }
+        }
+        println!(
+            "\n✓ Exported {} profile(s) to {}{}",
+            profiles.len(),
+            dir.display(),
+            encrypted_note
+        );
+        return Ok(());
+    }
+
+    // Settings are machine-specific, so the export only carries profiles
+    let mut data = StorageData::new();
+    data.profiles = profiles;
+    let contents = seal(serde_json::to_vec_pretty(&data)?)?;
+
+    match file {
+        Some(path) if ctx.dry_run => {
+            println!(
+                "[dry-run] Would write {} profile(s) to {}",
+                data.profiles.len(),
+                path.display()
+            );
+        }
+        Some(path) => {
+            fs::write(&path, &contents)?;
+            eprintln!(
+                "✓ Exported {} profile(s) to {}{}",
+                data.profiles.len(),
+                path.display(),
+                encrypted_note
+            );
+        }
+        None => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(&contents)?;
+            writeln!(stdout)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A profile (or file) that 'import' could not accept, or accepted with a warning
+#[derive(Debug, Serialize)]
+struct ImportIssue {
+    /// Profile name, or the file name when the file didn't parse
+    source: String,
+    reason: String,
+}
+
+/// Everything one 'import' run did, as printed by `--json`
+#[derive(Debug, Default, Serialize)]
+struct ImportSummary {
+    added: Vec<String>,
+    unchanged: Vec<String>,
+    conflicts: Vec<String>,
+    invalid: Vec<ImportIssue>,
+    /// Profiles imported despite failing validation (--no-validate)
+    warnings: Vec<ImportIssue>,
+}
+
+/// Handle the 'import' command to add profiles from an export file, or
+/// from a directory of per-profile files with `--dir`
+pub fn handle_import(
+    ctx: &Context,
+    file: Option<PathBuf>,
+    dir: Option<PathBuf>,
+    decrypt: bool,
+    no_validate: bool,
+    json: bool,
+) -> Result<()> {
+    let passphrase = if decrypt { Some(prompt_passphrase(false)?) } else { None };
+    let mut valid = Vec::new();
+    let mut invalid = Vec::new();
+    let mut summary = ImportSummary::default();
+    let mut check = |profile: &Profile| -> Result<()> {
+        let warning = check_imported(profile, no_validate)?;
+        if let Some(reason) = warning {
+            summary.warnings.push(ImportIssue { source: profile.name.clone(), reason });
+        }
+        Ok(())
+    };
+
+    if let Some(dir) = dir {
+        let mut paths: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let profile = read_export(&path, passphrase.as_deref()).and_then(|contents| {
+                let profile: Profile = serde_json::from_slice(&contents).map_err(|e| {
+                    crate::error::ProfileError::InvalidInput(format!("Not a gex profile: {}", e))
+                })?;
+                check(&profile)?;
+                Ok(profile)
+            });
+
+            match profile {
+                Ok(profile) => valid.push(profile),
+                Err(e) => invalid.push(ImportIssue { source: file_name, reason: e.to_string() }),
+            }
+        }
+    } else if let Some(file) = file {
+        let contents = read_export(&file, passphrase.as_deref())?;
+        let data: StorageData = serde_json::from_slice(&contents).map_err(|e| {
+            crate::error::ProfileError::InvalidInput(format!("Not a valid gex export: {}", e))
+        })?;
+
+        for profile in data.profiles {
+            match check(&profile) {
+                Ok(()) => valid.push(profile),
+                Err(e) => invalid.push(ImportIssue { source: profile.name, reason: e.to_string() }),
+            }
+        }
+    }
+
+    let mut manager = ctx.profile_manager()?;
+    let report = manager.import_profiles(valid)?;
+    summary.added = report.added;
+    summary.unchanged = report.unchanged;
+    summary.conflicts = report.conflicts;
+    summary.invalid = invalid;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        print_import_summary(&summary);
+    }
+
+    // Fail so scripts and CI notice a bad import file
+    if !summary.invalid.is_empty() {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "{} profile(s) were rejected as invalid",
+            summary.invalid.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Print what 'import' did, one line per profile and then the totals
+fn print_import_summary(summary: &ImportSummary) {
+    for name in &summary.added {
+        println!("  ✓ Imported '{}'", name);
+    }
+    for issue in &summary.warnings {
+        println!("  ⚠ '{}' fails validation, importing anyway: {}", issue.source, issue.reason);
+    }
+    for name in &summary.unchanged {
+        println!("  - Skipped '{}' (already exists)", name);
+    }
+    for name in &summary.conflicts {
+        println!("  ✗ Skipped '{}' (a different profile with this name exists)", name);
+    }
+    for issue in &summary.invalid {
+        println!("  ✗ Rejected '{}': {}", issue.source, issue.reason);
+    }
+
+    println!("\n✓ Imported {} profile(s)", summary.added.len());
+    println!(
+        "  {} added, {} already present, {} name conflict(s), {} invalid",
+        summary.added.len(),
+        summary.unchanged.len(),
+        summary.conflicts.len(),
+        summary.invalid.len()
+    );
+    // Import only stores profiles; host blocks are written by ssh-sync or switch
+    if !summary.added.is_empty() {
+        println!("  No SSH host blocks were written; run 'gex ssh-sync' to add them.");
+    }
+}
+
+/// Validate an imported profile. With `no_validate` failures are only
+/// returned as a warning, except for the profile name, which ends up in
+/// file names and SSH host aliases and so must always be valid.
+fn check_imported(profile: &Profile, no_validate: bool) -> Result<Option<String>> {
+    if !no_validate {
+        return profile.validate().map(|()| None);
+    }
+
+    if !Validator::validate_profile_name(&profile.name) {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "Invalid profile name '{}' (not skipped by --no-validate)",
+            profile.name
+        )));
+    }
+
+    Ok(profile.validate().err().map(|e| e.to_string()))
+}
+
+/// Read an export file, decrypting it when a passphrase is given
+fn read_export(path: &Path, passphrase: Option<&str>) -> Result<Vec<u8>> {
+    let contents = fs::read(path)?;
+
+    match passphrase {
+        Some(passphrase) => export::decrypt(&contents, passphrase),
+        None if export::is_encrypted(&contents) => Err(crate::error::ProfileError::InvalidInput(
+            "This export is encrypted; re-run with --decrypt".to_string(),
+        )),
+        None => Ok(contents),
+    }
+}
+
+/// Prompt for an export passphrase, asking twice when creating one
+fn prompt_passphrase(confirm: bool) -> Result<String> {
+    let prompt = Password::new();
+    let prompt = if confirm {
+        prompt
+            .with_prompt("Export passphrase")
+            .with_confirmation("Confirm passphrase", "Passphrases don't match")
+    } else {
+        prompt.with_prompt("Import passphrase")
+    };
+
+    prompt
+        .interact()
+        .map_err(|e| crate::error::ProfileError::InvalidInput(e.to_string()))
+}
+
+/// Handle the 'whoami' command to show the identity git resolves right now
+pub fn handle_whoami() -> Result<()> {
+    println!("Effective git identity:\n");
+
+    for (label, key) in [("Name", "user.name"), ("Email", "user.email")] {
+        match GitConfigManager::get_effective_config(key)? {
+            Some((value, origin)) => println!("  {:<6} {}  ({})", format!("{}:", label), value, origin),
+            None => println!("  {:<6} (not set)", format!("{}:", label)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the 'current' command to print the active profile name
+pub fn handle_current(ctx: &Context) -> Result<()> {
+    let switcher = ctx.switcher()?;
+    let status = switcher.get_current_status()?;
+
+    // Local takes precedence, matching what git itself uses in a repository
+    let (profile, default_color) = match (status.local, status.global) {
+        (Some(profile), _) => (profile, "magenta"),
+        (None, Some(profile)) => (profile, "cyan"),
+        (None, None) => return Ok(()),
+    };
+
+    // Prompts capture stdout, so they need --color (always) to get color
+    if ctx.use_color() {
+        let name_color = profile.color.as_deref().unwrap_or(default_color);
+        println!("{}", paint(&profile.name, name_color));
+    } else {
+        println!("{}", profile.name);
+    }
+
+    Ok(())
+}
+
+/// Handle the 'which' command to list profiles using an SSH key or email
+pub fn handle_which(ctx: &Context, ssh_key: Option<String>, email: Option<String>) -> Result<()> {
+    let manager = ctx.profile_manager()?;
+
+    let (label, profiles) = match (ssh_key, email) {
+        (_, Some(email)) => {
+            let profiles = manager.profiles_with_email(&email)?;
+            (format!("email '{}'", email), profiles)
+        }
+        (Some(key), None) => {
+            let profiles = manager.profiles_using_key(&key)?;
+            (format!("SSH key '{}'", key), profiles)
+        }
+        (None, None) => {
+            return Err(crate::error::ProfileError::InvalidInput(
+                "Provide an SSH key name or --email".to_string(),
+            ))
+        }
+    };
+
+    if profiles.is_empty() {
+        println!("No profiles use {}", label);
+        return Ok(());
+    }
+
+    println!("Profiles using {}:\n", label);
+    for profile in profiles {
+        println!("  ● {} ({}, {})", profile.name, profile.username, profile.email);
+    }
+
+    Ok(())
+}
+
+/// Handle the 'doctor' command to diagnose common setup problems, and
+/// with `fix` offer to remediate the ones it can. Fails when a check
+/// failed and wasn't fixed.
+pub fn handle_doctor(ctx: &Context, fix: bool, yes: bool, json: bool) -> Result<()> {
+    let profiles = ctx.profile_manager()?.get_all_profiles()?;
+    let mut ssh_config = ctx.ssh_config()?;
+    let checks = doctor::run_checks(&profiles, &ssh_config)?;
+    let failures = checks.iter().filter(|c| c.status == CheckStatus::Fail).count();
+
+    if json {
+        let report = doctor::Report { ok: failures == 0, checks };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return doctor_result(failures);
+    }
+
+    for check in &checks {
+        let icon = match check.status {
+            CheckStatus::Ok => "✓",
+            CheckStatus::Warn => "⚠",
+            CheckStatus::Fail => "✗",
+        };
+        println!("  {} {}: {}", icon, check.name, check.detail);
+    }
+
+    let problems = checks.iter().filter(|c| c.status != CheckStatus::Ok).count();
+    if problems == 0 {
+        println!("\n✓ No problems found");
+        return Ok(());
+    }
+    println!("\n{} problem(s) found", problems);
+
+    let fixable = checks.iter().filter(|c| c.fix.is_some()).count();
+    if !fix || fixable == 0 {
+        if fixable > 0 {
+            println!("Run 'gex doctor --fix' to repair {} of them", fixable);
+        }
+        return doctor_result(failures);
+    }
+
+    println!();
+    let (mut failed, mut unresolved) = (0, failures);
+    for check in &checks {
+        let Some(fix) = &check.fix else {
+            continue;
+        };
+        if !yes && !ctx.dry_run {
+            let confirmed = Confirm::new()
+                .with_prompt(format!("{}?", fix))
+                .default(true)
+                .interact()
+                .unwrap_or(false);
+            if !confirmed {
+                println!("  Skipped: {}", fix);
+                continue;
+            }
+        }
+
+        match apply_fix(ctx, &mut ssh_config, &profiles, fix) {
+            Ok(()) => {
+                println!("✓ {}", fix);
+                if check.status == CheckStatus::Fail {
+                    unresolved -= 1;
+                }
+            }
+            Err(e) => {
+                println!("✗ {}: {}", fix, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(crate::error::ProfileError::InvalidInput(format!("{} fix(es) failed", failed)));
+    }
+    doctor_result(unresolved)
+}
+
+/// Turn the number of failed doctor checks into the command's result
+fn doctor_result(failures: usize) -> Result<()> {
+    if failures > 0 {
+        return Err(crate::error::ProfileError::InvalidInput(format!("{} check(s) failed", failures)));
+    }
+    Ok(())
+}
+
+/// Apply one of doctor's remediations
+fn apply_fix(ctx: &Context, ssh_config: &mut SSHConfigManager, profiles: &[Profile], fix: &Fix) -> Result<()> {
+    match fix {
+        Fix::CreateSshConfig(_) => {
+            // Asked for explicitly, so it overrides auto_create_ssh_config
+            ssh_config.set_auto_create(true);
+            ssh_config.ensure_ssh_config_exists()
+        }
+        Fix::SyncHosts => ssh_config.sync_hosts(profiles).map(|_| ()),
+        Fix::KeyPermissions(path) => {
+            if ctx.dry_run {
+                println!("[dry-run] Would chmod 600 {}", path.display());
+                return Ok(());
+            }
+            doctor::restrict_key(path)
+        }
+        Fix::PruneOrphans(names) => {
+            for name in names {
+                ssh_config.remove_host(name)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Handle the 'recover' command to salvage profiles from a corrupted file
+pub fn handle_recover(ctx: &Context, yes: bool) -> Result<()> {
+    let storage = ctx.storage()?;
+
+    let Some(contents) = storage.load_raw()? else {
+        println!("No profiles file found; nothing to recover.");
+        return Ok(());
+    };
+
+    if serde_json::from_str::<StorageData>(&contents).is_ok() {
+        println!("✓ The profiles file is not corrupted; nothing to recover.");
+        return Ok(());
+    }
+
+    let profiles = recover::salvage_profiles(&contents);
+    if profiles.is_empty() {
+        return Err(crate::error::ProfileError::InvalidInput(
+            "No profiles could be recovered from the file".to_string(),
+        ));
+    }
+
+    println!("Recovered {} profile(s):\n", profiles.len());
+    for profile in &profiles {
+        println!("  ● {} ({}, {})", profile.name, profile.username, profile.email);
+    }
+    println!("\nSettings could not be recovered and will be reset to defaults.\n");
+
+    if !yes && !ctx.dry_run {
+        let confirm = Confirm::new()
+            .with_prompt("Rewrite the profiles file with these profiles?")
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+
+        if !confirm {
+            println!("Recovery cancelled.");
+            return Ok(());
+        }
+    }
+
+    let backup_path = storage.backup_to("json.corrupt")?;
+    let mut data = StorageData::new();
+    data.profiles = profiles;
+    storage.save(&data)?;
+
+    println!("✓ Profiles file rebuilt (corrupted original kept at {})", backup_path.display());
+    Ok(())
+}
+
+/// Handle the 'agent' command to show which profile keys are in ssh-agent
+pub fn handle_agent(ctx: &Context) -> Result<()> {
+    let Some(loaded) = agent::loaded_fingerprints()? else {
+        println!("No ssh-agent is running (or SSH_AUTH_SOCK is not set).");
+        println!("\nStart one with: eval \"$(ssh-agent -s)\"");
+        return Ok(());
+    };
+
+    let switcher = ctx.switcher()?;
+    let profiles = ctx.profile_manager()?.get_all_profiles()?;
+    if profiles.is_empty() {
+        println!("No profiles found.");
+        return Ok(());
+    }
+
+    for profile in &profiles {
+        let (icon, state) = if agent::is_key_loaded(&profile.ssh_key_name, &loaded) {
+            ("✓", "loaded")
+        } else {
+            ("✗", "not loaded")
+        };
+        println!("  {} {} ({}): {}", icon, profile.name, profile.ssh_key_name, state);
+    }
+
+    // Offer to load the key of the profile git is using right now
+    let status = switcher.get_current_status()?;
+    let Some(active) = status.local.or(status.global) else {
+        return Ok(());
+    };
+    if agent::is_key_loaded(&active.ssh_key_name, &loaded) {
+        return Ok(());
+    }
+
+    println!();
+    if ctx.dry_run {
+        println!("[dry-run] Would run: ssh-add {}", SSHConfigManager::get_ssh_key_path(&active.ssh_key_name).display());
+        return Ok(());
+    }
+
+    let confirm = Confirm::new()
+        .with_prompt(format!("Add the key for active profile '{}' to the agent?", active.name))
+        .default(true)
+        .interact()
+        .unwrap_or(false);
+
+    if confirm {
+        agent::add_key(&active.ssh_key_name)?;
+        println!("✓ Added {} to ssh-agent", active.ssh_key_name);
+    }
+
+    Ok(())
+}
+
+/// Prompt for an email address, offering one found in a key comment
+fn prompt_email(suggested: Option<String>) -> Result<String> {
+    let missing = || {
+        crate::error::ProfileError::InvalidInput("No email given; pass --email <email>".to_string())
+    };
+
+    // Scripts get the key's email without a prompt
+    if !std::io::stdin().is_terminal() {
+        return suggested.ok_or_else(missing);
+    }
+
+    if let Some(suggested) = suggested {
+        let use_suggested = Confirm::new()
+            .with_prompt(format!("Use '{}' from the key comment as the email?", suggested))
+            .default(true)
+            .interact()
+            .unwrap_or(false);
+        if use_suggested {
+            return Ok(suggested);
+        }
+    }
+
+    Input::<String>::new()
+        .with_prompt("Email")
+        .validate_with(|input: &String| {
+            if Validator::validate_email(input) { Ok(()) } else { Err("Invalid email format") }
+        })
+        .interact_text()
+        .map_err(|_| missing())
+}
+
+/// Handle the 'init' command: a guided first-time setup
+pub fn handle_init(ctx: &Context) -> Result<()> {
+    let profiles = ctx.profile_manager()?.get_all_profiles()?;
+
+    if profiles.is_empty() {
+        println!("Welcome to gex! Let's create your first profile.\n");
+    } else {
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        println!("You already have {} profile(s): {}\n", profiles.len(), names.join(", "));
+
+        let another = Confirm::new()
+            .with_prompt("Add another profile?")
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+        if !another {
+            return Ok(());
+        }
+    }
+
+    let name: String = Input::new()
+        .with_prompt("Profile name (e.g. personal, work)")
+        .validate_with(|input: &String| -> std::result::Result<(), String> {
+            if !Validator::validate_profile_name(input) {
+                Err("Use only letters, digits, hyphens and underscores".to_string())
+            } else if profiles.iter().any(|p| &p.name == input) {
+                Err(format!("Profile '{}' already exists", input))
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+
+    let username: String = Input::new()
+        .with_prompt("GitHub username")
+        .validate_with(|input: &String| {
+            if Validator::validate_username(input) { Ok(()) } else { Err("Invalid GitHub username format") }
+        })
+        .interact_text()?;
+
+    let ssh_key: String = Input::new()
+        .with_prompt("SSH key name (in ~/.ssh)")
+        .default(keygen::default_key_name(&name))
+        .validate_with(|input: &String| {
+            if Validator::validate_ssh_key_name(input) { Ok(()) } else { Err("Invalid SSH key name") }
+        })
+        .interact_text()?;
+
+    let email = prompt_email(keygen::email_from_key(&ssh_key))?;
+    offer_key_generation(ctx, &ssh_key, &email)?;
+    let key_path = SSHConfigManager::get_ssh_key_path(&ssh_key);
+
+    println!();
+    handle_add(ctx, AddArgs {
+        name: name.clone(),
+        username,
+        email: Some(email),
+        ssh_key: Some(ssh_key.clone()),
+        color: None,
+        signing_mode: SigningMode::None,
+        pull_rebase: None,
+        default_branch: None,
+        add_keys_to_agent: false,
+        use_keychain: false,
+        org: None,
+        force: false,
+        commit_identity_only: false,
+        port: None,
+        description: None,
+        note: None,
+        git_config: Vec::new(),
+    })?;
+
+    let make_default = Confirm::new()
+        .with_prompt(format!("Use '{}' as your global git identity?", name))
+        .default(profiles.is_empty())
+        .interact()
+        .unwrap_or(false);
+
+    if make_default {
+        handle_switch(ctx, SwitchArgs {
+            name: Some(name.clone()),
+            global: true,
+            ..Default::default()
+        })?;
+    }
+
+    println!("\nNext steps:");
+    println!("  1. Add your public key to GitHub (Settings → SSH and GPG keys):");
+    println!("       cat {}.pub", key_path.display());
+    println!("  2. Check GitHub accepts it:  gex verify {}", name);
+    println!("  3. Use it in a repository:   gex switch {}", name);
+    println!("  4. Clone with the profile's host alias: git clone git@github.com-{}:owner/repo.git", name);
+
+    Ok(())
+}
+
+/// Handle the 'self-test' command
+pub fn handle_self_test() -> Result<()> {
+    println!("Running self-test in a temporary sandbox (your config is not touched)...\n");
+
+    let binary = std::env::current_exe()?;
+    let stages = selftest::run(&binary)?;
+
+    for stage in &stages {
+        match &stage.error {
+            None => println!("  ✓ {}", stage.name),
+            Some(error) => println!("  ✗ {}: {}", stage.name, error),
+        }
+    }
+
+    if let Some(failed) = stages.iter().find(|stage| stage.error.is_some()) {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "Self-test failed at stage '{}'",
+            failed.name
+        )));
+    }
+
+    println!("\n✓ All systems go");
+    Ok(())
+}
+
+fn invalid_branch(branch: &str) -> crate::error::ProfileError {
+    crate::error::ProfileError::InvalidInput(format!("Invalid branch name '{}'", branch))
+}
+
+fn invalid_color(color: &str) -> crate::error::ProfileError {
+    crate::error::ProfileError::InvalidInput(format!(
+        "Unknown color '{}'. Available colors: {}",
+        color,
+        COLOR_NAMES.join(", ")
+    ))
+}
+
+/// Handle the 'config theme' command to select the TUI color theme
+pub fn handle_config_theme(ctx: &Context, name: String) -> Result<()> {
+    if Theme::from_name(&name).is_none() {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "Unknown theme '{}'. Available themes: {}",
+            name,
+            Theme::NAMES.join(", ")
+        )));
+    }
+
+    let mut manager = ctx.profile_manager()?;
+    let mut settings = manager.get_settings()?;
+    settings.theme = name.clone();
+    manager.update_settings(settings)?;
+
+    println!("✓ TUI theme set to '{}'", name);
+    Ok(())
+}
+
+/// Handle the 'config max-profiles' command to change the profile limit
+pub fn handle_config_max_profiles(ctx: &Context, limit: usize) -> Result<()> {
+    let mut manager = ctx.profile_manager()?;
+    let count = manager.profile_count()?;
+
+    if limit < count {
+        return Err(crate::error::ProfileError::InvalidInput(format!(
+            "Limit {} is below the {} profiles already stored",
+            limit, count
+        )));
+    }
+
+    let mut settings = manager.get_settings()?;
+    settings.max_profiles = limit;
+    manager.update_settings(settings)?;
+
+    println!("✓ Maximum number of profiles set to {}", limit);
+    Ok(())
+}
+
+/// Handle the 'config get' command to print one setting
+pub fn handle_config_get(ctx: &Context, key: String) -> Result<()> {
+    let settings = ctx.profile_manager()?.get_settings()?;
+
+    let value = settings.get(&key).ok_or_else(|| {
+        crate::error::ProfileError::InvalidInput(format!(
+            "Unknown setting '{}'. Available settings: {}",
+            key,
+            Settings::KEYS.join(", ")
+        ))
+    })?;
+
+    println!("{}", value);
+    Ok(())
+}
+
+/// Handle the 'config list' command to print every setting
+pub fn handle_config_list(ctx: &Context) -> Result<()> {
+    let settings = ctx.profile_manager()?.get_settings()?;
+    let defaults = Settings::default();
+
+    for key in Settings::KEYS {
+        let value = settings.get(key).unwrap_or_default();
+        if defaults.get(key).as_ref() == Some(&value) {
+            println!("{} = {} (default)", key, value);
+        } else {
+            println!("{} = {}", key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Handle the 'config auto-create-ssh-config' command
+pub fn handle_config_auto_create_ssh_config(ctx: &Context, enabled: bool) -> Result<()> {
+    let mut manager = ctx.profile_manager()?;
+    let mut settings = manager.get_settings()?;
+    settings.auto_create_ssh_config = enabled;
+    manager.update_settings(settings)?;
+
+    if enabled {
+        println!("✓ gex will create ~/.ssh/config when it's missing");
+    } else {
+        println!("✓ gex will no longer create ~/.ssh/config; it must already exist");
+    }
+    Ok(())
+}
+
+/// Handle the 'config ssh-identities-only' command
+pub fn handle_config_ssh_identities_only(ctx: &Context, enabled: bool) -> Result<()> {
+    let mut manager = ctx.profile_manager()?;
+    let mut settings = manager.get_settings()?;
+    settings.ssh_identities_only = enabled;
+    manager.update_settings(settings)?;
+
+    if enabled {
+        println!("✓ SSH host blocks will include 'IdentitiesOnly yes'");
+    } else {
+        println!("✓ SSH host blocks will leave out 'IdentitiesOnly yes'; ssh may offer every agent key");
+    }
+    println!("  Run 'gex ssh-sync' to rewrite existing blocks.");
+    Ok(())
+}
+
+/// Handle the 'config ssh-config-section' command
+pub fn handle_config_ssh_config_section(ctx: &Context, line: Option<String>) -> Result<()> {
+    let line = line.filter(|l| !l.trim().is_empty());
+    let mut manager = ctx.profile_manager()?;
+    let mut settings = manager.get_settings()?;
+    settings.ssh_config_section = line.clone();
+    manager.update_settings(settings)?;
+
+    match line {
+        Some(line) => println!("✓ New SSH host blocks will be inserted after '{}'", line),
+        None => println!("✓ New SSH host blocks will be appended to the end of ~/.ssh/config"),
+    }
+    Ok(())
+}
+
+/// Handle 'backup create': snapshot the profiles file and managed SSH
+/// blocks into a timestamped archive
+pub fn handle_backup_create(ctx: &Context, dir: Option<PathBuf>) -> Result<()> {
+    let storage = ctx.storage()?.load()?;
+    let ssh_blocks = ctx.ssh_config()?.managed_blocks()?;
+    let dir = match dir {
+        Some(dir) => dir,
+        None => Snapshot::default_dir()?,
+    };
+
+    let profiles = storage.profiles.len();
+    let blocks = ssh_blocks.len();
+    if ctx.dry_run {
+        println!(
+            "[dry-run] Would write {} profile(s) and {} SSH host block(s) to {}",
+            profiles,
+            blocks,
+            dir.join(Snapshot::file_name()).display()
+        );
+        return Ok(());
+    }
+
+    let path = Snapshot::new(storage, ssh_blocks).write(&dir)?;
+    println!("✓ Backed up {} profile(s) and {} SSH host block(s) to {}", profiles, blocks, path.display());
+    println!("  Restore with: gex backup restore {}", path.display());
+    Ok(())
+}
+
+/// Handle 'backup restore': put back the profiles file and managed SSH
+/// blocks saved by 'backup create'
+pub fn handle_backup_restore(ctx: &Context, archive: PathBuf, yes: bool) -> Result<()> {
+    let snapshot = Snapshot::read(&archive)?;
+
+    let confirm = yes
+        || Confirm::new()
+            .with_prompt(format!(
+                "Replace all profiles and gex SSH host blocks with the backup from {}?",
+                time::format_stored(&snapshot.created_at)
+            ))
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+    if !confirm {
+        println!("Restore cancelled.");
+        return Ok(());
+    }
+
+    ctx.ssh_config()?.restore_managed_blocks(&snapshot.ssh_block_pairs())?;
+    ctx.storage()?.save(&snapshot.storage)?;
+
+    println!(
+        "✓ Restored {} profile(s) and {} SSH host block(s) from {}",
+        snapshot.storage.profiles.len(),
+        snapshot.ssh_blocks.len(),
+        archive.display()
+    );
+    Ok(())
+}
+
+/// Handle the 'context create' command to add an isolated profile set
+pub fn handle_context_create(ctx: &Context, name: String) -> Result<()> {
+    let store = ContextStore::new()?;
+
+    if ctx.dry_run {
+        println!("[dry-run] Would create context '{}' at {}", name, store.profiles_path(&name)?.display());
+        return Ok(());
+    }
+
+    store.create(&name)?;
+
+    println!("✓ Context '{}' created", name);
+    println!("\nSwitch to it with: gex context use {}", name);
+    Ok(())
+}
+
+/// Handle the 'context use' command to change the active context
+pub fn handle_context_use(ctx: &Context, name: String) -> Result<()> {
+    let store = ContextStore::new()?;
+
+    if ctx.dry_run {
+        println!("[dry-run] Would make context '{}' active", name);
+        return Ok(());
+    }
+
+    store.set_active(&name)?;
+
+    println!("✓ Now using context '{}'", name);
+    Ok(())
+}
+
+/// Handle the 'context list' command to show all contexts
+pub fn handle_context_list() -> Result<()> {
+    let store = ContextStore::new()?;
+    let active = store.active()?;
+
+    for name in store.list()? {
+        let marker = if name == active { "●" } else { " " };
+        println!("  {} {}", marker, name);
+    }
+
+    Ok(())
 }
@@ -0,0 +1,425 @@
+use crate::error::Result;
+use crate::git::config::GitConfigManager;
+use crate::git::executor::{get_git_version, is_git_installed};
+use crate::git::remote;
+use crate::profile::{Profile, SigningMode};
+use crate::ssh::agent;
+use crate::ssh::config::{HostState, SSHConfigManager};
+use crate::switcher::ssh_command_conflict;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Outcome of a single doctor check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// A single diagnostic result
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How `doctor --fix` can remediate the problem, if it can
+    #[serde(skip)]
+    pub fix: Option<Fix>,
+}
+
+/// Every check's result, as printed by `doctor --json`
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// False when any check failed; warnings don't count
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Check {
+    fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+/// A remediation `doctor --fix` can apply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// Create the missing SSH config file
+    CreateSshConfig(PathBuf),
+    /// Rewrite the managed host blocks from the profiles (ssh-sync)
+    SyncHosts,
+    /// Restrict a private key to its owner
+    KeyPermissions(PathBuf),
+    /// Remove managed host blocks that no profile uses
+    PruneOrphans(Vec<String>),
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fix::CreateSshConfig(path) => write!(f, "Create {}", path.display()),
+            Fix::SyncHosts => write!(f, "Rewrite the drifted host blocks (ssh-sync)"),
+            Fix::KeyPermissions(path) => write!(f, "chmod 600 {}", path.display()),
+            Fix::PruneOrphans(names) => write!(f, "Remove the orphaned host block(s) for: {}", names.join(", ")),
+        }
+    }
+}
+
+/// Run every check against the current environment
+pub fn run_checks(profiles: &[Profile], ssh_config: &SSHConfigManager) -> Result<Vec<Check>> {
+    let mut checks = Vec::new();
+
+    if !is_git_installed() {
+        checks.push(Check::new("git", CheckStatus::Fail, "git is not installed or not in PATH"));
+        return Ok(checks);
+    }
+    checks.push(Check::new("git", CheckStatus::Ok, get_git_version()?));
+
+    checks.push(match ssh_command_conflict()? {
+        Some(warning) => Check::new("core.sshCommand", CheckStatus::Warn, warning),
+        None => Check::new("core.sshCommand", CheckStatus::Ok, "not set"),
+    });
+
+    checks.push(global_config_check(
+        GitConfigManager::global_config_path(),
+        GitConfigManager::global_config_override().is_some(),
+    ));
+
+    checks.push(agent_check(profiles)?);
+
+    let helper = GitConfigManager::get_effective_config("credential.helper")?.map(|(value, _)| value);
+    let https_remotes: Vec<String> = remote::list_remotes()?
+        .into_iter()
+        .filter(|(_, url)| url.starts_with("https://"))
+        .map(|(name, _)| name)
+        .collect();
+    checks.push(credential_check(helper.as_deref(), &https_remotes));
+
+    checks.extend(ssh_config_checks(ssh_config, profiles)?);
+    checks.extend(key_permission_checks(profiles));
+
+    Ok(checks)
+}
+
+/// Check that the SSH config exists and that its managed host blocks match
+/// the profiles
+fn ssh_config_checks(ssh_config: &SSHConfigManager, profiles: &[Profile]) -> Result<Vec<Check>> {
+    let path = &ssh_config.config_path;
+    if !path.exists() {
+        return Ok(vec![Check::new(
+            "ssh config",
+            CheckStatus::Warn,
+            format!("{} does not exist", path.display()),
+        )
+        .with_fix(Fix::CreateSshConfig(path.clone()))]);
+    }
+
+    let mut checks = vec![Check::new("ssh config", CheckStatus::Ok, path.display().to_string())];
+
+    // Profiles that were never switched to have no block yet; that's fine
+    let (mut drifted, mut orphaned) = (Vec::new(), Vec::new());
+    for drift in ssh_config.check_hosts(profiles)? {
+        match drift.state {
+            HostState::Differs { .. } => drifted.push(drift.name),
+            HostState::Orphaned => orphaned.push(drift.name),
+            HostState::Missing { .. } | HostState::InSync => {}
+        }
+    }
+
+    if !drifted.is_empty() {
+        checks.push(
+            Check::new(
+                "ssh host blocks",
+                CheckStatus::Warn,
+                format!("out of date for: {} (run 'gex ssh-sync')", drifted.join(", ")),
+            )
+            .with_fix(Fix::SyncHosts),
+        );
+    }
+    if !orphaned.is_empty() {
+        checks.push(
+            Check::new(
+                "orphaned host blocks",
+                CheckStatus::Warn,
+                format!("no profile uses: {}", orphaned.join(", ")),
+            )
+            .with_fix(Fix::PruneOrphans(orphaned)),
+        );
+    }
+
+    Ok(checks)
+}
+
+/// Warn about private keys other users can read, which ssh refuses to use
+fn key_permission_checks(profiles: &[Profile]) -> Vec<Check> {
+    let mut paths: Vec<PathBuf> = profiles
+        .iter()
+        .map(|p| SSHConfigManager::get_ssh_key_path(&p.ssh_key_name))
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    paths.iter().filter_map(|path| loose_key_check(path)).collect()
+}
+
+/// A check for a key whose permissions allow group or other access
+fn loose_key_check(path: &Path) -> Option<Check> {
+    let mode = key_mode(path)?;
+    if mode & 0o077 == 0 {
+        return None;
+    }
+    Some(
+        Check::new(
+            "key permissions",
+            CheckStatus::Fail,
+            format!("{} is mode {:o}; ssh ignores keys others can read", path.display(), mode),
+        )
+        .with_fix(Fix::KeyPermissions(path.to_path_buf())),
+    )
+}
+
+#[cfg(unix)]
+fn key_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).ok().map(|m| m.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn key_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Restrict a private key to its owner (chmod 600)
+#[cfg(unix)]
+pub fn restrict_key(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).map_err(|e| {
+        crate::error::ProfileError::PermissionDenied(format!("Failed to chmod {}: {}", path.display(), e))
+    })
+}
+
+#[cfg(not(unix))]
+pub fn restrict_key(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Show which file global switches write to, warning when a
+/// `GIT_CONFIG_GLOBAL` override points into a missing directory
+fn global_config_check(path: Option<PathBuf>, overridden: bool) -> Check {
+    let Some(path) = path else {
+        return Check::new("global config", CheckStatus::Warn, "could not determine the home directory");
+    };
+
+    let source = if overridden { " (from GIT_CONFIG_GLOBAL)" } else { "" };
+    let missing_dir = path.parent().is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.is_dir());
+    if overridden && missing_dir {
+        return Check::new(
+            "global config",
+            CheckStatus::Warn,
+            format!("{}{}: its directory does not exist, so global switches will fail", path.display(), source),
+        );
+    }
+    Check::new("global config", CheckStatus::Ok, format!("{}{}", path.display(), source))
+}
+
+/// Warn when HTTPS remotes would authenticate through a credential helper,
+/// which serves whatever account it has stored regardless of the profile
+fn credential_check(helper: Option<&str>, https_remotes: &[String]) -> Check {
+    let Some(helper) = helper else {
+        return Check::new("credential.helper", CheckStatus::Ok, "not set");
+    };
+
+    if https_remotes.is_empty() {
+        return Check::new(
+            "credential.helper",
+            CheckStatus::Ok,
+            format!("{} (not used by SSH remotes)", helper),
+        );
+    }
+
+    Check::new(
+        "credential.helper",
+        CheckStatus::Warn,
+        format!(
+            "'{}' supplies the token for HTTPS remote(s) {}, whichever account it belongs to; \
+             gex only manages SSH keys. Switch them to SSH (see 'gex url') or set \
+             credential.https://github.com.username per repository",
+            helper,
+            https_remotes.join(", ")
+        ),
+    )
+}
+
+/// Report profiles whose key isn't loaded in the running ssh-agent
+fn agent_check(profiles: &[Profile]) -> Result<Check> {
+    let Some(loaded) = agent::loaded_fingerprints()? else {
+        return Ok(Check::new("ssh-agent", CheckStatus::Warn, "no ssh-agent is running"));
+    };
+
+    let missing: Vec<&str> = profiles
+        .iter()
+        .filter(|p| !agent::is_key_loaded(&p.ssh_key_name, &loaded))
+        .map(|p| p.name.as_str())
+        .collect();
+
+    Ok(if missing.is_empty() {
+        Check::new("ssh-agent", CheckStatus::Ok, format!("{} key(s) loaded", loaded.len()))
+    } else {
+        Check::new(
+            "ssh-agent",
+            CheckStatus::Warn,
+            format!("keys not loaded for: {} (run 'gex agent')", missing.join(", ")),
+        )
+    })
+}
+
+/// Everything that would stop a profile from being switched to
+pub fn profile_problems(profile: &Profile) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Err(e) = profile.validate() {
+        problems.push(e.to_string());
+    }
+
+    let key_path = SSHConfigManager::get_ssh_key_path(&profile.ssh_key_name);
+    if !key_path.exists() {
+        problems.push(format!("SSH key not found: {}", key_path.display()));
+    }
+
+    if profile.signing_mode == SigningMode::Ssh {
+        let pub_path = SSHConfigManager::get_ssh_key_path(&format!("{}.pub", profile.ssh_key_name));
+        if !pub_path.exists() {
+            problems.push(format!("public key for signing not found: {}", pub_path.display()));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_checks_starts_with_git() {
+        let checks = run_checks(&[], &SSHConfigManager::new().unwrap()).unwrap();
+        assert_eq!(checks[0].name, "git");
+        if is_git_installed() {
+            assert_eq!(checks[0].status, CheckStatus::Ok);
+            assert!(checks.iter().any(|c| c.name == "core.sshCommand"));
+            assert!(checks.iter().any(|c| c.name == "ssh-agent"));
+            assert!(checks.iter().any(|c| c.name == "credential.helper"));
+        }
+    }
+
+    #[test]
+    fn test_ssh_config_checks_skip_commit_identity_only() {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let temp_dir = std::env::temp_dir().join(format!("gex_doctor_test_{}", timestamp));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let mut ssh_config = SSHConfigManager::new().unwrap();
+        ssh_config.config_path = temp_dir.join("config");
+        std::fs::write(&ssh_config.config_path, "Host myserver\n  HostName example.com\n").unwrap();
+
+        let mut profile = Profile::new(
+            "idonly".to_string(),
+            "john".to_string(),
+            "john@example.com".to_string(),
+            "id_idonly".to_string(),
+        );
+        profile.commit_identity_only = true;
+
+        let checks = ssh_config_checks(&ssh_config, std::slice::from_ref(&profile)).unwrap();
+        let names: Vec<&str> = checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["ssh config"]);
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_loose_key_check() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("gex_doctor_test_{}", timestamp));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key = dir.join("id_test");
+        std::fs::write(&key, "dummy").unwrap();
+
+        std::fs::set_permissions(&key, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let check = loose_key_check(&key).unwrap();
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.detail.contains("mode 644"));
+        assert_eq!(check.fix, Some(Fix::KeyPermissions(key.clone())));
+
+        restrict_key(&key).unwrap();
+        assert!(loose_key_check(&key).is_none());
+        assert!(loose_key_check(&dir.join("missing")).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_global_config_check() {
+        let dir = std::env::temp_dir();
+        let check = global_config_check(Some(dir.join("gitconfig")), true);
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert!(check.detail.ends_with("gitconfig (from GIT_CONFIG_GLOBAL)"));
+
+        let check = global_config_check(Some(dir.join("gex_missing_dir_test").join("gitconfig")), true);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.contains("does not exist"));
+
+        assert_eq!(global_config_check(None, false).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_credential_check() {
+        assert_eq!(credential_check(None, &["origin".to_string()]).status, CheckStatus::Ok);
+        assert_eq!(credential_check(Some("osxkeychain"), &[]).status, CheckStatus::Ok);
+
+        let check = credential_check(Some("osxkeychain"), &["origin".to_string()]);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.contains("'osxkeychain'"));
+        assert!(check.detail.contains("origin"));
+    }
+
+    #[test]
+    fn test_profile_problems() {
+        let mut profile = Profile {
+            name: "broken".to_string(),
+            username: "john-doe".to_string(),
+            email: "john@example.com".to_string(),
+            ssh_key_name: "id_gex_missing_test_key".to_string(),
+            ..Default::default()
+        };
+
+        let problems = profile_problems(&profile);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("SSH key not found"));
+
+        profile.email = "not-an-email".to_string();
+        profile.signing_mode = SigningMode::Ssh;
+        assert_eq!(profile_problems(&profile).len(), 3);
+    }
+}
@@ -62,7 +62,7 @@ impl ProfileError {
                 "Git is not installed or not found in PATH\n\n💡 Suggestion: Install git from https://git-scm.com/downloads\n   After installation, restart your terminal".to_string()
             }
             ProfileError::ConfigCorrupted => {
-                "Configuration file is corrupted\n\n💡 Suggestions:\n   • Backup the config file (if needed)\n   • Delete the config file to start fresh:\n     Windows: del %USERPROFILE%\\.github-profile-switcher\\profiles.json\n     Linux/Mac: rm ~/.github-profile-switcher/profiles.json\n   • Or manually fix the JSON syntax in the config file".to_string()
+                "Configuration file is corrupted\n\n💡 Suggestions:\n   • Try salvaging your profiles with: gex recover\n   • Backup the config file (if needed)\n   • Delete the config file to start fresh:\n     Windows: del %USERPROFILE%\\.github-profile-switcher\\profiles.json\n     Linux/Mac: rm ~/.github-profile-switcher/profiles.json\n   • Or manually fix the JSON syntax in the config file".to_string()
             }
             ProfileError::PermissionDenied(path) => {
                 format!(
@@ -82,10 +82,59 @@ impl ProfileError {
         }
     }
 
+    /// Stable name of the error kind, for machine-readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProfileError::ProfileNotFound(_) => "ProfileNotFound",
+            ProfileError::ProfileExists(_) => "ProfileExists",
+            ProfileError::SshKeyNotFound(_) => "SshKeyNotFound",
+            ProfileError::NotGitRepo => "NotGitRepo",
+            ProfileError::GitNotInstalled => "GitNotInstalled",
+            ProfileError::ConfigCorrupted => "ConfigCorrupted",
+            ProfileError::PermissionDenied(_) => "PermissionDenied",
+            ProfileError::InvalidInput(_) => "InvalidInput",
+            ProfileError::Io(_) => "Io",
+            ProfileError::Json(_) => "Json",
+        }
+    }
+
+    /// Process exit code for this error. Generic failures keep exit code 1
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ProfileError::ProfileNotFound(_) => 3,
+            ProfileError::ProfileExists(_) => 4,
+            ProfileError::SshKeyNotFound(_) => 5,
+            ProfileError::NotGitRepo => 6,
+            ProfileError::GitNotInstalled => 7,
+            ProfileError::ConfigCorrupted => 8,
+            ProfileError::PermissionDenied(_) => 9,
+            ProfileError::InvalidInput(_) | ProfileError::Io(_) | ProfileError::Json(_) => 1,
+        }
+    }
+
+    /// The error as a `{"error", "message", "code"}` JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "error": self.kind(),
+            "message": self.to_string(),
+            "code": self.exit_code(),
+        })
+        .to_string()
+    }
+
     /// Check if this error should show suggestions
     pub fn should_show_suggestion(&self) -> bool {
         !matches!(self, ProfileError::Io(_) | ProfileError::Json(_))
     }
 }
 
+/// A prompt that can't be shown (e.g. without a terminal) is an IO error
+impl From<dialoguer::Error> for ProfileError {
+    fn from(error: dialoguer::Error) -> Self {
+        match error {
+            dialoguer::Error::IO(e) => ProfileError::Io(e),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ProfileError>;
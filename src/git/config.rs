@@ -1,24 +1,184 @@
 use crate::error::{ProfileError, Result};
-use crate::git::executor::execute_git;
-use crate::git::ConfigScope;
-use crate::profile::Profile;
-use std::path::Path;
+use crate::git::executor::{execute_git, execute_git_raw};
+use crate::git::{ConfigScope, HeadState};
+use crate::profile::{Profile, SigningMode};
+use crate::ssh::config::SSHConfigManager;
+use crate::utils::paths;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-pub struct GitConfigManager;
+/// Environment variable git reads to use another file as global config
+pub const GLOBAL_CONFIG_ENV: &str = "GIT_CONFIG_GLOBAL";
+
+/// Records which keys came from a profile's `git_config`, so switching to
+/// another profile can unset them
+const GIT_CONFIG_KEYS: &str = "gex.configKeys";
+
+/// Reads and writes git config through the `git` CLI
+#[derive(Debug, Default)]
+pub struct GitConfigManager {
+    /// Log intended writes instead of running them
+    dry_run: bool,
+}
+
+/// Keys `apply_profile` may write besides the org rewrite and the
+/// profile's extra git config
+const PROFILE_KEYS: [&str; 8] = [
+    "user.name",
+    "user.email",
+    "user.signingkey",
+    "gpg.format",
+    "commit.gpgsign",
+    "pull.rebase",
+    "init.defaultBranch",
+    GIT_CONFIG_KEYS,
+];
+
+/// The values a profile switch may overwrite in one scope, taken before
+/// the switch so a failed one can put them back
+#[derive(Debug)]
+pub struct ConfigSnapshot {
+    scope: ConfigScope,
+    values: Vec<(String, Vec<String>)>,
+}
+
+impl ConfigSnapshot {
+    /// Write the saved values back, removing keys that were unset before
+    pub fn restore(&self, git_config: &GitConfigManager) -> Result<()> {
+        for (key, values) in &self.values {
+            git_config.unset_config(self.scope, key)?;
+            for value in values {
+                git_config.add_config(self.scope, key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
 
 impl GitConfigManager {
+    /// Create a new GitConfigManager that writes for real
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Log intended git config writes instead of running them
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
     /// Set a git config value for the specified scope
-    pub fn set_config(scope: ConfigScope, key: &str, value: &str) -> Result<()> {
+    pub fn set_config(&self, scope: ConfigScope, key: &str, value: &str) -> Result<()> {
         let scope_flag = scope.as_flag();
+        if self.dry_run {
+            println!("[dry-run] Would run: git config {} {} \"{}\"", scope_flag, key, value);
+            return Ok(());
+        }
         execute_git(&["config", scope_flag, key, value])?;
         Ok(())
     }
 
+    /// Remove every value of a git config key from the specified scope (a
+    /// missing key is not an error)
+    pub fn unset_config(&self, scope: ConfigScope, key: &str) -> Result<()> {
+        let scope_flag = scope.as_flag();
+        if self.dry_run {
+            println!("[dry-run] Would run: git config {} --unset-all {}", scope_flag, key);
+            return Ok(());
+        }
+
+        // Only values in the scope's own file can be unset, not included ones
+        if !Self::read_values(scope, key, false)?.is_empty() {
+            execute_git(&["config", scope_flag, "--unset-all", key])?;
+        }
+        Ok(())
+    }
+
+    /// Add a value to a key that may repeat, keeping its existing values
+    fn add_config(&self, scope: ConfigScope, key: &str, value: &str) -> Result<()> {
+        let scope_flag = scope.as_flag();
+        if self.dry_run {
+            println!("[dry-run] Would run: git config {} --add {} \"{}\"", scope_flag, key, value);
+            return Ok(());
+        }
+        execute_git(&["config", scope_flag, "--add", key, value])?;
+        Ok(())
+    }
+
     /// Get a git config value for the specified scope
     pub fn get_config(scope: ConfigScope, key: &str) -> Result<Option<String>> {
+        let Some(bytes) = Self::get_config_bytes(scope, key)? else {
+            return Ok(None);
+        };
+
+        match String::from_utf8(bytes) {
+            Ok(value) => Ok(Some(value.trim().to_string())),
+            Err(e) => {
+                eprintln!(
+                    "Warning: git config {} is not valid UTF-8; invalid bytes were replaced",
+                    key
+                );
+                Ok(Some(String::from_utf8_lossy(e.as_bytes()).trim().to_string()))
+            }
+        }
+    }
+
+    /// Get a git configuration value as the exact bytes git stores. Files
+    /// pulled in with `[include]` are read too, and for a multi-valued key
+    /// the last value wins, as in git itself
+    pub fn get_config_bytes(scope: ConfigScope, key: &str) -> Result<Option<Vec<u8>>> {
         let scope_flag = scope.as_flag();
-        match execute_git(&["config", scope_flag, key]) {
-            Ok(value) => Ok(Some(value)),
+        // --null terminates the value with NUL instead of a newline, so
+        // trailing whitespace in the value survives
+        match execute_git_raw(&["config", "--null", "--includes", scope_flag, "--get", key]) {
+            Ok(mut value) => {
+                if value.last() == Some(&0) {
+                    value.pop();
+                }
+                Ok(Some(value))
+            }
+            // Key not found, or a git that refuses --get on a multi-valued key
+            Err(ProfileError::InvalidInput(_)) => Ok(Self::read_values(scope, key, true)?.pop()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get every value of a key that may repeat, in file order
+    pub fn get_config_all(scope: ConfigScope, key: &str) -> Result<Vec<String>> {
+        Ok(Self::read_values(scope, key, true)?
+            .into_iter()
+            .map(|value| String::from_utf8_lossy(&value).trim().to_string())
+            .collect())
+    }
+
+    /// Raw values of a key in one scope (`--get-all`); empty if it is unset
+    fn read_values(scope: ConfigScope, key: &str, includes: bool) -> Result<Vec<Vec<u8>>> {
+        let mut args = vec!["config", "--null", scope.as_flag(), "--get-all", key];
+        if includes {
+            args.insert(2, "--includes");
+        }
+        match execute_git_raw(&args) {
+            Ok(output) if output.is_empty() => Ok(Vec::new()),
+            // Each value is NUL-terminated
+            Ok(output) => Ok(output
+                .strip_suffix(&[0])
+                .unwrap_or(&output)
+                .split(|b| *b == 0)
+                .map(<[u8]>::to_vec)
+                .collect()),
+            Err(ProfileError::InvalidInput(_)) => Ok(Vec::new()), // Key not found
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the value git itself resolves for a key (no scope flag, so all
+    /// of git's precedence rules apply) along with the origin that supplied it
+    pub fn get_effective_config(key: &str) -> Result<Option<(String, String)>> {
+        match execute_git(&["config", "--show-origin", "--get", key]) {
+            Ok(output) => {
+                // Output format: "<origin>\t<value>"
+                let (origin, value) = output.split_once('\t').unwrap_or(("", output.as_str()));
+                Ok(Some((value.to_string(), origin.to_string())))
+            }
             Err(ProfileError::InvalidInput(_)) => Ok(None), // Key not found
             Err(e) => Err(e),
         }
@@ -26,7 +186,61 @@ impl GitConfigManager {
 
     /// Check if the current directory is a git repository
     pub fn is_git_repository() -> Result<bool> {
-        Ok(Path::new(".git").exists())
+        // Ask git rather than looking for `.git`, so subdirectories work and
+        // worktree/submodule `.git` files resolve to their real git dir
+        match execute_git(&["rev-parse", "--is-inside-work-tree"]) {
+            Ok(output) => Ok(output.trim() == "true"),
+            Err(ProfileError::InvalidInput(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Check if the current directory is inside a linked worktree
+    /// (created with `git worktree add`) rather than the main working tree
+    pub fn is_linked_worktree() -> Result<bool> {
+        let output = match execute_git(&["rev-parse", "--git-dir", "--git-common-dir"]) {
+            Ok(output) => output,
+            Err(ProfileError::InvalidInput(_)) => return Ok(false), // Not a repository
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = output.lines();
+        let (Some(git_dir), Some(common_dir)) = (lines.next(), lines.next()) else {
+            return Ok(false);
+        };
+
+        // git prints these relative to the current directory in the main
+        // working tree but absolute inside a linked worktree
+        Ok(fs::canonicalize(git_dir)? != fs::canonicalize(common_dir)?)
+    }
+
+    /// Report what HEAD points at, or None outside a repository
+    pub fn head_state() -> Result<Option<HeadState>> {
+        if !Self::is_git_repository()? {
+            return Ok(None);
+        }
+
+        // symbolic-ref fails when HEAD is detached; rev-parse fails when
+        // the branch it names has no commits yet
+        let state = match execute_git(&["symbolic-ref", "-q", "HEAD"]) {
+            Err(ProfileError::InvalidInput(_)) => HeadState::Detached,
+            Err(e) => return Err(e),
+            Ok(_) => match execute_git(&["rev-parse", "-q", "--verify", "HEAD"]) {
+                Ok(_) => HeadState::Branch,
+                Err(ProfileError::InvalidInput(_)) => HeadState::Unborn,
+                Err(e) => return Err(e),
+            },
+        };
+        Ok(Some(state))
+    }
+
+    /// Enable `extensions.worktreeConfig` so git accepts `--worktree` writes
+    fn enable_worktree_config(&self) -> Result<()> {
+        let enabled = Self::get_config(ConfigScope::Local, "extensions.worktreeConfig")?;
+        if enabled.as_deref() != Some("true") {
+            self.set_config(ConfigScope::Local, "extensions.worktreeConfig", "true")?;
+        }
+        Ok(())
     }
 
     /// Get the current profile information from git config
@@ -40,27 +254,228 @@ impl GitConfigManager {
         }
     }
 
+    /// Read back user.name/user.email from the scope a profile was just
+    /// applied to, failing if git doesn't report the profile's values
+    pub fn verify_profile(&self, profile: &Profile, scope: ConfigScope) -> Result<()> {
+        // Nothing was written in dry-run mode
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let (username, email) = Self::get_current_profile(scope)?.unwrap_or_default();
+        if username != profile.username || email != profile.email {
+            return Err(ProfileError::InvalidInput(format!(
+                "git {} config reports '{} <{}>' after switching, expected '{} <{}>'",
+                scope, username, email, profile.username, profile.email
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Apply a profile's git configuration
-    pub fn apply_profile(profile: &Profile, scope: ConfigScope) -> Result<()> {
-        // Check if we're in a git repo for local scope
-        if scope == ConfigScope::Local && !Self::is_git_repository()? {
+    pub fn apply_profile(&self, profile: &Profile, scope: ConfigScope) -> Result<()> {
+        // Check if we're in a git repo for local and worktree scope
+        let needs_repo = matches!(scope, ConfigScope::Local | ConfigScope::Worktree);
+        if needs_repo && !Self::is_git_repository()? {
             return Err(ProfileError::NotGitRepo);
         }
 
+        let signing_key = match profile.signing_mode {
+            SigningMode::Ssh => {
+                let pub_key = format!("{}.pub", profile.ssh_key_name);
+                let pub_path = SSHConfigManager::get_ssh_key_path(&pub_key);
+                if !pub_path.exists() {
+                    return Err(ProfileError::SshKeyNotFound(
+                        pub_path.to_string_lossy().to_string(),
+                    ));
+                }
+                Some(pub_path)
+            }
+            SigningMode::None => None,
+        };
+
+        if scope == ConfigScope::Worktree {
+            self.enable_worktree_config()?;
+        }
+
+        self.apply_signing(scope, signing_key.as_deref())?;
+
         // Set user.name
-        Self::set_config(scope, "user.name", &profile.username)?;
+        self.set_config(scope, "user.name", &profile.username)?;
 
         // Set user.email
-        Self::set_config(scope, "user.email", &profile.email)?;
+        self.set_config(scope, "user.email", &profile.email)?;
+
+        self.apply_preferences(profile, scope)?;
+        self.apply_org_rewrite(profile, scope)?;
+        self.apply_git_config(profile, scope)?;
+
+        Ok(())
+    }
+
+    /// Record every key applying `profile` to `scope` could change, as
+    /// stored in the scope's own file
+    pub fn snapshot(profile: &Profile, scope: ConfigScope) -> Result<ConfigSnapshot> {
+        let mut keys: Vec<String> = PROFILE_KEYS.iter().map(|key| key.to_string()).collect();
+        if let Some(previous) = Self::get_config(scope, GIT_CONFIG_KEYS)? {
+            keys.extend(previous.split_whitespace().map(str::to_string));
+        }
+        keys.extend(profile.git_config.keys().cloned());
+        keys.extend(Self::org_rewrite_keys(profile, scope)?);
+        keys.extend(profile.org_rewrite().map(|(key, _)| key));
+        keys.sort_by_key(|key| key.to_ascii_lowercase());
+        keys.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+        let mut values = Vec::new();
+        for key in keys {
+            let current = Self::read_values(scope, &key, false)?
+                .iter()
+                .map(|value| String::from_utf8_lossy(value).into_owned())
+                .collect();
+            values.push((key, current));
+        }
+        Ok(ConfigSnapshot { scope, values })
+    }
+
+    /// Set or unset the optional per-profile git preferences
+    fn apply_preferences(&self, profile: &Profile, scope: ConfigScope) -> Result<()> {
+        let preferences = [
+            ("pull.rebase", profile.pull_rebase.map(|v| v.to_string())),
+            ("init.defaultBranch", profile.default_branch.clone()),
+        ];
+
+        for (key, value) in preferences {
+            match value {
+                Some(value) => self.set_config(scope, key, &value)?,
+                None => self.unset_config(scope, key)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the profile's extra git config entries, unsetting those a
+    /// previously applied profile set and this one doesn't
+    fn apply_git_config(&self, profile: &Profile, scope: ConfigScope) -> Result<()> {
+        let previous = Self::get_config(scope, GIT_CONFIG_KEYS)?.unwrap_or_default();
+        for key in previous.split_whitespace() {
+            if !profile.git_config.contains_key(key) {
+                self.unset_config(scope, key)?;
+            }
+        }
+
+        for (key, value) in &profile.git_config {
+            self.set_config(scope, key, value)?;
+        }
+
+        if profile.git_config.is_empty() {
+            self.unset_config(scope, GIT_CONFIG_KEYS)
+        } else {
+            let keys: Vec<&str> = profile.git_config.keys().map(String::as_str).collect();
+            self.set_config(scope, GIT_CONFIG_KEYS, &keys.join(" "))
+        }
+    }
+
+    /// Point the profile's org HTTPS URLs at its host alias, dropping any
+    /// rewrite left over from a previous org of the same profile. An
+    /// `insteadOf` key may hold several values; other prefixes the user
+    /// added to the current one are kept
+    fn apply_org_rewrite(&self, profile: &Profile, scope: ConfigScope) -> Result<()> {
+        let rewrite = profile.org_rewrite();
+        for key in Self::org_rewrite_keys(profile, scope)? {
+            let current = rewrite.as_ref().is_some_and(|(k, _)| k.eq_ignore_ascii_case(&key));
+            if !current {
+                self.unset_config(scope, &key)?;
+            }
+        }
+
+        if let Some((key, value)) = rewrite {
+            if !Self::get_config_all(scope, &key)?.contains(&value) {
+                self.add_config(scope, &key, &value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `insteadOf` keys in the scope that point at the profile's host alias
+    fn org_rewrite_keys(profile: &Profile, scope: ConfigScope) -> Result<Vec<String>> {
+        let pattern = format!(
+            "^url\\.git@{}:.*\\.insteadof$",
+            profile.ssh_host().replace('.', "\\.")
+        );
+        let mut keys: Vec<String> =
+            match execute_git(&["config", scope.as_flag(), "--name-only", "--get-regexp", &pattern]) {
+                Ok(keys) => keys.lines().map(str::to_string).collect(),
+                Err(ProfileError::InvalidInput(_)) => Vec::new(), // No matches
+                Err(e) => return Err(e),
+            };
+        // A multi-valued key is listed once per value
+        keys.dedup();
+        Ok(keys)
+    }
+
+    /// The global config file set through `GIT_CONFIG_GLOBAL`, if any
+    pub fn global_config_override() -> Option<PathBuf> {
+        std::env::var_os(GLOBAL_CONFIG_ENV)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    }
 
+    /// The file `git config --global` reads and writes: `GIT_CONFIG_GLOBAL`
+    /// when set, otherwise git's own default under `$HOME`
+    pub fn global_config_path() -> Option<PathBuf> {
+        if let Some(path) = Self::global_config_override() {
+            return Some(path);
+        }
+        // git resolves these from HOME, not GEX_HOME
+        let home = std::env::var_os("HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .or_else(dirs::home_dir)?;
+        let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"));
+        Some(default_global_config(&home, &xdg_config))
+    }
+
+    /// Configure SSH commit signing with the given public key, or remove a
+    /// previous SSH signing setup when `signing_key` is None
+    fn apply_signing(&self, scope: ConfigScope, signing_key: Option<&Path>) -> Result<()> {
+        match signing_key {
+            Some(path) => {
+                self.set_config(scope, "gpg.format", "ssh")?;
+                self.set_config(scope, "user.signingkey", paths::require_utf8(path, "signing key")?)?;
+                self.set_config(scope, "commit.gpgsign", "true")?;
+            }
+            // Only undo what SSH signing set, leaving any GPG setup alone
+            None if Self::get_config(scope, "gpg.format")?.as_deref() == Some("ssh") => {
+                self.unset_config(scope, "gpg.format")?;
+                self.unset_config(scope, "user.signingkey")?;
+                self.unset_config(scope, "commit.gpgsign")?;
+            }
+            None => {}
+        }
         Ok(())
     }
 }
 
+/// git's default global config: `~/.gitconfig`, unless only the XDG
+/// `git/config` exists
+fn default_global_config(home: &Path, xdg_config: &Path) -> PathBuf {
+    let gitconfig = home.join(".gitconfig");
+    let xdg = xdg_config.join("git").join("config");
+    if !gitconfig.exists() && xdg.exists() {
+        return xdg;
+    }
+    gitconfig
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::git::executor::is_git_installed;
+    use crate::utils::testing::lock_cwd;
     use std::fs;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -92,7 +507,7 @@ mod tests {
         }
 
         // Set a test config value
-        let result = GitConfigManager::set_config(
+        let result = GitConfigManager::new().set_config(
             ConfigScope::Global,
             "gex.test.value",
             "test123",
@@ -122,29 +537,372 @@ mod tests {
         assert_eq!(value.unwrap(), None);
     }
 
+    #[test]
+    fn test_get_effective_config() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        GitConfigManager::new().set_config(ConfigScope::Local, "user.email", "local@example.com")
+            .unwrap();
+
+        // Local config wins over anything set globally
+        let (value, origin) = GitConfigManager::get_effective_config("user.email")
+            .unwrap()
+            .unwrap();
+        assert_eq!(value, "local@example.com");
+        assert!(origin.starts_with("file:"));
+        assert!(origin.ends_with(".git/config"));
+
+        let missing = GitConfigManager::get_effective_config("gex.nonexistent.key").unwrap();
+        assert_eq!(missing, None);
+
+        // Cleanup
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_get_config_non_utf8() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        // Latin-1 "café" is not valid UTF-8
+        let mut config = fs::read(".git/config").unwrap();
+        config.extend_from_slice(b"[gex]\n\tname = caf\xe9\n");
+        fs::write(".git/config", config).unwrap();
+
+        let bytes = GitConfigManager::get_config_bytes(ConfigScope::Local, "gex.name").unwrap();
+        assert_eq!(bytes, Some(b"caf\xe9".to_vec()));
+
+        let value = GitConfigManager::get_config(ConfigScope::Local, "gex.name").unwrap();
+        assert_eq!(value, Some("caf\u{FFFD}".to_string()));
+
+        assert_eq!(GitConfigManager::get_config_bytes(ConfigScope::Local, "gex.missing").unwrap(), None);
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_get_config_multi_value_and_includes() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        execute_git(&["config", "--local", "--add", "gex.multi", "first"]).unwrap();
+        execute_git(&["config", "--local", "--add", "gex.multi", "second"]).unwrap();
+        assert_eq!(
+            GitConfigManager::get_config(ConfigScope::Local, "gex.multi").unwrap(),
+            Some("second".to_string())
+        );
+        assert_eq!(
+            GitConfigManager::get_config_all(ConfigScope::Local, "gex.multi").unwrap(),
+            vec!["first".to_string(), "second".to_string()]
+        );
+        assert!(GitConfigManager::get_config_all(ConfigScope::Local, "gex.missing").unwrap().is_empty());
+
+        // Values from an included file are read, but unset leaves them alone
+        fs::write(".git/extra.inc", "[gex]\n\tincluded = yes\n").unwrap();
+        execute_git(&["config", "--local", "include.path", "extra.inc"]).unwrap();
+        assert_eq!(
+            GitConfigManager::get_config(ConfigScope::Local, "gex.included").unwrap(),
+            Some("yes".to_string())
+        );
+        GitConfigManager::new().unset_config(ConfigScope::Local, "gex.included").unwrap();
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
     #[test]
     fn test_is_git_repository() {
         if !is_git_installed() {
             return;
         }
 
+        let _guard = lock_cwd();
         let original_dir = std::env::current_dir().unwrap();
         let temp_dir = create_temp_git_repo();
 
         // Should be true in git repo
         assert!(GitConfigManager::is_git_repository().unwrap());
 
+        // ...and in any subdirectory of it
+        let sub_dir = temp_dir.join("src").join("nested");
+        fs::create_dir_all(&sub_dir).unwrap();
+        std::env::set_current_dir(&sub_dir).unwrap();
+        assert!(GitConfigManager::is_git_repository().unwrap());
+
+        // Cleanup
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_head_state() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        // Fresh repository: the branch has no commits yet
+        assert_eq!(GitConfigManager::head_state().unwrap(), Some(HeadState::Unborn));
+
+        execute_git(&["-c", "user.name=t", "-c", "user.email=t@example.com", "commit", "-q", "--allow-empty", "-m", "init"]).unwrap();
+        assert_eq!(GitConfigManager::head_state().unwrap(), Some(HeadState::Branch));
+
+        execute_git(&["checkout", "-q", "--detach"]).unwrap();
+        assert_eq!(GitConfigManager::head_state().unwrap(), Some(HeadState::Detached));
+
+        // Outside any repository
+        std::env::set_current_dir(std::env::temp_dir()).unwrap();
+        if !GitConfigManager::is_git_repository().unwrap() {
+            assert_eq!(GitConfigManager::head_state().unwrap(), None);
+        }
+
         // Cleanup
         std::env::set_current_dir(&original_dir).unwrap();
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_unset_config() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        GitConfigManager::new().set_config(ConfigScope::Local, "user.name", "testuser").unwrap();
+        GitConfigManager::new().unset_config(ConfigScope::Local, "user.name").unwrap();
+        assert_eq!(GitConfigManager::get_config(ConfigScope::Local, "user.name").unwrap(), None);
+
+        // Unsetting a missing key is fine
+        GitConfigManager::new().unset_config(ConfigScope::Local, "user.name").unwrap();
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_apply_signing() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+        let pub_path = temp_dir.join("id_test.pub");
+
+        GitConfigManager::new().apply_signing(ConfigScope::Local, Some(&pub_path)).unwrap();
+        let get = |key| GitConfigManager::get_config(ConfigScope::Local, key).unwrap();
+        assert_eq!(get("gpg.format"), Some("ssh".to_string()));
+        assert_eq!(get("user.signingkey"), Some(pub_path.to_string_lossy().to_string()));
+        assert_eq!(get("commit.gpgsign"), Some("true".to_string()));
+
+        // Switching to a non-signing profile removes the SSH signing keys
+        GitConfigManager::new().apply_signing(ConfigScope::Local, None).unwrap();
+        assert_eq!(get("gpg.format"), None);
+        assert_eq!(get("user.signingkey"), None);
+        assert_eq!(get("commit.gpgsign"), None);
+
+        // A GPG signing setup is left untouched
+        GitConfigManager::new().set_config(ConfigScope::Local, "user.signingkey", "ABCD1234").unwrap();
+        GitConfigManager::new().apply_signing(ConfigScope::Local, None).unwrap();
+        assert_eq!(get("user.signingkey"), Some("ABCD1234".to_string()));
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_verify_profile() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        let profile = Profile {
+            name: "test".to_string(),
+            username: "john-doe".to_string(),
+            email: "john@example.com".to_string(),
+            ssh_key_name: "id_rsa".to_string(),
+            ..Default::default()
+        };
+        assert!(GitConfigManager::new().verify_profile(&profile, ConfigScope::Local).is_err());
+
+        GitConfigManager::new().apply_profile(&profile, ConfigScope::Local).unwrap();
+        assert!(GitConfigManager::new().verify_profile(&profile, ConfigScope::Local).is_ok());
+
+        GitConfigManager::new().set_config(ConfigScope::Local, "user.email", "other@example.com").unwrap();
+        assert!(GitConfigManager::new().verify_profile(&profile, ConfigScope::Local).is_err());
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_apply_preferences() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        let mut profile = Profile {
+            pull_rebase: Some(true),
+            default_branch: Some("trunk".to_string()),
+            ..Default::default()
+        };
+        GitConfigManager::new().apply_preferences(&profile, ConfigScope::Local).unwrap();
+        let get = |key| GitConfigManager::get_config(ConfigScope::Local, key).unwrap();
+        assert_eq!(get("pull.rebase"), Some("true".to_string()));
+        assert_eq!(get("init.defaultBranch"), Some("trunk".to_string()));
+
+        // Leaving them None unsets them again
+        profile.pull_rebase = None;
+        profile.default_branch = None;
+        GitConfigManager::new().apply_preferences(&profile, ConfigScope::Local).unwrap();
+        assert_eq!(get("pull.rebase"), None);
+        assert_eq!(get("init.defaultBranch"), None);
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_apply_git_config() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        let mut profile = Profile::default();
+        for entry in ["pull.rebase=true", "core.autocrlf=input", "alias.lg=log --oneline"] {
+            let (key, value) = Profile::parse_git_config(entry).unwrap();
+            profile.git_config.insert(key, value);
+        }
+        GitConfigManager::new().apply_git_config(&profile, ConfigScope::Local).unwrap();
+        let get = |key| GitConfigManager::get_config(ConfigScope::Local, key).unwrap();
+        assert_eq!(get("pull.rebase"), Some("true".to_string()));
+        assert_eq!(get("core.autocrlf"), Some("input".to_string()));
+        assert_eq!(get("alias.lg"), Some("log --oneline".to_string()));
+
+        // A profile without an entry unsets what the previous one set
+        profile.git_config.remove("core.autocrlf");
+        profile.git_config.remove("alias.lg");
+        GitConfigManager::new().apply_git_config(&profile, ConfigScope::Local).unwrap();
+        assert_eq!(get("core.autocrlf"), None);
+        assert_eq!(get("alias.lg"), None);
+        assert_eq!(get("pull.rebase"), Some("true".to_string()));
+
+        profile.git_config.clear();
+        GitConfigManager::new().apply_git_config(&profile, ConfigScope::Local).unwrap();
+        assert_eq!(get("pull.rebase"), None);
+        assert_eq!(get(GIT_CONFIG_KEYS), None);
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_apply_org_rewrite() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+
+        let mut profile = Profile {
+            name: "work".to_string(),
+            org: Some("acme".to_string()),
+            ..Default::default()
+        };
+        GitConfigManager::new().apply_org_rewrite(&profile, ConfigScope::Local).unwrap();
+        // Applying twice must not add a second value
+        GitConfigManager::new().apply_org_rewrite(&profile, ConfigScope::Local).unwrap();
+        let get = |key| GitConfigManager::get_config(ConfigScope::Local, key).unwrap();
+        assert_eq!(
+            get("url.git@github.com-work:acme/.insteadOf"),
+            Some("https://github.com/acme/".to_string())
+        );
+
+        // Changing the org replaces the old rewrite
+        profile.org = Some("globex".to_string());
+        GitConfigManager::new().apply_org_rewrite(&profile, ConfigScope::Local).unwrap();
+        assert_eq!(get("url.git@github.com-work:acme/.insteadOf"), None);
+        assert!(get("url.git@github.com-work:globex/.insteadOf").is_some());
+
+        // A second prefix for the same org survives a re-apply, and both
+        // go once the org is dropped
+        let key = "url.git@github.com-work:globex/.insteadOf";
+        execute_git(&["config", "--local", "--add", key, "gh:globex/"]).unwrap();
+        GitConfigManager::new().apply_org_rewrite(&profile, ConfigScope::Local).unwrap();
+        assert_eq!(
+            GitConfigManager::get_config_all(ConfigScope::Local, key).unwrap(),
+            vec!["https://github.com/globex/".to_string(), "gh:globex/".to_string()]
+        );
+
+        profile.org = None;
+        GitConfigManager::new().apply_org_rewrite(&profile, ConfigScope::Local).unwrap();
+        assert_eq!(get(key), None);
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_apply_profile_ssh_signing_requires_pub_key() {
+        let profile = Profile {
+            name: "signer".to_string(),
+            username: "signer".to_string(),
+            email: "signer@example.com".to_string(),
+            ssh_key_name: "gex_test_key_that_does_not_exist".to_string(),
+            signing_mode: SigningMode::Ssh,
+            ..Default::default()
+        };
+
+        let result = GitConfigManager::new().apply_profile(&profile, ConfigScope::Global);
+        assert!(matches!(result, Err(ProfileError::SshKeyNotFound(_))));
+    }
+
     #[test]
     fn test_get_current_profile() {
         if !is_git_installed() {
             return;
         }
 
+        let _guard = lock_cwd();
         let original_dir = std::env::current_dir().unwrap();
         let temp_dir = create_temp_git_repo();
 
@@ -152,8 +910,8 @@ mod tests {
         std::env::set_current_dir(&temp_dir).unwrap();
 
         // Set some config
-        GitConfigManager::set_config(ConfigScope::Local, "user.name", "testuser").unwrap();
-        GitConfigManager::set_config(ConfigScope::Local, "user.email", "test@example.com")
+        GitConfigManager::new().set_config(ConfigScope::Local, "user.name", "testuser").unwrap();
+        GitConfigManager::new().set_config(ConfigScope::Local, "user.email", "test@example.com")
             .unwrap();
 
         // Get current profile
@@ -175,6 +933,7 @@ mod tests {
             return;
         }
 
+        let _guard = lock_cwd();
         let original_dir = std::env::current_dir().unwrap();
         let temp_dir = create_temp_git_repo();
 
@@ -186,9 +945,10 @@ mod tests {
             username: "john-doe".to_string(),
             email: "john@example.com".to_string(),
             ssh_key_name: "id_rsa".to_string(),
+            ..Default::default()
         };
 
-        let result = GitConfigManager::apply_profile(&profile, ConfigScope::Local);
+        let result = GitConfigManager::new().apply_profile(&profile, ConfigScope::Local);
         assert!(result.is_ok());
 
         // Verify the config was set
@@ -203,12 +963,127 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_apply_profile_dry_run() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let temp_dir = create_temp_git_repo();
+        std::env::set_current_dir(&temp_dir).unwrap();
+
+        let config_path = temp_dir.join(".git").join("config");
+        let before = fs::read_to_string(&config_path).unwrap();
+
+        let mut profile = Profile {
+            name: "test".to_string(),
+            username: "john-doe".to_string(),
+            email: "john@example.com".to_string(),
+            ssh_key_name: "id_rsa".to_string(),
+            pull_rebase: Some(true),
+            org: Some("acme".to_string()),
+            ..Default::default()
+        };
+        profile.git_config.insert("core.autocrlf".to_string(), "input".to_string());
+
+        let mut git_config = GitConfigManager::new();
+        git_config.set_dry_run(true);
+        git_config.apply_profile(&profile, ConfigScope::Local).unwrap();
+        git_config.verify_profile(&profile, ConfigScope::Local).unwrap();
+
+        // Only this manager is in dry-run mode; nothing reached the repo
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), before);
+        assert!(!GitConfigManager::new().dry_run);
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_apply_profile_in_worktree() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let main_repo = create_temp_git_repo();
+        execute_git(&[
+            "-c",
+            "user.name=init",
+            "-c",
+            "user.email=init@example.com",
+            "commit",
+            "--allow-empty",
+            "-m",
+            "initial",
+        ])
+        .unwrap();
+
+        let worktree_dir = main_repo.with_file_name(format!(
+            "{}_wt",
+            main_repo.file_name().unwrap().to_string_lossy()
+        ));
+        execute_git(&["worktree", "add", worktree_dir.to_str().unwrap()]).unwrap();
+
+        assert!(!GitConfigManager::is_linked_worktree().unwrap());
+        std::env::set_current_dir(&worktree_dir).unwrap();
+        assert!(GitConfigManager::is_git_repository().unwrap());
+        assert!(GitConfigManager::is_linked_worktree().unwrap());
+
+        let profile = Profile {
+            name: "test".to_string(),
+            username: "john-doe".to_string(),
+            email: "john@example.com".to_string(),
+            ssh_key_name: "id_rsa".to_string(),
+            ..Default::default()
+        };
+
+        // Worktree scope only lands in this worktree's config
+        GitConfigManager::new().apply_profile(&profile, ConfigScope::Worktree).unwrap();
+        let email = GitConfigManager::get_config(ConfigScope::Worktree, "user.email").unwrap();
+        assert_eq!(email, Some("john@example.com".to_string()));
+        let shared = GitConfigManager::get_config(ConfigScope::Local, "user.email").unwrap();
+        assert_eq!(shared, None);
+
+        // `.git` is a file here; the write must land in the worktree's own git dir
+        assert!(worktree_dir.join(".git").is_file());
+        let git_dir = execute_git(&["rev-parse", "--absolute-git-dir"]).unwrap();
+        let worktree_config =
+            fs::read_to_string(Path::new(git_dir.trim()).join("config.worktree")).unwrap();
+        assert!(worktree_config.contains("john@example.com"));
+
+        // Subdirectories of the worktree resolve to the same config
+        let sub_dir = worktree_dir.join("nested");
+        fs::create_dir_all(&sub_dir).unwrap();
+        std::env::set_current_dir(&sub_dir).unwrap();
+        assert!(GitConfigManager::is_git_repository().unwrap());
+        let email = GitConfigManager::get_config(ConfigScope::Worktree, "user.email").unwrap();
+        assert_eq!(email, Some("john@example.com".to_string()));
+        std::env::set_current_dir(&worktree_dir).unwrap();
+
+        // Local scope is shared with the main repository
+        GitConfigManager::new().set_config(ConfigScope::Local, "user.email", "main@example.com")
+            .unwrap();
+        std::env::set_current_dir(&main_repo).unwrap();
+        let email = GitConfigManager::get_config(ConfigScope::Local, "user.email").unwrap();
+        assert_eq!(email, Some("main@example.com".to_string()));
+
+        // Cleanup
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&main_repo);
+        cleanup_temp_dir(&worktree_dir);
+    }
+
     #[test]
     fn test_apply_profile_local_not_git_repo() {
         if !is_git_installed() {
             return;
         }
 
+        let _guard = lock_cwd();
         let original_dir = std::env::current_dir().unwrap();
 
         // Create temp dir without git
@@ -225,9 +1100,10 @@ mod tests {
             username: "john-doe".to_string(),
             email: "john@example.com".to_string(),
             ssh_key_name: "id_rsa".to_string(),
+            ..Default::default()
         };
 
-        let result = GitConfigManager::apply_profile(&profile, ConfigScope::Local);
+        let result = GitConfigManager::new().apply_profile(&profile, ConfigScope::Local);
         assert!(result.is_err());
 
         match result {
@@ -241,4 +1117,28 @@ mod tests {
         std::env::set_current_dir(&original_dir).unwrap();
         cleanup_temp_dir(&temp_dir);
     }
+
+    #[test]
+    fn test_default_global_config() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("gex_gitconfig_test_{}", timestamp));
+        let xdg = home.join(".config");
+        fs::create_dir_all(&home).unwrap();
+
+        assert_eq!(default_global_config(&home, &xdg), home.join(".gitconfig"));
+
+        // Only the XDG file exists, so git uses it
+        fs::create_dir_all(xdg.join("git")).unwrap();
+        fs::write(xdg.join("git").join("config"), "").unwrap();
+        assert_eq!(default_global_config(&home, &xdg), xdg.join("git").join("config"));
+
+        // ~/.gitconfig wins once it exists
+        fs::write(home.join(".gitconfig"), "").unwrap();
+        assert_eq!(default_global_config(&home, &xdg), home.join(".gitconfig"));
+
+        cleanup_temp_dir(&home);
+    }
 }
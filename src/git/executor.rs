@@ -3,6 +3,13 @@ use std::process::Command;
 
 /// Execute a git command with the given arguments
 pub fn execute_git(args: &[&str]) -> Result<String> {
+    let stdout = execute_git_raw(args)?;
+    Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+}
+
+/// Execute a git command and return its stdout bytes untouched, for
+/// output that may not be valid UTF-8
+pub fn execute_git_raw(args: &[&str]) -> Result<Vec<u8>> {
     let output = Command::new("git")
         .args(args)
         .output()
@@ -15,11 +22,20 @@ pub fn execute_git(args: &[&str]) -> Result<String> {
         })?;
 
     if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        Ok(stdout)
+        Ok(output.stdout)
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        Err(ProfileError::InvalidInput(format!("Git command failed: {}", stderr)))
+        Err(git_failure(stderr))
+    }
+}
+
+/// Map git's stderr to an error, surfacing permission problems (e.g.
+/// writing `--system` config without privileges) as PermissionDenied
+fn git_failure(stderr: String) -> ProfileError {
+    if stderr.contains("Permission denied") {
+        ProfileError::PermissionDenied(stderr)
+    } else {
+        ProfileError::InvalidInput(format!("Git command failed: {}", stderr))
     }
 }
 
@@ -84,4 +100,13 @@ mod tests {
         let result = execute_git(&["invalid-command-that-does-not-exist"]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_git_failure_permission_denied() {
+        let err = git_failure("error: could not lock config file /etc/gitconfig: Permission denied".to_string());
+        assert!(matches!(err, ProfileError::PermissionDenied(_)));
+
+        let err = git_failure("error: key does not contain a section: foo".to_string());
+        assert!(matches!(err, ProfileError::InvalidInput(_)));
+    }
 }
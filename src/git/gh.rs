@@ -0,0 +1,59 @@
+use crate::error::{ProfileError, Result};
+use serde::Deserialize;
+use std::process::Command;
+
+/// One entry of `gh repo list --json name`
+#[derive(Deserialize)]
+struct RepoName {
+    name: String,
+}
+
+/// List the names of an org's (or user's) repositories with the GitHub
+/// CLI. gh follows the API's pagination itself, up to `limit` repos.
+pub fn list_repos(owner: &str, limit: usize) -> Result<Vec<String>> {
+    let output = match Command::new("gh")
+        .args(["repo", "list", owner, "--limit", &limit.to_string(), "--json", "name"])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ProfileError::InvalidInput(
+                "gh (GitHub CLI) not found; install it from https://cli.github.com and run 'gh auth login'"
+                    .to_string(),
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if !output.status.success() {
+        return Err(ProfileError::InvalidInput(format!(
+            "gh repo list {} failed: {}",
+            owner,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    parse_repo_list(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `gh repo list --json name` output into sorted repository names
+pub fn parse_repo_list(json: &str) -> Result<Vec<String>> {
+    let repos: Vec<RepoName> = serde_json::from_str(json)
+        .map_err(|e| ProfileError::InvalidInput(format!("Unexpected gh output: {}", e)))?;
+    let mut names: Vec<String> = repos.into_iter().map(|repo| repo.name).collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_list() {
+        let json = r#"[{"name":"web"},{"name":"api"},{"name":"web"}]"#;
+        assert_eq!(parse_repo_list(json).unwrap(), vec!["api", "web"]);
+        assert!(parse_repo_list("[]").unwrap().is_empty());
+        assert!(parse_repo_list("not json").is_err());
+    }
+}
@@ -1,12 +1,22 @@
 pub mod config;
 pub mod executor;
+pub mod gh;
+pub mod remote;
 
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfigScope {
     Global,
+    /// Repository config. In a linked worktree this is the config shared
+    /// with the main repository and every other worktree.
     Local,
+    /// Per-worktree config (`config.worktree`), only affecting the current
+    /// working tree.
+    Worktree,
+    /// System-wide config (e.g. `/etc/gitconfig`), usually needs elevated
+    /// privileges to write.
+    System,
 }
 
 impl ConfigScope {
@@ -15,15 +25,30 @@ impl ConfigScope {
         match self {
             ConfigScope::Global => "--global",
             ConfigScope::Local => "--local",
+            ConfigScope::Worktree => "--worktree",
+            ConfigScope::System => "--system",
         }
     }
 }
 
+/// What HEAD points at in the current repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadState {
+    /// HEAD is a branch with at least one commit
+    Branch,
+    /// HEAD points directly at a commit
+    Detached,
+    /// HEAD names a branch that has no commits yet (e.g. right after `git init`)
+    Unborn,
+}
+
 impl fmt::Display for ConfigScope {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConfigScope::Global => write!(f, "global"),
             ConfigScope::Local => write!(f, "local"),
+            ConfigScope::Worktree => write!(f, "worktree"),
+            ConfigScope::System => write!(f, "system"),
         }
     }
 }
@@ -0,0 +1,127 @@
+use crate::error::{ProfileError, Result};
+use crate::git::executor::execute_git;
+
+/// Get the URL of the current repository's `origin` remote, if any
+pub fn origin_url() -> Result<Option<String>> {
+    match execute_git(&["remote", "get-url", "origin"]) {
+        Ok(url) => Ok(Some(url)),
+        Err(ProfileError::InvalidInput(_)) => Ok(None), // No repo or no origin
+        Err(e) => Err(e),
+    }
+}
+
+/// Extract `(owner, repo)` from an SSH (`git@host:owner/repo.git`,
+/// `ssh://git@host/owner/repo`) or HTTPS remote URL
+pub fn parse_owner_repo(url: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?.1,
+        None => url.split_once(':')?.1,
+    };
+
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.split_once('/')?;
+
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+/// List the repository's remotes as `(name, url)` pairs, including a
+/// separate entry when a push URL differs from the fetch URL
+pub fn list_remotes() -> Result<Vec<(String, String)>> {
+    let output = match execute_git(&["remote", "-v"]) {
+        Ok(output) => output,
+        Err(ProfileError::InvalidInput(_)) => return Ok(Vec::new()), // Not a repository
+        Err(e) => return Err(e),
+    };
+    Ok(parse_remotes(&output))
+}
+
+/// Parse `git remote -v` output ("<name>\t<url> (fetch|push)")
+pub fn parse_remotes(output: &str) -> Vec<(String, String)> {
+    let mut remotes: Vec<(String, String)> = Vec::new();
+
+    for line in output.lines() {
+        let Some((name, rest)) = line.split_once('\t') else {
+            continue;
+        };
+        let url = rest.rsplit_once(' ').map_or(rest, |(url, _)| url).to_string();
+        let remote = (name.to_string(), url);
+        if !remotes.contains(&remote) {
+            remotes.push(remote);
+        }
+    }
+
+    remotes
+}
+
+/// Extract the host from an SSH (`git@host:path`, `ssh://git@host:22/path`)
+/// or HTTPS remote URL
+pub fn parse_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    let authority = match url.split_once("://") {
+        Some((_, rest)) => {
+            let authority = rest.split('/').next()?;
+            // Drop a port, but not the ':' of an IPv6 literal
+            match authority.rsplit_once(':') {
+                Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+                _ => authority,
+            }
+        }
+        None => url.split_once(':')?.0,
+    };
+
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_owner_repo() {
+        let expected = Some(("octo".to_string(), "hello-world".to_string()));
+        assert_eq!(parse_owner_repo("git@github.com:octo/hello-world.git"), expected);
+        assert_eq!(parse_owner_repo("git@github.com-work:octo/hello-world.git"), expected);
+        assert_eq!(parse_owner_repo("https://github.com/octo/hello-world"), expected);
+        assert_eq!(parse_owner_repo("https://github.com/octo/hello-world.git/"), expected);
+        assert_eq!(parse_owner_repo("ssh://git@github.com/octo/hello-world.git"), expected);
+
+        assert_eq!(parse_owner_repo("/srv/git/repo.git"), None);
+        assert_eq!(parse_owner_repo("https://github.com/octo"), None);
+        assert_eq!(parse_owner_repo("https://gitlab.com/group/sub/repo.git"), None);
+    }
+
+    #[test]
+    fn test_parse_remotes() {
+        let output = "origin\tgit@github.com-work:octo/app.git (fetch)\n\
+                      origin\tgit@github.com-work:octo/app.git (push)\n\
+                      upstream\thttps://github.com/octo/app.git (fetch)\n\
+                      upstream\tgit@github.com:octo/app.git (push)\n";
+        assert_eq!(
+            parse_remotes(output),
+            vec![
+                ("origin".to_string(), "git@github.com-work:octo/app.git".to_string()),
+                ("upstream".to_string(), "https://github.com/octo/app.git".to_string()),
+                ("upstream".to_string(), "git@github.com:octo/app.git".to_string()),
+            ]
+        );
+        assert!(parse_remotes("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_host() {
+        let host = |url| parse_host(url).unwrap();
+        assert_eq!(host("git@github.com-work:octo/app.git"), "github.com-work");
+        assert_eq!(host("github.com-work:octo/app.git"), "github.com-work");
+        assert_eq!(host("ssh://git@github.com-work:22/octo/app.git"), "github.com-work");
+        assert_eq!(host("https://github.com/octo/app.git"), "github.com");
+        assert_eq!(host("https://user@github.com/octo/app"), "github.com");
+
+        assert_eq!(parse_host("/srv/git/app.git"), None);
+    }
+}
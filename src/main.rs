@@ -1,5 +1,7 @@
+mod doctor;
 mod error;
 mod profile;
+mod selftest;
 mod git;
 mod ssh;
 mod storage;
@@ -9,7 +11,10 @@ mod cli;
 mod utils;
 
 use clap::{Parser, Subcommand};
-use cli::handlers;
+use cli::context::Context;
+use cli::handlers::{self, AddArgs, CompletionKind, EditArgs, ListFormat, StatusScope, SwitchArgs};
+use std::path::PathBuf;
+use utils::color::ColorMode;
 
 #[derive(Parser)]
 #[command(name = "gex")]
@@ -17,6 +22,35 @@ use cli::handlers;
 #[command(version)]
 #[command(author)]
 struct Cli {
+    /// Show what would change without writing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// When to use colors: auto (terminal only), always or never; a bare
+    /// --color means always
+    #[arg(
+        long = "color",
+        id = "color_mode",
+        global = true,
+        value_enum,
+        num_args = 0..=1,
+        default_value_t = ColorMode::Auto,
+        default_missing_value = "always"
+    )]
+    color_mode: ColorMode,
+
+    /// Disable colored output (same as --color never)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Write the output of read-only commands (list, status) to a file
+    #[arg(long, global = true, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Print errors to stderr as JSON ({"error", "message", "code"})
+    #[arg(long, global = true)]
+    json_errors: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -24,70 +58,417 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Add a new profile
-    Add {
-        /// Profile name
+    Add(AddArgs),
+    /// List all profiles
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::List)]
+        format: ListFormat,
+        /// Only print broken profiles (missing key, invalid fields); exit non-zero if any
+        #[arg(long, conflicts_with = "format")]
+        check: bool,
+        /// Mark each profile's SSH key with ✓ (present) or ✗ (missing)
+        #[arg(long, conflicts_with = "check")]
+        installed_keys: bool,
+        /// Mark the profile whose host alias the current repo's origin uses
+        #[arg(long, conflicts_with = "check")]
+        remote_match: bool,
+        /// Also show each profile's description
+        #[arg(short, long, conflicts_with = "check")]
+        verbose: bool,
+        /// Most recently switched-to profiles first (from 'gex history')
+        #[arg(long, conflicts_with = "check")]
+        sort_by_usage: bool,
+    },
+    /// Switch to a profile
+    Switch(SwitchArgs),
+    /// Delete a profile
+    Delete {
+        /// Profile name to delete
         name: String,
-        /// GitHub username
-        #[arg(short, long)]
-        username: String,
-        /// Email address
+        /// Don't ask for confirmation
         #[arg(short, long)]
-        email: String,
+        yes: bool,
+    },
+    /// Edit a profile
+    Edit(EditArgs),
+    /// Show current profile status
+    Status {
+        /// Also show which profile and key each remote will use
+        #[arg(long)]
+        remote: bool,
+        /// Only report this scope
+        #[arg(long, value_enum)]
+        scope: Option<StatusScope>,
+    },
+    /// Show the identity git will actually commit with, and where it comes from
+    Whoami,
+    /// Show recent profile switches and their notes
+    History {
+        /// Number of entries to show (most recent last)
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Only show switches newer than this (e.g. 30m, 24h, 7d, 2w)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only show switches to this profile
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Print the name of the active profile (local if set, otherwise global);
+    /// pass --color to keep its color when a shell prompt captures it
+    Current,
+    /// Rename an SSH key file (and its .pub) and update the profiles using it
+    RenameKey {
+        /// Current key name (e.g., id_rsa_work)
+        old: String,
+        /// New key name
+        new: String,
+    },
+    /// Show which profiles use an SSH key (or an email address)
+    Which {
         /// SSH key name (e.g., id_rsa_personal)
+        #[arg(required_unless_present = "email", conflicts_with = "email")]
+        ssh_key: Option<String>,
+        /// Look up profiles by email instead
+        #[arg(long)]
+        email: Option<String>,
+    },
+    /// Diagnose common setup problems
+    Doctor {
+        /// Offer to fix the problems that can be repaired automatically
+        #[arg(long)]
+        fix: bool,
+        /// Apply every fix without asking
+        #[arg(short, long, requires = "fix")]
+        yes: bool,
+        /// Print the check results as JSON
+        #[arg(long, conflicts_with = "fix")]
+        json: bool,
+    },
+    /// Print completion candidates for shell scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// What to complete
+        #[arg(value_enum)]
+        kind: CompletionKind,
+        /// Only print candidates starting with this
+        prefix: Option<String>,
+    },
+    /// Salvage profiles from a corrupted profiles file
+    Recover {
+        /// Don't ask for confirmation before rewriting the file
         #[arg(short, long)]
-        ssh_key: String,
+        yes: bool,
     },
-    /// List all profiles
-    List,
-    /// Switch to a profile
-    Switch {
-        /// Profile name to switch to
+    /// Show which profiles' keys are loaded in ssh-agent
+    Agent,
+    /// Print the clone URL for a repository using a profile's SSH host
+    Url {
+        /// Profile name
         name: String,
-        /// Apply globally (default is local to current repository)
-        #[arg(short, long)]
-        global: bool,
+        /// Repository as owner/repo (or an existing GitHub URL)
+        repo: String,
+        /// Print the HTTPS URL instead
+        #[arg(long)]
+        https: bool,
     },
-    /// Delete a profile
-    Delete {
-        /// Profile name to delete
+    /// Clone every repository of a GitHub organization (listed with gh)
+    /// through a profile's SSH host, with its identity set in each clone
+    Clone {
+        /// Profile name
         name: String,
+        /// GitHub organization (or user) whose repositories to clone
+        #[arg(long, value_name = "ORG")]
+        all_from: String,
+        /// Directory to clone into (defaults to the current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Maximum number of repositories to fetch from gh
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
     },
-    /// Edit a profile
-    Edit {
-        /// Profile name to edit
+    /// Run a command as a profile (identity and SSH key set through the
+    /// environment), e.g. gex run work -- git push
+    Run {
+        /// Profile name
         name: String,
+        /// Command and its arguments
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
     },
-    /// Show current profile status
-    Status,
+    /// Walk through creating a profile (and optionally an SSH key)
+    Init,
+    /// Run add/switch/status/delete in a throwaway sandbox
+    SelfTest,
     /// Launch interactive TUI
     Tui,
+    /// Rebuild all gex-managed SSH host blocks from the stored profiles
+    SshSync {
+        /// Only report drift from the stored profiles; exit non-zero if any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Remove gex-managed state
+    Prune {
+        /// Remove every gex-managed host block from ~/.ssh/config
+        #[arg(long, required = true)]
+        ssh: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Remove all gex state (selected steps, or everything if none are given)
+    Uninstall {
+        /// Delete gex's profiles, contexts, history and backups, and their
+        /// directories once empty
+        #[arg(long)]
+        config: bool,
+        /// Remove gex-managed host blocks from ~/.ssh/config
+        #[arg(long)]
+        ssh: bool,
+        /// Unset the global git identity if it belongs to a gex profile
+        #[arg(long)]
+        git_identity: bool,
+        /// Don't ask for confirmation before each step
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Check that a profile's SSH key authenticates with GitHub
+    Verify {
+        /// Profile name to verify
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+        /// Verify every profile
+        #[arg(long)]
+        all: bool,
+        /// Seconds to wait for each probe
+        #[arg(long, default_value_t = 10)]
+        timeout: u64,
+    },
+    /// Export profiles to a file (or stdout)
+    Export {
+        /// File to write (defaults to stdout)
+        #[arg(conflicts_with = "output_dir")]
+        file: Option<PathBuf>,
+        /// Directory to write one <name>.json per profile into (with --split)
+        #[arg(long, requires = "split")]
+        output_dir: Option<PathBuf>,
+        /// Write each profile to its own file
+        #[arg(long, requires = "output_dir")]
+        split: bool,
+        /// Encrypt the export with a passphrase
+        #[arg(long)]
+        encrypt: bool,
+        /// Only export these profiles (repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',', value_name = "NAMES")]
+        select: Vec<String>,
+    },
+    /// Import profiles from an export file
+    Import {
+        /// File to read
+        #[arg(required_unless_present = "dir", conflicts_with = "dir")]
+        file: Option<PathBuf>,
+        /// Read every *.json in a directory as an individual profile
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Decrypt a passphrase-encrypted export
+        #[arg(long)]
+        decrypt: bool,
+        /// Import profiles that fail validation (e.g. legacy data), listing each failure
+        #[arg(long)]
+        no_validate: bool,
+        /// Print the import summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Snapshot or restore profiles and gex-managed SSH host blocks
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+    /// Manage isolated profile sets (contexts)
+    Context {
+        #[command(subcommand)]
+        action: ContextAction,
+    },
+    /// Change gex settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Set the TUI color theme (default, mono, solarized)
+    Theme {
+        /// Theme name
+        name: String,
+    },
+    /// Set the maximum number of profiles that can be stored
+    MaxProfiles {
+        /// Profile limit
+        limit: usize,
+    },
+    /// Print the value of one setting
+    Get {
+        /// Setting name (see 'gex config list')
+        key: String,
+    },
+    /// Print every setting and its value
+    List,
+    /// Allow or forbid creating ~/.ssh/config when it doesn't exist
+    AutoCreateSshConfig {
+        /// true or false
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Include (true) or leave out (false) 'IdentitiesOnly yes' in SSH host blocks
+    SshIdentitiesOnly {
+        /// true or false
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Insert new SSH host blocks after this line of ~/.ssh/config
+    /// (a comment or a Host/Match line); leave it out to append at the end
+    SshConfigSection {
+        /// Anchor line, e.g. "# gex profiles"
+        line: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BackupAction {
+    /// Write a timestamped archive of profiles.json and the gex SSH blocks
+    Create {
+        /// Directory for the archive (defaults to backups/ in the config directory)
+        #[arg(long, visible_alias = "backup-dir")]
+        dir: Option<PathBuf>,
+    },
+    /// Roll profiles and gex SSH blocks back to an archive
+    Restore {
+        /// Archive written by 'gex backup create'
+        archive: PathBuf,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContextAction {
+    /// Create a new, empty context
+    Create {
+        /// Context name
+        name: String,
+    },
+    /// Make a context active for all commands
+    Use {
+        /// Context name ("default" for the original profiles file)
+        name: String,
+    },
+    /// List all contexts
+    List,
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    if cli.output.is_some() && !matches!(cli.command, Commands::List { .. } | Commands::Status { .. }) {
+        eprintln!("❌ Error: --output is only supported by 'list' and 'status'");
+        std::process::exit(2);
+    }
+    let color = if cli.no_color { ColorMode::Never } else { cli.color_mode };
+    let ctx = Context::new(cli.dry_run, color).with_output(cli.output);
+    let json_errors = cli.json_errors;
 
     let result = match cli.command {
-        Commands::Add {
-            name,
-            username,
-            email,
-            ssh_key,
-        } => handlers::handle_add(name, username, email, ssh_key),
-        Commands::List => handlers::handle_list(),
-        Commands::Switch { name, global } => handlers::handle_switch(name, global),
-        Commands::Delete { name } => handlers::handle_delete(name),
-        Commands::Edit { name } => handlers::handle_edit(name),
-        Commands::Status => handlers::handle_status(),
+        Commands::Add(args) => handlers::handle_add(&ctx, args),
+        Commands::List { format, check, installed_keys, remote_match, verbose, sort_by_usage } => {
+            if check {
+                handlers::handle_list_check(&ctx)
+            } else {
+                handlers::handle_list(&ctx, format, installed_keys, remote_match, verbose, sort_by_usage)
+            }
+        }
+        Commands::Switch(args) => handlers::handle_switch(&ctx, args),
+        Commands::Delete { name, yes } => handlers::handle_delete(&ctx, name, yes),
+        Commands::Edit(args) => handlers::handle_edit(&ctx, args),
+        Commands::Status { remote, scope } => handlers::handle_status(&ctx, remote, scope),
+        Commands::Whoami => handlers::handle_whoami(),
+        Commands::History { limit, since, profile } => handlers::handle_history(limit, since, profile),
+        Commands::Current => handlers::handle_current(&ctx),
+        Commands::RenameKey { old, new } => handlers::handle_rename_key(&ctx, old, new),
+        Commands::Which { ssh_key, email } => handlers::handle_which(&ctx, ssh_key, email),
+        Commands::Doctor { fix, yes, json } => handlers::handle_doctor(&ctx, fix, yes, json),
+        Commands::Complete { kind, prefix } => handlers::handle_complete(&ctx, kind, prefix),
+        Commands::Recover { yes } => handlers::handle_recover(&ctx, yes),
+        Commands::Agent => handlers::handle_agent(&ctx),
+        Commands::Url { name, repo, https } => handlers::handle_url(&ctx, name, repo, https),
+        Commands::Clone { name, all_from, dir, limit } => handlers::handle_clone_all(&ctx, name, all_from, dir, limit),
+        Commands::Run { name, command } => handlers::handle_run(&ctx, name, command),
+        Commands::Init => handlers::handle_init(&ctx),
+        Commands::SelfTest => handlers::handle_self_test(),
         Commands::Tui => {
             use tui::app::TuiApp;
-            let mut app = TuiApp::new()?;
+            let mut app = TuiApp::new(&ctx)?;
             app.run()?;
             Ok(())
         }
+        Commands::SshSync { check } => handlers::handle_ssh_sync(&ctx, check),
+        Commands::Prune { ssh: _, yes } => handlers::handle_prune_ssh(&ctx, yes),
+        Commands::Uninstall {
+            config,
+            ssh,
+            git_identity,
+            yes,
+        } => handlers::handle_uninstall(&ctx, config, ssh, git_identity, yes),
+        Commands::Verify { name, all: _, timeout } => handlers::handle_verify(&ctx, name, timeout),
+        Commands::Export {
+            file,
+            output_dir,
+            split: _,
+            encrypt,
+            select,
+        } => handlers::handle_export(&ctx, file, output_dir, encrypt, select),
+        Commands::Import {
+            file,
+            dir,
+            decrypt,
+            no_validate,
+            json,
+        } => handlers::handle_import(&ctx, file, dir, decrypt, no_validate, json),
+        Commands::Backup { action } => match action {
+            BackupAction::Create { dir } => handlers::handle_backup_create(&ctx, dir),
+            BackupAction::Restore { archive, yes } => handlers::handle_backup_restore(&ctx, archive, yes),
+        },
+        Commands::Context { action } => match action {
+            ContextAction::Create { name } => handlers::handle_context_create(&ctx, name),
+            ContextAction::Use { name } => handlers::handle_context_use(&ctx, name),
+            ContextAction::List => handlers::handle_context_list(),
+        },
+        Commands::Config { action } => match action {
+            ConfigAction::Theme { name } => handlers::handle_config_theme(&ctx, name),
+            ConfigAction::MaxProfiles { limit } => handlers::handle_config_max_profiles(&ctx, limit),
+            ConfigAction::Get { key } => handlers::handle_config_get(&ctx, key),
+            ConfigAction::List => handlers::handle_config_list(&ctx),
+            ConfigAction::AutoCreateSshConfig { enabled } => {
+                handlers::handle_config_auto_create_ssh_config(&ctx, enabled)
+            }
+            ConfigAction::SshIdentitiesOnly { enabled } => {
+                handlers::handle_config_ssh_identities_only(&ctx, enabled)
+            }
+            ConfigAction::SshConfigSection { line } => handlers::handle_config_ssh_config_section(&ctx, line),
+        },
     };
 
     // Handle errors with user-friendly messages
     if let Err(e) = result {
+        if json_errors {
+            eprintln!("{}", e.to_json());
+            std::process::exit(e.exit_code());
+        }
+
         eprintln!("❌ Error: {}", e);
         
         // Show suggestion if available
@@ -95,7 +476,7 @@ fn main() -> anyhow::Result<()> {
             eprintln!("\n{}", e.with_suggestion());
         }
         
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 
     Ok(())
@@ -1,6 +1,18 @@
 use crate::error::{ProfileError, Result};
 use crate::profile::Profile;
 use crate::storage::service::StorageService;
+use crate::storage::Settings;
+
+/// Outcome of importing a batch of profiles
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Profiles that were added
+    pub added: Vec<String>,
+    /// Profiles that already exist with the same identity
+    pub unchanged: Vec<String>,
+    /// Profiles whose name exists with a different identity (left as is)
+    pub conflicts: Vec<String>,
+}
 
 pub struct ProfileManager {
     pub(crate) storage: StorageService,
@@ -13,24 +25,30 @@ impl ProfileManager {
         Ok(Self { storage })
     }
 
-    /// Create a new profile
-    pub fn create_profile(&mut self, profile: Profile) -> Result<()> {
-        // Check if profile already exists
-        if self.profile_exists(&profile.name)? {
-            return Err(ProfileError::ProfileExists(profile.name.clone()));
-        }
+    /// Log intended writes instead of saving profiles
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.storage.set_dry_run(dry_run);
+    }
 
+    /// Create a new profile, returning it as stored
+    pub fn create_profile(&mut self, profile: Profile) -> Result<Profile> {
         // Load current data
         let mut data = self.storage.load()?;
 
+        // Check if profile already exists
+        if data.profiles.iter().any(|p| p.name == profile.name) {
+            return Err(ProfileError::ProfileExists(profile.name.clone()));
+        }
+        Self::check_profile_limit(data.profiles.len() + 1, &data.settings)?;
+
         // Add the new profile
-        data.profiles.push(profile);
+        data.profiles.push(profile.clone());
         data.touch();
 
         // Save back to storage
         self.storage.save(&data)?;
 
-        Ok(())
+        Ok(profile)
     }
 
     /// Get a profile by name
@@ -51,8 +69,30 @@ impl ProfileManager {
         Ok(data.profiles)
     }
 
+    /// Find a profile by name or, when no profile has that exact name, by
+    /// its 1-based number in `gex list`
+    pub fn resolve_profile(&self, name_or_index: &str) -> Result<Profile> {
+        let mut profiles = self.get_all_profiles()?;
+        if let Some(at) = profiles.iter().position(|p| p.name == name_or_index) {
+            return Ok(profiles.swap_remove(at));
+        }
+
+        let Ok(index) = name_or_index.parse::<usize>() else {
+            return Err(ProfileError::ProfileNotFound(name_or_index.to_string()));
+        };
+        if index == 0 || index > profiles.len() {
+            return Err(ProfileError::InvalidInput(format!(
+                "No profile number {}; 'gex list' numbers them 1 to {}",
+                index,
+                profiles.len()
+            )));
+        }
+        Ok(profiles.swap_remove(index - 1))
+    }
+
     /// Update an existing profile
-    pub fn update_profile(&mut self, name: &str, updated_profile: Profile) -> Result<()> {
+    /// Returns false (and leaves the file untouched) if nothing changed
+    pub fn update_profile(&mut self, name: &str, updated_profile: Profile) -> Result<bool> {
         // Load current data
         let mut data = self.storage.load()?;
 
@@ -63,6 +103,11 @@ impl ProfileManager {
             .position(|p| p.name == name)
             .ok_or_else(|| ProfileError::ProfileNotFound(name.to_string()))?;
 
+        // Skip the save so last_modified only moves on real changes
+        if data.profiles[profile_index] == updated_profile {
+            return Ok(false);
+        }
+
         // Update the profile
         data.profiles[profile_index] = updated_profile;
         data.touch();
@@ -70,7 +115,65 @@ impl ProfileManager {
         // Save back to storage
         self.storage.save(&data)?;
 
-        Ok(())
+        Ok(true)
+    }
+
+    /// Create the profile if absent, otherwise replace the stored one, in
+    /// a single load/save. Returns the stored profile and whether it was created.
+    pub fn upsert_profile(&mut self, profile: Profile) -> Result<(Profile, bool)> {
+        let mut data = self.storage.load()?;
+
+        let created = match data.profiles.iter().position(|p| p.name == profile.name) {
+            // Unchanged profiles leave the file (and last_modified) alone
+            Some(index) if data.profiles[index] == profile => return Ok((profile, false)),
+            Some(index) => {
+                data.profiles[index] = profile.clone();
+                false
+            }
+            None => {
+                Self::check_profile_limit(data.profiles.len() + 1, &data.settings)?;
+                data.profiles.push(profile.clone());
+                true
+            }
+        };
+
+        data.touch();
+        self.storage.save(&data)?;
+        Ok((profile, created))
+    }
+
+    /// Apply the same edit to every matching profile in one load/save.
+    /// All edited profiles are validated before anything is written.
+    /// Returns the names of the profiles that actually changed.
+    pub fn update_matching<M, E>(&mut self, matches: M, edit: E) -> Result<Vec<String>>
+    where
+        M: Fn(&Profile) -> bool,
+        E: Fn(&mut Profile),
+    {
+        let mut data = self.storage.load()?;
+        let mut changed = Vec::new();
+
+        for profile in data.profiles.iter_mut().filter(|p| matches(p)) {
+            let mut updated = profile.clone();
+            edit(&mut updated);
+            if updated != *profile {
+                if let Err(ProfileError::InvalidInput(msg)) = updated.validate() {
+                    return Err(ProfileError::InvalidInput(format!(
+                        "Profile '{}': {}",
+                        profile.name, msg
+                    )));
+                }
+                *profile = updated;
+                changed.push(profile.name.clone());
+            }
+        }
+
+        if !changed.is_empty() {
+            data.touch();
+            self.storage.save(&data)?;
+        }
+
+        Ok(changed)
     }
 
     /// Delete a profile
@@ -97,14 +200,110 @@ impl ProfileManager {
 
     /// Check if a profile exists
     pub fn profile_exists(&self, name: &str) -> Result<bool> {
+        Ok(self.storage.profile_names()?.iter().any(|n| n == name))
+    }
+
+    /// Number of stored profiles, without loading them
+    pub fn profile_count(&self) -> Result<usize> {
+        self.storage.profile_count()
+    }
+
+    /// Get all profiles that use the given SSH key
+    pub fn profiles_using_key(&self, ssh_key_name: &str) -> Result<Vec<Profile>> {
+        let data = self.storage.load()?;
+        Ok(data
+            .profiles
+            .into_iter()
+            .filter(|p| p.ssh_key_name == ssh_key_name)
+            .collect())
+    }
+
+    /// Point every profile that uses the `old` key at `new` instead.
+    /// Returns the updated profiles
+    pub fn rename_key(&mut self, old: &str, new: &str) -> Result<Vec<Profile>> {
+        let mut data = self.storage.load()?;
+        let mut renamed = Vec::new();
+
+        for profile in data.profiles.iter_mut().filter(|p| p.ssh_key_name == old) {
+            profile.ssh_key_name = new.to_string();
+            renamed.push(profile.clone());
+        }
+
+        if !renamed.is_empty() {
+            data.touch();
+            self.storage.save(&data)?;
+        }
+        Ok(renamed)
+    }
+
+    /// Get all profiles with the given email (case-insensitive)
+    pub fn profiles_with_email(&self, email: &str) -> Result<Vec<Profile>> {
+        let data = self.storage.load()?;
+        Ok(data
+            .profiles
+            .into_iter()
+            .filter(|p| p.email.eq_ignore_ascii_case(email))
+            .collect())
+    }
+
+    /// Add imported profiles in a single load/save, skipping names that
+    /// already exist
+    pub fn import_profiles(&mut self, profiles: Vec<Profile>) -> Result<ImportReport> {
+        let mut data = self.storage.load()?;
+        let mut report = ImportReport::default();
+
+        for profile in profiles {
+            match data.profiles.iter().find(|p| p.name == profile.name) {
+                Some(existing) if existing.same_identity(&profile) => report.unchanged.push(profile.name),
+                Some(_) => report.conflicts.push(profile.name),
+                None => {
+                    report.added.push(profile.name.clone());
+                    data.profiles.push(profile);
+                }
+            }
+        }
+
+        Self::check_profile_limit(data.profiles.len(), &data.settings)?;
+
+        if !report.added.is_empty() {
+            data.touch();
+            self.storage.save(&data)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Ensure storing `count` profiles stays within the configured limit
+    fn check_profile_limit(count: usize, settings: &Settings) -> Result<()> {
+        if count > settings.max_profiles {
+            return Err(ProfileError::InvalidInput(format!(
+                "Profile limit of {} reached; raise it with 'gex config max-profiles <n>'",
+                settings.max_profiles
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get the stored user settings
+    pub fn get_settings(&self) -> Result<Settings> {
         let data = self.storage.load()?;
-        Ok(data.profiles.iter().any(|p| p.name == name))
+        Ok(data.settings)
+    }
+
+    /// Replace the stored user settings
+    pub fn update_settings(&mut self, settings: Settings) -> Result<()> {
+        let mut data = self.storage.load()?;
+        data.settings = settings;
+        data.touch();
+        self.storage.save(&data)?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::StorageData;
     use std::fs;
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -121,6 +320,7 @@ mod tests {
         let config_path = temp_dir.join("profiles.json");
         let storage = StorageService {
             config_path: config_path.clone(),
+            dry_run: false,
         };
 
         let manager = ProfileManager { storage };
@@ -139,6 +339,7 @@ mod tests {
             username: format!("{}-user", name),
             email: format!("{}@example.com", name),
             ssh_key_name: format!("id_rsa_{}", name),
+            ..Default::default()
         }
     }
 
@@ -159,6 +360,25 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_resolve_profile() {
+        let (mut manager, temp_dir) = create_test_manager();
+        manager.create_profile(create_test_profile("work")).unwrap();
+        manager.create_profile(create_test_profile("personal")).unwrap();
+        manager.create_profile(create_test_profile("1")).unwrap();
+
+        assert_eq!(manager.resolve_profile("personal").unwrap().name, "personal");
+        assert_eq!(manager.resolve_profile("2").unwrap().name, "personal");
+        // An exact name wins over a number
+        assert_eq!(manager.resolve_profile("1").unwrap().name, "1");
+
+        assert!(matches!(manager.resolve_profile("0"), Err(ProfileError::InvalidInput(_))));
+        assert!(matches!(manager.resolve_profile("4"), Err(ProfileError::InvalidInput(_))));
+        assert!(matches!(manager.resolve_profile("other"), Err(ProfileError::ProfileNotFound(_))));
+
+        let _ = fs::remove_dir_all(temp_dir);
+    }
+
     #[test]
     fn test_create_duplicate_profile() {
         let (mut manager, temp_dir) = create_test_manager();
@@ -181,6 +401,52 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_upsert_profile_creates_then_updates() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        let mut profile = create_test_profile("work");
+        let (stored, created) = manager.upsert_profile(profile.clone()).unwrap();
+        assert!(created);
+        assert_eq!(stored, profile);
+        assert!(!manager.upsert_profile(profile.clone()).unwrap().1);
+
+        profile.email = "new@example.com".to_string();
+        let (stored, created) = manager.upsert_profile(profile).unwrap();
+        assert!(!created);
+        assert_eq!(stored.email, "new@example.com");
+
+        let profiles = manager.get_all_profiles().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].email, "new@example.com");
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_large_profiles_file() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        let mut data = StorageData::new();
+        data.settings.max_profiles = 10_000;
+        data.profiles = (0..5000).map(|i| create_test_profile(&format!("p{}", i))).collect();
+        manager.storage.save(&data).unwrap();
+
+        assert_eq!(manager.get_all_profiles().unwrap().len(), 5000);
+        manager.create_profile(create_test_profile("extra")).unwrap();
+        assert_eq!(manager.profile_count().unwrap(), 5001);
+        assert!(manager.profile_exists("p4999").unwrap());
+
+        // The new profile is appended and the stored order is kept
+        let names = manager.storage.profile_names().unwrap();
+        assert_eq!(names.len(), 5001);
+        assert_eq!(names[..3], ["p0", "p1", "p2"]);
+        assert_eq!(names.last().unwrap(), "extra");
+        assert_eq!(manager.get_all_profiles().unwrap()[4999], create_test_profile("p4999"));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
     #[test]
     fn test_get_profile_exists() {
         let (mut manager, temp_dir) = create_test_manager();
@@ -253,6 +519,7 @@ mod tests {
             username: "new-username".to_string(),
             email: "newemail@example.com".to_string(),
             ssh_key_name: "id_ed25519_new".to_string(),
+            ..Default::default()
         };
 
         let result = manager.update_profile("personal", updated_profile);
@@ -267,6 +534,56 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_update_profile_unchanged_skips_save() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        manager.create_profile(create_test_profile("personal")).unwrap();
+        let before = fs::read_to_string(&manager.storage.config_path).unwrap();
+
+        let changed = manager
+            .update_profile("personal", create_test_profile("personal"))
+            .unwrap();
+        assert!(!changed);
+        assert_eq!(fs::read_to_string(&manager.storage.config_path).unwrap(), before);
+
+        let mut updated = create_test_profile("personal");
+        updated.email = "new@example.com".to_string();
+        assert!(manager.update_profile("personal", updated).unwrap());
+        assert_ne!(fs::read_to_string(&manager.storage.config_path).unwrap(), before);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_update_matching() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        for (name, email) in [("work", "me@old.com"), ("oss", "me@old.com"), ("home", "me@home.org")] {
+            let mut profile = create_test_profile(name);
+            profile.email = email.to_string();
+            manager.create_profile(profile).unwrap();
+        }
+
+        let changed = manager
+            .update_matching(|p| p.email.ends_with("@old.com"), |p| p.email = "me@new.com".to_string())
+            .unwrap();
+        assert_eq!(changed, vec!["work".to_string(), "oss".to_string()]);
+        assert_eq!(manager.get_profile("oss").unwrap().unwrap().email, "me@new.com");
+        assert_eq!(manager.get_profile("home").unwrap().unwrap().email, "me@home.org");
+
+        // One invalid result means nothing is saved
+        let before = fs::read_to_string(&manager.storage.config_path).unwrap();
+        let result = manager.update_matching(
+            |_| true,
+            |p| p.email = if p.name == "home" { "broken".to_string() } else { "x@y.com".to_string() },
+        );
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&manager.storage.config_path).unwrap(), before);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
     #[test]
     fn test_update_profile_not_found() {
         let (mut manager, temp_dir) = create_test_manager();
@@ -334,6 +651,114 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_rename_key() {
+        let (mut manager, temp_dir) = create_test_manager();
+        for (name, key) in [("work", "id_old"), ("oss", "id_old"), ("home", "id_home")] {
+            let mut profile = create_test_profile(name);
+            profile.ssh_key_name = key.to_string();
+            manager.create_profile(profile).unwrap();
+        }
+
+        let renamed = manager.rename_key("id_old", "id_new").unwrap();
+        assert_eq!(renamed.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["work", "oss"]);
+        assert!(manager.profiles_using_key("id_old").unwrap().is_empty());
+        assert_eq!(manager.profiles_using_key("id_new").unwrap().len(), 2);
+        assert_eq!(manager.get_profile("home").unwrap().unwrap().ssh_key_name, "id_home");
+
+        assert!(manager.rename_key("id_missing", "id_other").unwrap().is_empty());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_profiles_using_key_and_email() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        let mut shared = create_test_profile("work");
+        shared.ssh_key_name = "id_rsa_personal".to_string();
+        manager.create_profile(create_test_profile("personal")).unwrap();
+        manager.create_profile(shared).unwrap();
+        manager.create_profile(create_test_profile("oss")).unwrap();
+
+        let names = |profiles: Vec<Profile>| profiles.into_iter().map(|p| p.name).collect::<Vec<_>>();
+        assert_eq!(names(manager.profiles_using_key("id_rsa_personal").unwrap()), vec!["personal", "work"]);
+        assert!(manager.profiles_using_key("id_rsa_missing").unwrap().is_empty());
+        assert_eq!(names(manager.profiles_with_email("OSS@example.com").unwrap()), vec!["oss"]);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_import_profiles_skips_existing() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        manager.create_profile(create_test_profile("work")).unwrap();
+
+        manager.create_profile(create_test_profile("oss")).unwrap();
+
+        // A different display color doesn't make the profile a conflict
+        let mut recolored = create_test_profile("work");
+        recolored.color = Some("red".to_string());
+        let mut changed = create_test_profile("oss");
+        changed.email = "new@example.com".to_string();
+
+        let report = manager
+            .import_profiles(vec![recolored, changed, create_test_profile("personal")])
+            .unwrap();
+        assert_eq!(report.added, vec!["personal".to_string()]);
+        assert_eq!(report.unchanged, vec!["work".to_string()]);
+        assert_eq!(report.conflicts, vec!["oss".to_string()]);
+        assert_eq!(manager.get_all_profiles().unwrap().len(), 3);
+        assert_eq!(manager.get_profile("oss").unwrap().unwrap().email, "oss@example.com");
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_profile_limit() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        let mut settings = manager.get_settings().unwrap();
+        settings.max_profiles = 2;
+        manager.update_settings(settings).unwrap();
+
+        manager.create_profile(create_test_profile("one")).unwrap();
+        manager.create_profile(create_test_profile("two")).unwrap();
+
+        let result = manager.create_profile(create_test_profile("three"));
+        assert!(matches!(result, Err(ProfileError::InvalidInput(_))));
+
+        // Bulk import is rejected as a whole rather than partially applied
+        manager.delete_profile("two").unwrap();
+        let result = manager.import_profiles(vec![
+            create_test_profile("four"),
+            create_test_profile("five"),
+        ]);
+        assert!(matches!(result, Err(ProfileError::InvalidInput(_))));
+        assert_eq!(manager.get_all_profiles().unwrap().len(), 1);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_update_settings() {
+        let (mut manager, temp_dir) = create_test_manager();
+
+        assert_eq!(manager.get_settings().unwrap(), Settings::default());
+
+        manager.create_profile(create_test_profile("personal")).unwrap();
+        let mut settings = manager.get_settings().unwrap();
+        settings.theme = "mono".to_string();
+        manager.update_settings(settings).unwrap();
+
+        assert_eq!(manager.get_settings().unwrap().theme, "mono");
+        // Profiles are untouched
+        assert_eq!(manager.get_all_profiles().unwrap().len(), 1);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
     #[test]
     fn test_multiple_operations() {
         let (mut manager, temp_dir) = create_test_manager();
@@ -349,6 +774,7 @@ mod tests {
             username: "updated-user".to_string(),
             email: "updated@example.com".to_string(),
             ssh_key_name: "id_rsa_updated".to_string(),
+            ..Default::default()
         };
         manager.update_profile("profile2", updated).unwrap();
 
@@ -1,13 +1,79 @@
 pub mod manager;
 
+use crate::error::{ProfileError, Result};
+use crate::utils::validator::Validator;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Longest description (or note) a profile can carry, in characters
+pub const MAX_DESCRIPTION_LEN: usize = 200;
+
+/// Keys gex sets itself, which a profile's `git_config` can't override
+const RESERVED_GIT_CONFIG_KEYS: [&str; 3] = ["user.name", "user.email", "gex.configkeys"];
+
+/// How commits made with a profile are signed
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningMode {
+    /// Don't configure commit signing
+    #[default]
+    None,
+    /// Sign commits with the profile's SSH key (`gpg.format=ssh`)
+    Ssh,
+}
+
+impl SigningMode {
+    /// Whether signing is disabled
+    pub fn is_none(&self) -> bool {
+        *self == SigningMode::None
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Profile {
     pub name: String,
     pub username: String,
     pub email: String,
     pub ssh_key_name: String,
+    /// Named color used to tell profiles apart in the TUI and prompts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Commit signing applied when switching to this profile
+    #[serde(default, skip_serializing_if = "SigningMode::is_none")]
+    pub signing_mode: SigningMode,
+    /// `pull.rebase` applied when switching; unset when None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_rebase: Option<bool>,
+    /// `init.defaultBranch` applied when switching; unset when None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    /// Add `AddKeysToAgent yes` to the profile's SSH host block
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub add_keys_to_agent: bool,
+    /// Add `UseKeychain yes` to the profile's SSH host block (macOS only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub use_keychain: bool,
+    /// GitHub organization whose HTTPS URLs are rewritten to this profile's host alias
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    /// Only manage the commit identity on switch: no SSH key check, no host block
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub commit_identity_only: bool,
+    /// SSH port for the host block; 443 goes through `ssh.github.com`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_port: Option<u16>,
+    /// Free-text note, e.g. "acme work account"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Reminder shown on every switch and in status, e.g. "squash-merge only"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Extra git config set when switching and unset when switching away
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub git_config: BTreeMap<String, String>,
 }
 
 impl Profile {
@@ -18,11 +84,483 @@ impl Profile {
             username,
             email,
             ssh_key_name,
+            color: None,
+            signing_mode: SigningMode::None,
+            pull_rebase: None,
+            default_branch: None,
+            add_keys_to_agent: false,
+            use_keychain: false,
+            org: None,
+            commit_identity_only: false,
+            ssh_port: None,
+            description: None,
+            note: None,
+            git_config: BTreeMap::new(),
         }
     }
 
+    /// Clean up a description as entered: line breaks become spaces and
+    /// surrounding whitespace is trimmed; empty means no description
+    pub fn clean_description(text: &str) -> Result<Option<String>> {
+        clean_line(text, "Description")
+    }
+
+    /// Clean up a reminder note the same way as a description
+    pub fn clean_note(text: &str) -> Result<Option<String>> {
+        clean_line(text, "Note")
+    }
+
+    /// Parse a `--git-config key=value` entry
+    pub fn parse_git_config(entry: &str) -> Result<(String, String)> {
+        let (key, value) = entry.split_once('=').ok_or_else(|| {
+            ProfileError::InvalidInput(format!("Invalid git config '{}': expected key=value", entry))
+        })?;
+        check_git_config(key, value)?;
+        Ok((key.to_string(), value.to_string()))
+    }
+
+    /// Load and validate a single profile from a JSON or YAML (`.yaml`/`.yml`) file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let is_yaml = path
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml");
+
+        let profile: Profile = if is_yaml {
+            serde_yaml::from_str(&contents).map_err(|e| {
+                ProfileError::InvalidInput(format!("Invalid profile file {}: {}", path.display(), e))
+            })?
+        } else {
+            serde_json::from_str(&contents).map_err(|e| {
+                ProfileError::InvalidInput(format!("Invalid profile file {}: {}", path.display(), e))
+            })?
+        };
+
+        profile.validate()?;
+        Ok(profile)
+    }
+
     /// Get the SSH host identifier for this profile
     pub fn ssh_host(&self) -> String {
         format!("github.com-{}", self.name)
     }
+
+    /// Real host behind the profile's alias: GitHub only serves SSH on
+    /// port 443 from `ssh.github.com`
+    pub fn ssh_hostname(&self) -> &'static str {
+        if self.ssh_port == Some(443) {
+            "ssh.github.com"
+        } else {
+            "github.com"
+        }
+    }
+
+    /// SSH clone URL for `owner/repo` that goes through this profile's host alias
+    pub fn ssh_clone_url(&self, path: &str) -> String {
+        format!("git@{}:{}.git", self.ssh_host(), clone_path(path))
+    }
+
+    /// `url.<base>.insteadOf` key and value that send the profile's org
+    /// HTTPS URLs through its host alias, if an org is set
+    pub fn org_rewrite(&self) -> Option<(String, String)> {
+        self.org.as_ref().map(|org| {
+            (
+                format!("url.git@{}:{}/.insteadOf", self.ssh_host(), org),
+                format!("https://github.com/{}/", org),
+            )
+        })
+    }
+
+    /// Environment for running a command as this profile: the author and
+    /// committer variables, `GIT_SSH_COMMAND` pinned to `key_path`, and
+    /// `user.name`/`user.email` as `GIT_CONFIG_*` entries numbered after
+    /// the `config_count` entries already in the environment
+    pub fn git_env(&self, key_path: &Path, config_count: usize) -> Vec<(String, String)> {
+        let quoted_key = format!("'{}'", key_path.display().to_string().replace('\'', r"'\''"));
+        let mut ssh_command = format!("ssh -i {} -o IdentitiesOnly=yes", quoted_key);
+        if let Some(port) = self.ssh_port {
+            ssh_command.push_str(&format!(" -o HostName={} -p {}", self.ssh_hostname(), port));
+        }
+        let mut env = vec![
+            ("GIT_AUTHOR_NAME".to_string(), self.username.clone()),
+            ("GIT_AUTHOR_EMAIL".to_string(), self.email.clone()),
+            ("GIT_COMMITTER_NAME".to_string(), self.username.clone()),
+            ("GIT_COMMITTER_EMAIL".to_string(), self.email.clone()),
+            ("GIT_SSH_COMMAND".to_string(), ssh_command),
+        ];
+
+        for (i, (key, value)) in [("user.name", &self.username), ("user.email", &self.email)]
+            .into_iter()
+            .enumerate()
+        {
+            env.push((format!("GIT_CONFIG_KEY_{}", config_count + i), key.to_string()));
+            env.push((format!("GIT_CONFIG_VALUE_{}", config_count + i), value.clone()));
+        }
+        env.push(("GIT_CONFIG_COUNT".to_string(), (config_count + 2).to_string()));
+        env
+    }
+
+    /// Plain HTTPS clone URL for `owner/repo` (no host alias applies)
+    pub fn https_clone_url(&self, path: &str) -> String {
+        format!("https://github.com/{}.git", clone_path(path))
+    }
+
+    /// Compare only the fields that define who the profile is (name,
+    /// identity, SSH key/port and signing), ignoring cosmetic or volatile
+    /// ones such as the display color. The host alias follows from the name.
+    pub fn same_identity(&self, other: &Profile) -> bool {
+        self.name == other.name
+            && self.username == other.username
+            && self.email == other.email
+            && self.ssh_key_name == other.ssh_key_name
+            && self.ssh_port == other.ssh_port
+            && self.signing_mode == other.signing_mode
+    }
+
+    /// Reject an empty or whitespace-only name, which would leave the host
+    /// alias and SSH config marker without a name
+    pub fn validate_name_present(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(ProfileError::InvalidInput("Profile name is empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Check that every field holds a valid value
+    pub fn validate(&self) -> Result<()> {
+        self.validate_name_present()?;
+
+        if !Validator::validate_profile_name(&self.name) {
+            return Err(ProfileError::InvalidInput(format!(
+                "Invalid profile name '{}'",
+                self.name
+            )));
+        }
+
+        if !Validator::validate_username(&self.username) {
+            return Err(ProfileError::InvalidInput(format!(
+                "Invalid GitHub username '{}'",
+                self.username
+            )));
+        }
+
+        if !Validator::validate_email(&self.email) {
+            return Err(ProfileError::InvalidInput(format!(
+                "Invalid email '{}'",
+                self.email
+            )));
+        }
+
+        if !Validator::validate_ssh_key_name(&self.ssh_key_name) {
+            return Err(ProfileError::InvalidInput(format!(
+                "Invalid SSH key name '{}'",
+                self.ssh_key_name
+            )));
+        }
+
+        if let Some(color) = &self.color {
+            if !Validator::validate_color(color) {
+                return Err(ProfileError::InvalidInput(format!("Invalid color '{}'", color)));
+            }
+        }
+
+        if let Some(branch) = &self.default_branch {
+            if !Validator::validate_branch_name(branch) {
+                return Err(ProfileError::InvalidInput(format!("Invalid branch name '{}'", branch)));
+            }
+        }
+
+        for (key, value) in &self.git_config {
+            check_git_config(key, value)?;
+        }
+
+        for (what, text) in [("description", &self.description), ("note", &self.note)] {
+            if text.as_deref().is_some_and(|text| !is_clean_line(text)) {
+                return Err(ProfileError::InvalidInput(format!(
+                    "Invalid {} for '{}': at most {} characters on one line",
+                    what, self.name, MAX_DESCRIPTION_LEN
+                )));
+            }
+        }
+
+        if self.ssh_port == Some(0) {
+            return Err(ProfileError::InvalidInput(
+                "Invalid SSH port 0; use a port between 1 and 65535".to_string(),
+            ));
+        }
+
+        // Organization names follow the same rules as usernames
+        if let Some(org) = &self.org {
+            if !Validator::validate_username(org) {
+                return Err(ProfileError::InvalidInput(format!("Invalid GitHub organization '{}'", org)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Normalize `owner/repo`, dropping surrounding slashes and a `.git` suffix
+fn clone_path(path: &str) -> &str {
+    let path = path.trim_matches('/');
+    path.strip_suffix(".git").unwrap_or(path)
+}
+
+/// Join a free-text field onto one trimmed line, rejecting it when too
+/// long; empty means unset
+fn clean_line(text: &str, what: &str) -> Result<Option<String>> {
+    let text = text
+        .split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.chars().count() > MAX_DESCRIPTION_LEN {
+        return Err(ProfileError::InvalidInput(format!(
+            "{} is longer than {} characters",
+            what, MAX_DESCRIPTION_LEN
+        )));
+    }
+    Ok((!text.is_empty()).then_some(text))
+}
+
+/// Reject git config keys not in `section.key` form or reserved by gex,
+/// and values spanning several lines
+fn check_git_config(key: &str, value: &str) -> Result<()> {
+    if !Validator::validate_git_config_key(key) {
+        return Err(ProfileError::InvalidInput(format!(
+            "Invalid git config key '{}': expected section.key",
+            key
+        )));
+    }
+    if RESERVED_GIT_CONFIG_KEYS.contains(&key.to_ascii_lowercase().as_str()) {
+        return Err(ProfileError::InvalidInput(format!(
+            "git config key '{}' is managed by gex itself",
+            key
+        )));
+    }
+    if value.contains(['\n', '\r']) {
+        return Err(ProfileError::InvalidInput(format!(
+            "Value for git config key '{}' must be a single line",
+            key
+        )));
+    }
+    Ok(())
+}
+
+/// Whether a stored free-text field is a single line within the length limit
+fn is_clean_line(text: &str) -> bool {
+    text.chars().count() <= MAX_DESCRIPTION_LEN && !text.chars().any(|c| c.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_profile() -> Profile {
+        Profile::new(
+            "work".to_string(),
+            "john-work".to_string(),
+            "john@work.com".to_string(),
+            "id_ed25519_work".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_git_config() {
+        assert_eq!(Profile::parse_git_config("a.b=c").unwrap(), ("a.b".to_string(), "c".to_string()));
+        assert_eq!(
+            Profile::parse_git_config("alias.lg=log --oneline=short").unwrap(),
+            ("alias.lg".to_string(), "log --oneline=short".to_string())
+        );
+        assert_eq!(Profile::parse_git_config("core.pager=").unwrap().1, "");
+
+        let err = Profile::parse_git_config("badkey").unwrap_err();
+        assert!(err.to_string().contains("expected key=value"), "{}", err);
+        assert!(Profile::parse_git_config("badkey=1").is_err());
+        assert!(Profile::parse_git_config("user.email=x@y.com").is_err());
+        assert!(Profile::parse_git_config("a.b=two\nlines").is_err());
+
+        let mut profile = valid_profile();
+        profile.git_config.insert("pull.rebase".to_string(), "true".to_string());
+        assert!(profile.validate().is_ok());
+        profile.git_config.insert("nodot".to_string(), "true".to_string());
+        assert!(profile.validate().is_err());
+    }
+
+    #[test]
+    fn test_clean_description() {
+        assert_eq!(
+            Profile::clean_description("  acme work account,\r\nexpires 2025 \n").unwrap(),
+            Some("acme work account, expires 2025".to_string())
+        );
+        assert_eq!(Profile::clean_description(" \n ").unwrap(), None);
+        assert!(Profile::clean_description(&"x".repeat(MAX_DESCRIPTION_LEN)).is_ok());
+        assert!(Profile::clean_description(&"é".repeat(MAX_DESCRIPTION_LEN + 1)).is_err());
+
+        assert_eq!(Profile::clean_note("squash-merge\nonly ").unwrap(), Some("squash-merge only".to_string()));
+        let err = Profile::clean_note(&"x".repeat(MAX_DESCRIPTION_LEN + 1)).unwrap_err();
+        assert!(err.to_string().contains("Note is longer"), "{}", err);
+    }
+
+    #[test]
+    fn test_git_env() {
+        let env = valid_profile().git_env(Path::new("/home/john/.ssh/id_ed25519_work"), 1);
+        let get = |key: &str| env.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+
+        assert_eq!(get("GIT_AUTHOR_NAME"), Some("john-work"));
+        assert_eq!(get("GIT_AUTHOR_EMAIL"), Some("john@work.com"));
+        assert_eq!(get("GIT_COMMITTER_NAME"), Some("john-work"));
+        assert_eq!(get("GIT_COMMITTER_EMAIL"), Some("john@work.com"));
+        assert_eq!(
+            get("GIT_SSH_COMMAND"),
+            Some("ssh -i '/home/john/.ssh/id_ed25519_work' -o IdentitiesOnly=yes")
+        );
+
+        // Config entries go after the one already present
+        assert_eq!(get("GIT_CONFIG_KEY_1"), Some("user.name"));
+        assert_eq!(get("GIT_CONFIG_VALUE_2"), Some("john@work.com"));
+        assert_eq!(get("GIT_CONFIG_COUNT"), Some("3"));
+
+        let env = valid_profile().git_env(Path::new("/tmp/it's"), 0);
+        let ssh = &env.iter().find(|(k, _)| k == "GIT_SSH_COMMAND").unwrap().1;
+        assert_eq!(ssh, r"ssh -i '/tmp/it'\''s' -o IdentitiesOnly=yes");
+    }
+
+    #[test]
+    fn test_signing_mode_serialization() {
+        // Profiles without signing keep their original JSON shape
+        let json = serde_json::to_string(&valid_profile()).unwrap();
+        assert!(!json.contains("signing_mode"));
+
+        let mut profile = valid_profile();
+        profile.signing_mode = SigningMode::Ssh;
+        let json = serde_json::to_string(&profile).unwrap();
+        assert!(json.contains("\"signing_mode\":\"ssh\""));
+        assert_eq!(serde_json::from_str::<Profile>(&json).unwrap(), profile);
+    }
+
+    #[test]
+    fn test_clone_urls() {
+        let profile = valid_profile();
+        let host = profile.ssh_host();
+
+        assert_eq!(profile.ssh_clone_url("octo/hello"), format!("git@{}:octo/hello.git", host));
+        assert_eq!(profile.ssh_clone_url("/octo/hello.git/"), format!("git@{}:octo/hello.git", host));
+        assert_eq!(profile.https_clone_url("octo/hello"), "https://github.com/octo/hello.git");
+    }
+
+    #[test]
+    fn test_org_rewrite() {
+        let mut profile = valid_profile();
+        assert_eq!(profile.org_rewrite(), None);
+
+        profile.org = Some("acme".to_string());
+        assert_eq!(
+            profile.org_rewrite(),
+            Some((
+                "url.git@github.com-work:acme/.insteadOf".to_string(),
+                "https://github.com/acme/".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_same_identity() {
+        let mut recolored = valid_profile();
+        recolored.color = Some("red".to_string());
+        assert!(valid_profile().same_identity(&recolored));
+        assert_ne!(valid_profile(), recolored);
+
+        let mut other_email = valid_profile();
+        other_email.email = "john@other.com".to_string();
+        assert!(!valid_profile().same_identity(&other_email));
+
+        let mut signing = valid_profile();
+        signing.signing_mode = SigningMode::Ssh;
+        assert!(!valid_profile().same_identity(&signing));
+
+        let mut other_port = valid_profile();
+        other_port.ssh_port = Some(443);
+        assert!(!valid_profile().same_identity(&other_port));
+
+        let mut other_key = valid_profile();
+        other_key.ssh_key_name = "id_ed25519_other".to_string();
+        assert!(!valid_profile().same_identity(&other_key));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let temp_dir = std::env::temp_dir().join(format!("gex_profile_file_test_{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let json_path = temp_dir.join("ci.json");
+        fs::write(&json_path, serde_json::to_string(&valid_profile()).unwrap()).unwrap();
+        assert_eq!(Profile::from_file(&json_path).unwrap(), valid_profile());
+
+        let yaml_path = temp_dir.join("ci.yml");
+        fs::write(
+            &yaml_path,
+            "name: work\nusername: john-work\nemail: john@work.com\nssh_key_name: id_ed25519_work\n",
+        )
+        .unwrap();
+        assert_eq!(Profile::from_file(&yaml_path).unwrap(), valid_profile());
+
+        // Invalid values are rejected before anything is applied
+        fs::write(&yaml_path, "name: work\nusername: bad_user\nemail: john@work.com\nssh_key_name: k\n").unwrap();
+        assert!(Profile::from_file(&yaml_path).is_err());
+        assert!(Profile::from_file(&temp_dir.join("missing.json")).is_err());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_validate_valid_profile() {
+        assert!(valid_profile().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_invalid_fields() {
+        let mut profile = valid_profile();
+        profile.name = "has space".to_string();
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.username = "user_name".to_string();
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.email = "not-an-email".to_string();
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.ssh_key_name = "../id_rsa".to_string();
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.color = Some("orange".to_string());
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.default_branch = Some("bad branch".to_string());
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.ssh_port = Some(0);
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.description = Some("two\nlines".to_string());
+        assert!(profile.validate().is_err());
+        profile.description = Some("x".repeat(MAX_DESCRIPTION_LEN + 1));
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.note = Some("squash-merge\tonly".to_string());
+        assert!(profile.validate().is_err());
+
+        let mut profile = valid_profile();
+        profile.org = Some("acme/repo".to_string());
+        assert!(profile.validate().is_err());
+    }
 }
@@ -0,0 +1,178 @@
+use crate::error::Result;
+use crate::utils::paths::HOME_OVERRIDE_ENV;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PROFILE_NAME: &str = "selftest";
+const PROFILE_EMAIL: &str = "selftest@example.com";
+const KEY_NAME: &str = "id_ed25519_gex_selftest";
+
+/// Outcome of one self-test stage
+#[derive(Debug, Clone)]
+pub struct Stage {
+    pub name: &'static str,
+    /// None on success, otherwise what went wrong
+    pub error: Option<String>,
+}
+
+/// A throwaway home directory that is removed when dropped, so cleanup
+/// also happens when a stage fails
+struct Sandbox {
+    root: PathBuf,
+    binary: PathBuf,
+}
+
+impl Sandbox {
+    fn new(binary: &Path) -> Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("gex_selftest_{}", timestamp));
+        fs::create_dir_all(root.join(".ssh"))?;
+        fs::create_dir_all(root.join("repo"))?;
+
+        Ok(Self {
+            root,
+            binary: binary.to_path_buf(),
+        })
+    }
+
+    fn repo(&self) -> PathBuf {
+        self.root.join("repo")
+    }
+
+    /// Build a command that can only see the sandbox: gex files via
+    /// GEX_HOME, and git's global/system config redirected or disabled
+    fn command(&self, program: &Path) -> Command {
+        let mut command = Command::new(program);
+        command
+            .current_dir(self.repo())
+            .env(HOME_OVERRIDE_ENV, &self.root)
+            .env("HOME", &self.root)
+            .env("USERPROFILE", &self.root)
+            .env("GIT_CONFIG_GLOBAL", self.root.join(".gitconfig"))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env_remove("SSH_AUTH_SOCK");
+        command
+    }
+
+    /// Run a command to completion, returning its stdout or an error
+    /// built from its stderr
+    fn run(&self, program: &Path, args: &[&str]) -> std::result::Result<String, String> {
+        let output = self
+            .command(program)
+            .args(args)
+            .output()
+            .map_err(|e| format!("failed to run {}: {}", program.display(), e))?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(stderr.trim().lines().next().unwrap_or("command failed").to_string())
+        }
+    }
+
+    fn gex(&self, args: &[&str]) -> std::result::Result<String, String> {
+        let binary = self.binary.clone();
+        self.run(&binary, args)
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+type StageFn = fn(&Sandbox) -> std::result::Result<(), String>;
+
+/// Run add/switch/status/delete against a throwaway home directory using
+/// the given gex binary. Stops at the first failing stage.
+pub fn run(binary: &Path) -> Result<Vec<Stage>> {
+    let sandbox = Sandbox::new(binary)?;
+
+    let stages: [(&'static str, StageFn); 7] = [
+        ("git init", git_init),
+        ("ssh-keygen", keygen),
+        ("add", add),
+        ("switch", switch),
+        ("ssh config", ssh_config),
+        ("status", status),
+        ("delete", delete),
+    ];
+
+    let mut results = Vec::new();
+    for (name, stage) in stages {
+        let error = stage(&sandbox).err();
+        let failed = error.is_some();
+        results.push(Stage { name, error });
+        if failed {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+fn git_init(sandbox: &Sandbox) -> std::result::Result<(), String> {
+    sandbox.run(Path::new("git"), &["init", "--quiet"]).map(|_| ())
+}
+
+fn keygen(sandbox: &Sandbox) -> std::result::Result<(), String> {
+    let key_path = sandbox.root.join(".ssh").join(KEY_NAME);
+    let key_arg = key_path.to_string_lossy();
+    sandbox
+        .run(
+            Path::new("ssh-keygen"),
+            &["-q", "-t", "ed25519", "-N", "", "-C", PROFILE_EMAIL, "-f", &key_arg],
+        )
+        .map(|_| ())
+}
+
+fn add(sandbox: &Sandbox) -> std::result::Result<(), String> {
+    sandbox
+        .gex(&["add", PROFILE_NAME, "-u", "gex-selftest", "-e", PROFILE_EMAIL, "-s", KEY_NAME])
+        .map(|_| ())
+}
+
+fn switch(sandbox: &Sandbox) -> std::result::Result<(), String> {
+    sandbox.gex(&["switch", PROFILE_NAME])?;
+
+    let email = sandbox.run(Path::new("git"), &["config", "--local", "user.email"])?;
+    if email.trim() != PROFILE_EMAIL {
+        return Err(format!("git user.email is '{}'", email.trim()));
+    }
+    Ok(())
+}
+
+fn ssh_config(sandbox: &Sandbox) -> std::result::Result<(), String> {
+    let content = fs::read_to_string(sandbox.root.join(".ssh").join("config"))
+        .map_err(|e| format!("failed to read SSH config: {}", e))?;
+
+    if !content.contains(&format!("Host github.com-{}", PROFILE_NAME)) {
+        return Err("host block was not written".to_string());
+    }
+    Ok(())
+}
+
+fn status(sandbox: &Sandbox) -> std::result::Result<(), String> {
+    let output = sandbox.gex(&["status"])?;
+    if !output.contains(PROFILE_EMAIL) {
+        return Err("status does not show the active profile".to_string());
+    }
+    Ok(())
+}
+
+fn delete(sandbox: &Sandbox) -> std::result::Result<(), String> {
+    sandbox.gex(&["delete", PROFILE_NAME, "--yes"])?;
+
+    let output = sandbox.gex(&["list"])?;
+    if !output.contains("No profiles found") {
+        return Err("profile is still listed".to_string());
+    }
+    Ok(())
+}
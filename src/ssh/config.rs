@@ -1,26 +1,229 @@
 use crate::error::{ProfileError, Result};
 use crate::profile::Profile;
+use crate::utils::paths;
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub struct SSHConfigManager {
     pub(crate) config_path: PathBuf,
+    pub(crate) dry_run: bool,
+    pub(crate) auto_create: bool,
+    /// Line after which new host blocks are inserted (the
+    /// `ssh_config_section` setting); None appends them at the end
+    pub(crate) section: Option<String>,
+    /// Write `IdentitiesOnly yes` in generated blocks (the
+    /// `ssh_identities_only` setting)
+    pub(crate) identities_only: bool,
+}
+
+/// Marker comment placed above every gex-managed host block
+const PROFILE_MARKER_PREFIX: &str = "# GitHub Profile: ";
+
+/// Upper bound on the size of a single generated host block
+const MAX_HOST_ENTRY_LEN: usize = 1024;
+
+/// Options gex writes in a host block (lowercase); other lines a user adds
+/// inside a managed block are carried over when it is rewritten
+const GENERATED_OPTIONS: [&str; 7] = [
+    "hostname",
+    "port",
+    "user",
+    "identityfile",
+    "identitiesonly",
+    "addkeystoagent",
+    "usekeychain",
+];
+
+/// Outcome of reconciling the SSH config with the stored profiles
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Profiles that had no host block yet
+    pub added: Vec<String>,
+    /// Profiles whose existing host block had different content
+    pub updated: Vec<String>,
+    /// Managed host blocks with no matching profile
+    pub removed: Vec<String>,
+}
+
+/// How a profile's installed host block compares with the generated one
+#[derive(Debug, PartialEq, Eq)]
+pub enum HostState {
+    /// The installed block matches exactly
+    InSync,
+    /// No block is installed for the profile
+    Missing { expected: String },
+    /// The installed block was edited or is out of date
+    Differs { installed: String, expected: String },
+    /// A managed block exists but no profile uses it
+    Orphaned,
+}
+
+/// A profile whose host block is not in the expected state
+#[derive(Debug, PartialEq, Eq)]
+pub struct HostDrift {
+    pub name: String,
+    pub state: HostState,
+}
+
+/// The SSH config split into lines, with the line range of every
+/// gex-managed block (marker included), found in a single pass
+struct ParsedConfig<'a> {
+    lines: Vec<&'a str>,
+    blocks: Vec<(String, Range<usize>)>,
+}
+
+impl<'a> ParsedConfig<'a> {
+    fn parse(content: &'a str) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut blocks = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            match lines[i].strip_prefix(PROFILE_MARKER_PREFIX) {
+                Some(name) => {
+                    let end = SSHConfigManager::block_end(&lines, i + 1);
+                    blocks.push((name.trim().to_string(), i..end));
+                    i = end;
+                }
+                None => i += 1,
+            }
+        }
+
+        Self { lines, blocks }
+    }
+
+    /// Line range of the block under `marker` (matched exactly, as written)
+    fn find(&self, marker: &str) -> Option<Range<usize>> {
+        self.blocks
+            .iter()
+            .map(|(_, range)| range.clone())
+            .find(|range| self.lines[range.start] == marker)
+    }
+
+    /// Lines of the block under `marker` that gex didn't generate, such as
+    /// the user's own comments or a `ProxyJump`, in their original order
+    fn user_lines(&self, marker: &str) -> Vec<&'a str> {
+        let Some(range) = self.find(marker) else {
+            return Vec::new();
+        };
+        self.lines[range]
+            .iter()
+            .skip(1)
+            .filter(|line| {
+                let line = line.trim();
+                let option = line.split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+                !line.is_empty() && option != "host" && !GENERATED_OPTIONS.contains(&option.as_str())
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Edits removing every managed block
+    fn remove_all(&self) -> Vec<(Range<usize>, &'static str)> {
+        self.blocks.iter().map(|(_, range)| (range.clone(), "")).collect()
+    }
+
+    /// Render the config with each `(range, text)` edit replacing those
+    /// lines, in one pass over the original lines. Edits must not overlap;
+    /// insertions at the same line keep their order.
+    fn render(&self, mut edits: Vec<(Range<usize>, &str)>) -> String {
+        edits.sort_by_key(|(range, _)| range.start);
+
+        let extra: usize = edits.iter().map(|(_, text)| text.len()).sum();
+        let mut out = Normalizer::with_capacity(self.lines.iter().map(|l| l.len() + 1).sum::<usize>() + extra);
+        let mut cursor = 0;
+
+        for (range, text) in edits {
+            debug_assert!(range.start >= cursor, "overlapping SSH config edits");
+            self.lines[cursor..range.start].iter().for_each(|line| out.push_line(line));
+            // Blank lines around the entry are collapsed by the normalizer
+            out.push_line("");
+            text.lines().for_each(|line| out.push_line(line));
+            out.push_line("");
+            cursor = range.end;
+        }
+        self.lines[cursor..].iter().for_each(|line| out.push_line(line));
+
+        out.finish()
+    }
+}
+
+/// Builds the canonical layout described on `SSHConfigManager::normalize`
+/// one line at a time
+struct Normalizer {
+    out: String,
+    pending_blank: bool,
+}
+
+impl Normalizer {
+    fn with_capacity(capacity: usize) -> Self {
+        Self { out: String::with_capacity(capacity), pending_blank: false }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            self.pending_blank = true;
+            return;
+        }
+        if !self.out.is_empty() && (self.pending_blank || line.starts_with(PROFILE_MARKER_PREFIX)) {
+            self.out.push('\n');
+        }
+        self.pending_blank = false;
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
 }
 
 impl SSHConfigManager {
     /// Create a new SSHConfigManager instance
     pub fn new() -> Result<Self> {
-        let home_dir = dirs::home_dir()
+        let home_dir = paths::home_dir()
             .ok_or_else(|| ProfileError::PermissionDenied("Could not determine home directory".to_string()))?;
         
         let config_path = home_dir.join(".ssh").join("config");
-        
-        Ok(Self { config_path })
+        // Key paths under the same directory are written into the config
+        paths::require_utf8(&config_path, "SSH config")?;
+
+        Ok(Self {
+            config_path,
+            dry_run: false,
+            auto_create: true,
+            section: None,
+            identities_only: true,
+        })
+    }
+
+    /// Log intended changes instead of writing the SSH config
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Whether a missing SSH config may be created (the
+    /// `auto_create_ssh_config` setting)
+    pub fn set_auto_create(&mut self, auto_create: bool) {
+        self.auto_create = auto_create;
+    }
+
+    /// Insert new host blocks after this line instead of at the end of
+    /// the file (the `ssh_config_section` setting)
+    pub fn set_section(&mut self, section: Option<String>) {
+        self.section = section;
+    }
+
+    /// Whether generated blocks pin the key with `IdentitiesOnly yes`
+    /// (the `ssh_identities_only` setting)
+    pub fn set_identities_only(&mut self, identities_only: bool) {
+        self.identities_only = identities_only;
     }
 
     /// Get the full path to an SSH key
     pub fn get_ssh_key_path(key_name: &str) -> PathBuf {
-        let home_dir = dirs::home_dir().expect("Could not determine home directory");
+        let home_dir = paths::home_dir().expect("Could not determine home directory");
         home_dir.join(".ssh").join(key_name)
     }
 
@@ -32,14 +235,23 @@ impl SSHConfigManager {
 
     /// Ensure the SSH config file exists
     pub fn ensure_ssh_config_exists(&self) -> Result<()> {
+        if !self.auto_create && !self.config_path.exists() {
+            return Err(ProfileError::InvalidInput(format!(
+                "{} does not exist and auto_create_ssh_config is off; create it yourself or run 'gex config auto-create-ssh-config true'",
+                self.config_path.display()
+            )));
+        }
+
+        if self.dry_run {
+            if !self.config_path.exists() {
+                println!("[dry-run] Would create {}", self.config_path.display());
+            }
+            return Ok(());
+        }
+
         // Ensure .ssh directory exists
         if let Some(parent) = self.config_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| ProfileError::PermissionDenied(
-                        format!("Failed to create .ssh directory: {}", e)
-                    ))?;
-            }
+            paths::ensure_dir(parent, ".ssh directory")?;
         }
 
         // Create config file if it doesn't exist
@@ -55,7 +267,7 @@ impl SSHConfigManager {
 
     /// Backup the SSH config file
     pub fn backup_ssh_config(&self) -> Result<()> {
-        if self.config_path.exists() {
+        if self.config_path.exists() && !self.dry_run {
             let backup_path = self.config_path.with_extension("config.bak");
             fs::copy(&self.config_path, &backup_path)
                 .map_err(|e| ProfileError::PermissionDenied(
@@ -65,27 +277,37 @@ impl SSHConfigManager {
         Ok(())
     }
 
-    /// Add or update a host entry for a profile
-    pub fn add_or_update_host(&mut self, profile: &Profile) -> Result<()> {
+    /// Put back the config saved by the last `backup_ssh_config`
+    pub fn restore_backup(&self) -> Result<()> {
+        let backup_path = self.config_path.with_extension("config.bak");
+        if backup_path.exists() && !self.dry_run {
+            fs::copy(&backup_path, &self.config_path)
+                .map_err(|e| ProfileError::PermissionDenied(
+                    format!("Failed to restore SSH config backup: {}", e)
+                ))?;
+        }
+        Ok(())
+    }
+
+    /// Add or update a host entry for a profile.
+    /// Returns false (and writes nothing) if the block was already up to date
+    pub fn add_or_update_host(&mut self, profile: &Profile) -> Result<bool> {
         self.ensure_ssh_config_exists()?;
-        self.backup_ssh_config()?;
 
         // Read existing config
-        let content = fs::read_to_string(&self.config_path)
-            .map_err(|e| ProfileError::PermissionDenied(
-                format!("Failed to read SSH config: {}", e)
-            ))?;
+        let content = self.read_config()?;
 
         // Parse and update config
         let updated_content = self.update_config_content(&content, profile)?;
 
-        // Write back
-        fs::write(&self.config_path, updated_content)
-            .map_err(|e| ProfileError::PermissionDenied(
-                format!("Failed to write SSH config: {}", e)
-            ))?;
+        // Leave the file and its backup alone when the block is already in place
+        if updated_content == content {
+            return Ok(false);
+        }
 
-        Ok(())
+        self.backup_ssh_config()?;
+        self.write_config(&updated_content)?;
+        Ok(true)
     }
 
     /// Remove a host entry for a profile
@@ -97,148 +319,443 @@ impl SSHConfigManager {
         self.backup_ssh_config()?;
 
         // Read existing config
-        let content = fs::read_to_string(&self.config_path)
-            .map_err(|e| ProfileError::PermissionDenied(
-                format!("Failed to read SSH config: {}", e)
-            ))?;
+        let content = self.read_config()?;
 
         // Remove the profile's host entry
         let updated_content = self.remove_host_from_content(&content, profile_name);
 
         // Write back
-        fs::write(&self.config_path, updated_content)
+        self.write_config(&updated_content)
+    }
+
+    /// List the profile names of all gex-managed host blocks
+    pub fn list_managed_hosts(&self) -> Result<Vec<String>> {
+        let content = self.read_config()?;
+        Ok(Self::managed_hosts_in_content(&content))
+    }
+
+    /// Remove every gex-managed host block, leaving user blocks intact.
+    /// Returns the profile names whose blocks were removed.
+    pub fn prune_managed_hosts(&mut self) -> Result<Vec<String>> {
+        let mut content = self.read_config()?;
+        let managed = Self::managed_hosts_in_content(&content);
+
+        if managed.is_empty() {
+            return Ok(managed);
+        }
+
+        self.backup_ssh_config()?;
+        let parsed = ParsedConfig::parse(&content);
+        content = parsed.render(parsed.remove_all());
+        self.write_config(&content)?;
+
+        Ok(managed)
+    }
+
+    /// Rebuild every gex-managed host block from the given profiles,
+    /// removing blocks for profiles that no longer exist or only manage
+    /// the commit identity. The file and its backup are left alone when
+    /// nothing would change.
+    pub fn sync_hosts(&mut self, profiles: &[Profile]) -> Result<SyncReport> {
+        let profiles = Self::host_profiles(profiles);
+        self.ensure_ssh_config_exists()?;
+
+        let original = self.read_config()?;
+        let parsed = ParsedConfig::parse(&original);
+
+        let managed = Self::managed_hosts_in_content(&original);
+        let mut report = SyncReport::default();
+
+        for name in &managed {
+            if !profiles.iter().any(|p| &p.name == name) {
+                report.removed.push(name.clone());
+            }
+        }
+
+        // Every managed block is dropped and the profiles' blocks are
+        // written fresh at the insertion point, in profile order
+        let at = self.insertion_point(&parsed)?;
+        let mut entries = Vec::with_capacity(profiles.len());
+        for profile in profiles {
+            Self::check_unmanaged_host(&original, profile)?;
+            let marker = format!("{}{}", PROFILE_MARKER_PREFIX, profile.name);
+            let entry = Self::host_entry(profile, self.identities_only)?;
+            let entry = with_user_lines(entry, &parsed.user_lines(&marker));
+
+            match Self::installed_block(&original, &profile.name) {
+                None => report.added.push(profile.name.clone()),
+                Some(installed) if installed != entry => report.updated.push(profile.name.clone()),
+                Some(_) => {}
+            }
+            entries.push(entry);
+        }
+
+        let mut edits = parsed.remove_all();
+        edits.extend(entries.iter().map(|entry| (at..at, entry.as_str())));
+        let content = parsed.render(edits);
+
+        if Self::normalize(&content) != Self::normalize(&original) {
+            self.backup_ssh_config()?;
+            self.write_config(&content)?;
+        }
+
+        Ok(report)
+    }
+
+    /// The profiles that get a host block; commit-identity-only profiles
+    /// leave the SSH config alone
+    fn host_profiles(profiles: &[Profile]) -> Vec<&Profile> {
+        profiles.iter().filter(|p| !p.commit_identity_only).collect()
+    }
+
+    /// Every gex-managed host block as `(profile name, block)`, marker
+    /// line included
+    pub fn managed_blocks(&self) -> Result<Vec<(String, String)>> {
+        let content = self.read_config()?;
+        Ok(Self::managed_hosts_in_content(&content)
+            .into_iter()
+            .filter_map(|name| {
+                let block = Self::installed_block(&content, &name)?;
+                Some((name, block))
+            })
+            .collect())
+    }
+
+    /// Replace every gex-managed host block with the given blocks (as
+    /// returned by `managed_blocks`), leaving user blocks intact
+    pub fn restore_managed_blocks(&mut self, blocks: &[(String, String)]) -> Result<()> {
+        if blocks.is_empty() && !self.config_path.exists() {
+            return Ok(());
+        }
+        self.ensure_ssh_config_exists()?;
+
+        let original = self.read_config()?;
+        let parsed = ParsedConfig::parse(&original);
+        for (name, block) in blocks {
+            let marker = format!("{}{}", PROFILE_MARKER_PREFIX, name);
+            if !block.starts_with(&format!("{}\n", marker)) {
+                return Err(ProfileError::InvalidInput(format!(
+                    "Host block for '{}' does not start with its gex marker",
+                    name
+                )));
+            }
+        }
+
+        let at = self.insertion_point(&parsed)?;
+        let mut edits = parsed.remove_all();
+        edits.extend(blocks.iter().map(|(_, block)| (at..at, block.as_str())));
+        let content = parsed.render(edits);
+
+        self.backup_ssh_config()?;
+        self.write_config(&content)
+    }
+
+    /// Fail if the SSH config already has an unmanaged block for the
+    /// profile's host alias
+    pub fn check_host_conflict(&self, profile: &Profile) -> Result<()> {
+        let content = self.read_config()?;
+        Self::check_unmanaged_host(&content, profile)
+    }
+
+    /// Read the SSH config, treating a missing file as empty
+    fn read_config(&self) -> Result<String> {
+        if !self.config_path.exists() {
+            return Ok(String::new());
+        }
+
+        fs::read_to_string(&self.config_path)
+            .map_err(|e| ProfileError::PermissionDenied(
+                format!("Failed to read SSH config: {}", e)
+            ))
+    }
+
+    /// Write the SSH config, or only report it when in dry-run mode
+    fn write_config(&self, content: &str) -> Result<()> {
+        if self.dry_run {
+            println!("[dry-run] Would write {}", self.config_path.display());
+            return Ok(());
+        }
+
+        fs::write(&self.config_path, Self::normalize(content))
             .map_err(|e| ProfileError::PermissionDenied(
                 format!("Failed to write SSH config: {}", e)
-            ))?;
+            ))
+    }
+
+    /// Canonical layout of the SSH config: no leading or repeated blank
+    /// lines, exactly one blank line before each managed block, and a
+    /// single trailing newline
+    fn normalize(content: &str) -> String {
+        let mut out = Normalizer::with_capacity(content.len());
+        content.lines().for_each(|line| out.push_line(line));
+        out.finish()
+    }
+
+    /// Extract the profile names from the managed block markers
+    fn managed_hosts_in_content(content: &str) -> Vec<String> {
+        content
+            .lines()
+            .filter_map(|line| line.strip_prefix(PROFILE_MARKER_PREFIX))
+            .map(|name| name.trim().to_string())
+            .collect()
+    }
+
+    /// Reject a profile whose host alias is already used by a block gex
+    /// doesn't manage, since ssh would use whichever block comes first
+    fn check_unmanaged_host(content: &str, profile: &Profile) -> Result<()> {
+        let host_marker = format!("{}{}", PROFILE_MARKER_PREFIX, profile.name);
+        let host_name = profile.ssh_host();
+        let mut previous = "";
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            let mut words = trimmed.split_whitespace();
+            let is_host_line = words
+                .next()
+                .is_some_and(|keyword| keyword.eq_ignore_ascii_case("Host"));
+
+            if is_host_line && words.any(|pattern| pattern == host_name) && previous != host_marker {
+                return Err(ProfileError::InvalidInput(format!(
+                    "SSH config already has an unmanaged 'Host {}' block; remove it or choose a different profile name",
+                    host_name
+                )));
+            }
+
+            if !trimmed.is_empty() {
+                previous = trimmed;
+            }
+        }
 
         Ok(())
     }
 
-    /// Update the config content with a new or updated host entry
-    fn update_config_content(&self, content: &str, profile: &Profile) -> Result<String> {
-        let host_marker = format!("# GitHub Profile: {}", profile.name);
-        let host_name = format!("github.com-{}", profile.name);
+    /// Build the host block gex would write for a profile
+    fn host_entry(profile: &Profile, identities_only: bool) -> Result<String> {
+        let host_marker = format!("{}{}", PROFILE_MARKER_PREFIX, profile.name);
+        let host_name = profile.ssh_host();
         let key_path = Self::get_ssh_key_path(&profile.ssh_key_name);
 
-        // Build the new host entry
-        let new_entry = format!(
-            "{}\nHost {}\n  HostName github.com\n  User git\n  IdentityFile {}\n  IdentitiesOnly yes\n",
-            host_marker,
-            host_name,
-            key_path.display()
-        );
+        // Values are written verbatim, so a line break would inject directives
+        let key_path_str = paths::require_utf8(&key_path, "SSH key")?;
+        if [profile.name.as_str(), key_path_str]
+            .iter()
+            .any(|value| value.chars().any(|c| c.is_control()))
+        {
+            return Err(ProfileError::InvalidInput(format!(
+                "Profile '{}' contains control characters and cannot be written to the SSH config",
+                profile.name.escape_default()
+            )));
+        }
 
-        // Check if this profile already has an entry
-        let lines: Vec<&str> = content.lines().collect();
-        let mut result = String::new();
-        let mut i = 0;
+        let mut entry = format!("{}\nHost {}\n  HostName {}\n", host_marker, host_name, profile.ssh_hostname());
+        if let Some(port) = profile.ssh_port {
+            entry.push_str(&format!("  Port {}\n", port));
+        }
+        entry.push_str(&format!("  User git\n  IdentityFile {}\n", key_path_str));
+        if identities_only {
+            entry.push_str("  IdentitiesOnly yes\n");
+        }
+        if profile.add_keys_to_agent {
+            entry.push_str("  AddKeysToAgent yes\n");
+        }
+        // Other platforms' ssh rejects the unknown option
+        if profile.use_keychain && cfg!(target_os = "macos") {
+            entry.push_str("  UseKeychain yes\n");
+        }
 
+        if entry.len() > MAX_HOST_ENTRY_LEN {
+            return Err(ProfileError::InvalidInput(format!(
+                "SSH host entry for '{}' exceeds {} bytes",
+                profile.name, MAX_HOST_ENTRY_LEN
+            )));
+        }
+
+        Ok(entry)
+    }
+
+    /// Return the index just past the managed block whose marker line
+    /// precedes `start`
+    fn block_end(lines: &[&str], start: usize) -> usize {
+        let mut i = start;
+
+        // The next line should be the Host line - skip it and all its properties
+        let mut in_host_block = false;
         while i < lines.len() {
-            if lines[i] == host_marker {
-                // Found existing entry, skip the entire block
+            let line = lines[i];
+
+            // If this is the Host line for this block, mark that we're in it
+            if line.starts_with("Host ") && !in_host_block {
+                in_host_block = true;
                 i += 1;
-                
-                // The next line should be the Host line - skip it and all its properties
-                let mut in_host_block = false;
-                while i < lines.len() {
-                    let line = lines[i];
-                    
-                    // If this is the Host line for this block, mark that we're in it
-                    if line.starts_with("Host ") && !in_host_block {
-                        in_host_block = true;
-                        i += 1;
-                        continue;
-                    }
-                    
-                    // If we're in the host block and hit an indented line, skip it
-                    if in_host_block && (line.starts_with("  ") || line.trim().is_empty()) {
-                        i += 1;
-                        continue;
-                    }
-                    
-                    // If we hit a comment or another Host line, we're done
-                    if line.trim().starts_with('#') || line.starts_with("Host ") {
-                        break;
-                    }
-                    
-                    // Skip empty lines between blocks
-                    if line.trim().is_empty() {
-                        i += 1;
-                        continue;
-                    }
-                    
-                    // Anything else means we're done with this block
-                    break;
-                }
-            } else {
-                result.push_str(lines[i]);
-                result.push('\n');
+                continue;
+            }
+
+            // If we're in the host block and hit an indented line, skip it
+            if in_host_block && (line.starts_with("  ") || line.trim().is_empty()) {
+                i += 1;
+                continue;
+            }
+
+            // If we hit a comment or another Host line, we're done
+            if line.trim().starts_with('#') || line.starts_with("Host ") {
+                break;
+            }
+
+            // Skip empty lines between blocks
+            if line.trim().is_empty() {
                 i += 1;
+                continue;
+            }
+
+            // Anything else means we're done with this block
+            break;
+        }
+
+        i
+    }
+
+    /// Return the installed block for a profile, marker line included
+    fn installed_block(content: &str, profile_name: &str) -> Option<String> {
+        let host_marker = format!("{}{}", PROFILE_MARKER_PREFIX, profile_name);
+        let parsed = ParsedConfig::parse(content);
+        let range = parsed.find(&host_marker)?;
+
+        let mut block = parsed.lines[range].join("\n");
+        block.truncate(block.trim_end().len());
+        block.push('\n');
+        Some(block)
+    }
+
+    /// Compare a profile's installed block with the one gex would generate
+    pub fn inspect_host(&self, content: &str, profile: &Profile) -> Result<HostState> {
+        let entry = Self::host_entry(profile, self.identities_only)?;
+        let Some(installed) = Self::installed_block(content, &profile.name) else {
+            return Ok(HostState::Missing { expected: entry });
+        };
+
+        // The user's own lines are kept on rewrite, so they aren't drift
+        let marker = format!("{}{}", PROFILE_MARKER_PREFIX, profile.name);
+        let expected = with_user_lines(entry, &ParsedConfig::parse(&installed).user_lines(&marker));
+        Ok(if installed == expected {
+            HostState::InSync
+        } else {
+            HostState::Differs { installed, expected }
+        })
+    }
+
+    /// Report how the SSH config differs from what `sync_hosts` would
+    /// write, without modifying anything
+    pub fn check_hosts(&self, profiles: &[Profile]) -> Result<Vec<HostDrift>> {
+        let profiles = Self::host_profiles(profiles);
+        let content = self.read_config()?;
+        let mut drift = Vec::new();
+
+        for profile in &profiles {
+            let state = self.inspect_host(&content, profile)?;
+            if state != HostState::InSync {
+                drift.push(HostDrift { name: profile.name.clone(), state });
             }
         }
 
-        // Add the new entry at the end
-        if !result.is_empty() && !result.ends_with("\n\n") {
-            result.push('\n');
+        for name in Self::managed_hosts_in_content(&content) {
+            if !profiles.iter().any(|p| p.name == name) {
+                drift.push(HostDrift { name, state: HostState::Orphaned });
+            }
         }
-        result.push_str(&new_entry);
 
-        Ok(result)
+        Ok(drift)
     }
 
-    /// Remove a host entry from the config content
-    fn remove_host_from_content(&self, content: &str, profile_name: &str) -> String {
-        let host_marker = format!("# GitHub Profile: {}", profile_name);
-        let lines: Vec<&str> = content.lines().collect();
-        let mut result = String::new();
-        let mut i = 0;
+    /// Update the config content with a new or updated host entry
+    fn update_config_content(&self, content: &str, profile: &Profile) -> Result<String> {
+        let host_marker = format!("{}{}", PROFILE_MARKER_PREFIX, profile.name);
+        let new_entry = Self::host_entry(profile, self.identities_only)?;
+        let new_entry = with_user_lines(new_entry, &ParsedConfig::parse(content).user_lines(&host_marker));
 
-        while i < lines.len() {
-            if lines[i] == host_marker {
-                // Found the entry to remove, skip the entire block
+        Self::check_unmanaged_host(content, profile)?;
+
+        self.place_entry(content, &host_marker, &new_entry)
+    }
+
+    /// Put `entry` in place of the block under `host_marker`, or at the
+    /// configured insertion point if there is none
+    fn place_entry(&self, content: &str, host_marker: &str, entry: &str) -> Result<String> {
+        let parsed = ParsedConfig::parse(content);
+
+        // Replace an existing entry where it is, so the file keeps its order
+        let range = match parsed.find(host_marker) {
+            Some(range) => range,
+            None => {
+                let at = self.insertion_point(&parsed)?;
+                at..at
+            }
+        };
+        Ok(parsed.render(vec![(range, entry)]))
+    }
+
+    /// Line index where new blocks go: the end of the configured section,
+    /// or the end of the file
+    fn insertion_point(&self, parsed: &ParsedConfig) -> Result<usize> {
+        match &self.section {
+            Some(section) => Self::section_end(&parsed.lines, section),
+            None => Ok(parsed.lines.len()),
+        }
+    }
+
+    /// Index where new blocks go for the configured section: after the
+    /// anchor line (and its body, if it is a Host/Match line) and after any
+    /// managed blocks already placed there
+    fn section_end(lines: &[&str], section: &str) -> Result<usize> {
+        let anchor = lines
+            .iter()
+            .position(|line| line.trim() == section.trim())
+            .ok_or_else(|| ProfileError::InvalidInput(format!(
+                "ssh_config_section line '{}' was not found in the SSH config; add it or run 'gex config ssh-config-section' to append at the end",
+                section
+            )))?;
+
+        let mut i = anchor + 1;
+        let header = lines[anchor].trim_start();
+        if header.starts_with("Host ") || header.starts_with("Match ") {
+            while i < lines.len() && (lines[i].starts_with(char::is_whitespace) || lines[i].trim().is_empty()) {
                 i += 1;
-                
-                // The next line should be the Host line - skip it and all its properties
-                let mut in_host_block = false;
-                while i < lines.len() {
-                    let line = lines[i];
-                    
-                    // If this is the Host line for this block, mark that we're in it
-                    if line.starts_with("Host ") && !in_host_block {
-                        in_host_block = true;
-                        i += 1;
-                        continue;
-                    }
-                    
-                    // If we're in the host block and hit an indented line, skip it
-                    if in_host_block && (line.starts_with("  ") || line.trim().is_empty()) {
-                        i += 1;
-                        continue;
-                    }
-                    
-                    // If we hit a comment or another Host line, we're done
-                    if line.trim().starts_with('#') || line.starts_with("Host ") {
-                        break;
-                    }
-                    
-                    // Skip empty lines between blocks
-                    if line.trim().is_empty() {
-                        i += 1;
-                        continue;
-                    }
-                    
-                    // Anything else means we're done with this block
-                    break;
-                }
-            } else {
-                result.push_str(lines[i]);
-                result.push('\n');
+            }
+        }
+
+        loop {
+            while i < lines.len() && lines[i].trim().is_empty() {
                 i += 1;
             }
+            if i < lines.len() && lines[i].starts_with(PROFILE_MARKER_PREFIX) {
+                i = Self::block_end(lines, i + 1);
+            } else {
+                break;
+            }
         }
+        Ok(i)
+    }
+
+    /// Remove a host entry from the config content
+    fn remove_host_from_content(&self, content: &str, profile_name: &str) -> String {
+        let host_marker = format!("{}{}", PROFILE_MARKER_PREFIX, profile_name);
+        let parsed = ParsedConfig::parse(content);
+        let edits = parsed
+            .blocks
+            .iter()
+            .filter(|(_, range)| parsed.lines[range.start] == host_marker)
+            .map(|(_, range)| (range.clone(), ""))
+            .collect();
+        parsed.render(edits)
+    }
+}
 
-        result
+/// Append lines carried over from the installed block to a generated one
+fn with_user_lines(mut entry: String, user_lines: &[&str]) -> String {
+    for line in user_lines {
+        entry.push_str(line);
+        entry.push('\n');
     }
+    entry
 }
 
 #[cfg(test)]
@@ -255,7 +772,13 @@ mod tests {
         fs::create_dir_all(&temp_dir).unwrap();
 
         let config_path = temp_dir.join("config");
-        let manager = SSHConfigManager { config_path };
+        let manager = SSHConfigManager {
+            config_path,
+            dry_run: false,
+            auto_create: true,
+            section: None,
+            identities_only: true,
+        };
 
         (manager, temp_dir)
     }
@@ -286,6 +809,30 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_auto_create_disabled() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        manager.set_auto_create(false);
+
+        let profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+
+        // A missing file is an error rather than being created
+        assert!(manager.add_or_update_host(&profile).is_err());
+        assert!(!manager.config_path.exists());
+
+        // An existing file is still updated
+        fs::write(&manager.config_path, "").unwrap();
+        manager.add_or_update_host(&profile).unwrap();
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(content.contains("Host github.com-work"));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
     #[test]
     fn test_add_host() {
         let (mut manager, temp_dir) = create_temp_ssh_manager();
@@ -295,6 +842,7 @@ mod tests {
             username: "john-doe".to_string(),
             email: "john@example.com".to_string(),
             ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
         };
 
         let result = manager.add_or_update_host(&profile);
@@ -320,6 +868,7 @@ mod tests {
             username: "john-work".to_string(),
             email: "john@work.com".to_string(),
             ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
         };
         manager.add_or_update_host(&profile1).unwrap();
 
@@ -329,6 +878,7 @@ mod tests {
             username: "john-work".to_string(),
             email: "john@work.com".to_string(),
             ssh_key_name: "id_ed25519_work".to_string(),
+            ..Default::default()
         };
         manager.add_or_update_host(&profile2).unwrap();
 
@@ -344,6 +894,43 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_rewrite_keeps_user_lines() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let mut profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        manager.add_or_update_host(&profile).unwrap();
+
+        // The user adds a note and a jump host inside the managed block
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        let edited = content.replace(
+            "  IdentitiesOnly yes\n",
+            "  IdentitiesOnly yes\n  # reached through the office bastion\n  ProxyJump bastion\n",
+        );
+        fs::write(&manager.config_path, &edited).unwrap();
+        assert!(manager.check_hosts(std::slice::from_ref(&profile)).unwrap().is_empty());
+
+        profile.ssh_key_name = "id_ed25519_work".to_string();
+        manager.add_or_update_host(&profile).unwrap();
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(content.contains("id_ed25519_work") && !content.contains("id_rsa_work"));
+        assert!(content.ends_with("  IdentitiesOnly yes\n  # reached through the office bastion\n  ProxyJump bastion\n"), "{}", content);
+
+        // Generated options are still gex's to set
+        manager.set_identities_only(false);
+        manager.sync_hosts(std::slice::from_ref(&profile)).unwrap();
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(!content.contains("IdentitiesOnly"));
+        assert_eq!(content.matches("ProxyJump bastion").count(), 1, "{}", content);
+        assert!(content.contains("  # reached through the office bastion\n"));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
     #[test]
     fn test_remove_host() {
         let (mut manager, temp_dir) = create_temp_ssh_manager();
@@ -354,12 +941,14 @@ mod tests {
             username: "john".to_string(),
             email: "john@personal.com".to_string(),
             ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
         };
         let profile2 = Profile {
             name: "work".to_string(),
             username: "john".to_string(),
             email: "john@work.com".to_string(),
             ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
         };
 
         manager.add_or_update_host(&profile1).unwrap();
@@ -397,6 +986,11 @@ mod tests {
         let backup_content = fs::read_to_string(&backup_path).unwrap();
         assert_eq!(backup_content, "test content");
 
+        // Restoring brings the backed-up content back
+        fs::write(&manager.config_path, "changed").unwrap();
+        manager.restore_backup().unwrap();
+        assert_eq!(fs::read_to_string(&manager.config_path).unwrap(), "test content");
+
         cleanup_temp_dir(&temp_dir);
     }
 
@@ -414,6 +1008,7 @@ mod tests {
             username: "john".to_string(),
             email: "john@example.com".to_string(),
             ssh_key_name: "id_rsa".to_string(),
+            ..Default::default()
         };
         manager.add_or_update_host(&profile).unwrap();
 
@@ -425,4 +1020,703 @@ mod tests {
 
         cleanup_temp_dir(&temp_dir);
     }
+
+    #[test]
+    fn test_list_managed_hosts() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        assert!(manager.list_managed_hosts().unwrap().is_empty());
+
+        fs::write(&manager.config_path, "Host myserver\n  HostName example.com\n").unwrap();
+        for name in ["personal", "work"] {
+            let profile = Profile {
+                name: name.to_string(),
+                ssh_key_name: format!("id_rsa_{}", name),
+                ..Default::default()
+            };
+            manager.add_or_update_host(&profile).unwrap();
+        }
+
+        assert_eq!(
+            manager.list_managed_hosts().unwrap(),
+            vec!["personal".to_string(), "work".to_string()]
+        );
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_sync_hosts() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let stale = Profile {
+            name: "old".to_string(),
+            ssh_key_name: "id_rsa_old".to_string(),
+            ..Default::default()
+        };
+        let work = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        fs::write(&manager.config_path, "Host myserver\n  HostName example.com\n\n").unwrap();
+        manager.add_or_update_host(&stale).unwrap();
+        manager.add_or_update_host(&work).unwrap();
+
+        // Manually edited block that should be reset
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        fs::write(&manager.config_path, content.replace("id_rsa_work", "id_hand_edited")).unwrap();
+
+        let personal = Profile {
+            name: "personal".to_string(),
+            ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
+        };
+        let report = manager.sync_hosts(&[work, personal]).unwrap();
+
+        assert_eq!(report.added, vec!["personal".to_string()]);
+        assert_eq!(report.updated, vec!["work".to_string()]);
+        assert_eq!(report.removed, vec!["old".to_string()]);
+
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(content.contains("Host myserver"));
+        assert!(content.contains("id_rsa_work"));
+        assert!(!content.contains("id_hand_edited"));
+        assert!(content.contains("Host github.com-personal"));
+        assert!(!content.contains("github.com-old"));
+
+        // The backup holds the pre-sync state
+        let backup = fs::read_to_string(manager.config_path.with_extension("config.bak")).unwrap();
+        assert!(backup.contains("id_hand_edited"));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_sync_hosts_unchanged_is_noop() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let work = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        let personal = Profile {
+            name: "personal".to_string(),
+            ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
+        };
+        let profiles = vec![work, personal.clone()];
+
+        fs::write(&manager.config_path, "Host myserver\n  HostName example.com\n").unwrap();
+        let report = manager.sync_hosts(&profiles).unwrap();
+        assert_eq!(report.added.len(), 2);
+
+        // A second sync finds identical blocks: nothing is reported, and
+        // neither the file nor its backup is written
+        let backup_path = manager.config_path.with_extension("config.bak");
+        fs::remove_file(&backup_path).unwrap();
+        let before = fs::read_to_string(&manager.config_path).unwrap();
+        let report = manager.sync_hosts(&profiles).unwrap();
+        assert!(report.added.is_empty() && report.updated.is_empty() && report.removed.is_empty());
+        assert_eq!(fs::read_to_string(&manager.config_path).unwrap(), before);
+        assert!(!backup_path.exists());
+
+        // Only the block that really changed counts as updated
+        let moved = Profile { ssh_port: Some(443), ..personal };
+        let report = manager.sync_hosts(&[profiles[0].clone(), moved]).unwrap();
+        assert_eq!(report.updated, vec!["personal".to_string()]);
+        assert!(backup_path.exists());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_sync_and_check_skip_commit_identity_only() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let work = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        let mut idonly = Profile {
+            name: "idonly".to_string(),
+            ssh_key_name: "id_rsa_idonly".to_string(),
+            commit_identity_only: true,
+            ..Default::default()
+        };
+        let profiles = vec![work.clone(), idonly.clone()];
+
+        fs::write(&manager.config_path, "").unwrap();
+        assert_eq!(manager.check_hosts(&profiles).unwrap()[0].name, "work");
+        let report = manager.sync_hosts(&profiles).unwrap();
+        assert_eq!(report.added, vec!["work".to_string()]);
+        assert!(!fs::read_to_string(&manager.config_path).unwrap().contains("github.com-idonly"));
+        assert!(manager.check_hosts(&profiles).unwrap().is_empty());
+
+        // A block left from before the setting was turned on is orphaned
+        idonly.commit_identity_only = false;
+        manager.add_or_update_host(&idonly).unwrap();
+        let drift = manager.check_hosts(&profiles).unwrap();
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].name, "idonly");
+        assert_eq!(drift[0].state, HostState::Orphaned);
+
+        let report = manager.sync_hosts(&profiles).unwrap();
+        assert_eq!(report.removed, vec!["idonly".to_string()]);
+        assert!(!fs::read_to_string(&manager.config_path).unwrap().contains("github.com-idonly"));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_check_hosts() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let work = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        let personal = Profile {
+            name: "personal".to_string(),
+            ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
+        };
+        let stale = Profile {
+            name: "old".to_string(),
+            ssh_key_name: "id_rsa_old".to_string(),
+            ..Default::default()
+        };
+        let profiles = vec![work.clone(), personal.clone()];
+
+        fs::write(&manager.config_path, "Host myserver\n  HostName example.com\n").unwrap();
+        manager.sync_hosts(&profiles).unwrap();
+        assert!(manager.check_hosts(&profiles).unwrap().is_empty());
+
+        manager.add_or_update_host(&stale).unwrap();
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        fs::write(&manager.config_path, content.replace("id_rsa_work", "id_hand_edited")).unwrap();
+        let before = fs::read_to_string(&manager.config_path).unwrap();
+
+        let drift = manager.check_hosts(&[work, personal, Profile {
+            name: "new".to_string(),
+            ssh_key_name: "id_rsa_new".to_string(),
+            ..Default::default()
+        }]).unwrap();
+
+        let names: Vec<&str> = drift.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["work", "new", "old"]);
+        assert!(matches!(&drift[0].state, HostState::Differs { installed, .. } if installed.contains("id_hand_edited")));
+        assert!(matches!(drift[1].state, HostState::Missing { .. }));
+        assert_eq!(drift[2].state, HostState::Orphaned);
+
+        // Checking never writes
+        assert_eq!(fs::read_to_string(&manager.config_path).unwrap(), before);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_host_entry_agent_options() {
+        let mut profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+
+        // Default output is unchanged
+        let entry = SSHConfigManager::host_entry(&profile, true).unwrap();
+        assert!(entry.ends_with("  IdentitiesOnly yes\n"));
+        assert!(!entry.contains("AddKeysToAgent"));
+        assert!(!entry.contains("UseKeychain"));
+
+        profile.add_keys_to_agent = true;
+        let entry = SSHConfigManager::host_entry(&profile, true).unwrap();
+        assert!(entry.ends_with("  IdentitiesOnly yes\n  AddKeysToAgent yes\n"));
+        assert!(!entry.contains("UseKeychain"));
+
+        profile.add_keys_to_agent = false;
+        profile.use_keychain = true;
+        let entry = SSHConfigManager::host_entry(&profile, true).unwrap();
+        assert!(!entry.contains("AddKeysToAgent"));
+        assert_eq!(entry.contains("  UseKeychain yes\n"), cfg!(target_os = "macos"));
+
+        profile.add_keys_to_agent = true;
+        let entry = SSHConfigManager::host_entry(&profile, true).unwrap();
+        assert!(entry.contains("  AddKeysToAgent yes\n"));
+        assert_eq!(entry.contains("  UseKeychain yes\n"), cfg!(target_os = "macos"));
+    }
+
+    #[test]
+    fn test_host_entry_ssh_port() {
+        let mut profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        let entry = SSHConfigManager::host_entry(&profile, true).unwrap();
+        assert!(entry.contains("  HostName github.com\n  User git\n"));
+        assert!(!entry.contains("Port"));
+
+        // Port 443 only works through ssh.github.com
+        profile.ssh_port = Some(443);
+        let entry = SSHConfigManager::host_entry(&profile, true).unwrap();
+        assert!(entry.contains("Host github.com-work\n  HostName ssh.github.com\n  Port 443\n  User git\n"));
+
+        profile.ssh_port = Some(2222);
+        let entry = SSHConfigManager::host_entry(&profile, true).unwrap();
+        assert!(entry.contains("  HostName github.com\n  Port 2222\n"));
+    }
+
+    #[test]
+    fn test_identities_only_setting() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        let profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+
+        manager.add_or_update_host(&profile).unwrap();
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(content.contains("  IdentitiesOnly yes\n"));
+
+        // Turning the setting off rewrites the block without the line
+        manager.set_identities_only(false);
+        assert!(matches!(
+            manager.check_hosts(std::slice::from_ref(&profile)).unwrap()[0].state,
+            HostState::Differs { .. }
+        ));
+        assert!(manager.add_or_update_host(&profile).unwrap());
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(!content.contains("IdentitiesOnly"));
+        assert!(content.contains("  IdentityFile "));
+        assert!(manager.check_hosts(&[profile]).unwrap().is_empty());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_add_host_with_agent_options_round_trips() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            add_keys_to_agent: true,
+            use_keychain: true,
+            ..Default::default()
+        };
+        manager.add_or_update_host(&profile).unwrap();
+        manager.add_or_update_host(&profile).unwrap();
+
+        // Rewriting replaces the whole block, extra lines included
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert_eq!(content.matches("AddKeysToAgent yes").count(), 1);
+        assert!(manager.check_hosts(&[profile]).unwrap().is_empty());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_unchanged_host_is_not_rewritten() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        let profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+
+        assert!(manager.add_or_update_host(&profile).unwrap());
+        let backup_path = manager.config_path.with_extension("config.bak");
+        let _ = fs::remove_file(&backup_path);
+        let modified = fs::metadata(&manager.config_path).unwrap().modified().unwrap();
+
+        assert!(!manager.add_or_update_host(&profile).unwrap());
+        assert_eq!(fs::metadata(&manager.config_path).unwrap().modified().unwrap(), modified);
+        assert!(!backup_path.exists());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_update_keeps_block_position() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        let work = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        let personal = Profile {
+            name: "personal".to_string(),
+            ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
+        };
+        manager.add_or_update_host(&work).unwrap();
+        manager.add_or_update_host(&personal).unwrap();
+
+        let updated = Profile {
+            ssh_key_name: "id_ed25519_work".to_string(),
+            ..work
+        };
+        manager.add_or_update_host(&updated).unwrap();
+
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        let work_at = content.find("id_ed25519_work").unwrap();
+        assert!(work_at < content.find("id_rsa_personal").unwrap());
+        assert!(manager.check_hosts(&[updated, personal]).unwrap().is_empty());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_section_insertion_point() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        fs::write(
+            &manager.config_path,
+            "Match host *.corp\n  User admin\n\n# gex profiles\n\nHost *\n  ServerAliveInterval 60\n",
+        )
+        .unwrap();
+        manager.set_section(Some("# gex profiles".to_string()));
+
+        let work = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+        let personal = Profile {
+            name: "personal".to_string(),
+            ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
+        };
+        manager.add_or_update_host(&work).unwrap();
+        manager.add_or_update_host(&personal).unwrap();
+
+        // Both blocks land, in order, between the anchor and `Host *`
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        let anchor = content.find("# gex profiles").unwrap();
+        let work_at = content.find("# GitHub Profile: work").unwrap();
+        let personal_at = content.find("# GitHub Profile: personal").unwrap();
+        let catch_all = content.find("Host *\n").unwrap();
+        assert!(anchor < work_at && work_at < personal_at && personal_at < catch_all);
+        assert!(manager.check_hosts(&[work.clone(), personal]).unwrap().is_empty());
+
+        // A Host/Match anchor puts blocks after that section's body
+        manager.set_section(Some("Match host *.corp".to_string()));
+        let oss = Profile {
+            name: "oss".to_string(),
+            ssh_key_name: "id_rsa_oss".to_string(),
+            ..Default::default()
+        };
+        manager.add_or_update_host(&oss).unwrap();
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        let oss_at = content.find("# GitHub Profile: oss").unwrap();
+        assert!(content.find("User admin").unwrap() < oss_at);
+        assert!(oss_at < content.find("# gex profiles").unwrap());
+
+        // A missing anchor is an error rather than a silent append
+        manager.set_section(Some("# nowhere".to_string()));
+        let missing = Profile { name: "missing".to_string(), ..work };
+        assert!(manager.add_or_update_host(&missing).is_err());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_restore_managed_blocks() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        let user_block = "Host example.com\n  User me\n";
+        fs::write(&manager.config_path, user_block).unwrap();
+
+        let profile = |name: &str| Profile {
+            name: name.to_string(),
+            ssh_key_name: format!("id_{}", name),
+            ..Default::default()
+        };
+        let (work, oss) = (profile("work"), profile("oss"));
+        manager.add_or_update_host(&work).unwrap();
+        let saved = manager.managed_blocks().unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].0, "work");
+
+        // A later change is rolled back to exactly the saved blocks
+        manager.remove_host("work").unwrap();
+        manager.add_or_update_host(&oss).unwrap();
+        manager.restore_managed_blocks(&saved).unwrap();
+
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(content.starts_with(user_block));
+        assert!(content.contains("# GitHub Profile: work"));
+        assert!(!content.contains("# GitHub Profile: oss"));
+        assert_eq!(manager.managed_blocks().unwrap(), saved);
+
+        let forged = vec![("work".to_string(), "Host evil\n".to_string())];
+        assert!(manager.restore_managed_blocks(&forged).is_err());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let messy = "\n\nHost a\n  User x\n\n\n\nHost b\n  User y\n# GitHub Profile: w\nHost github.com-w\n   \n\n";
+        let expected = "Host a\n  User x\n\nHost b\n  User y\n\n# GitHub Profile: w\nHost github.com-w\n";
+        assert_eq!(SSHConfigManager::normalize(messy), expected);
+        assert_eq!(SSHConfigManager::normalize(expected), expected);
+        assert_eq!(SSHConfigManager::normalize("\n \n"), "");
+    }
+
+    #[test]
+    fn test_repeated_updates_keep_whitespace_stable() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        fs::write(&manager.config_path, "Host example.com\n  User me\n\n\n").unwrap();
+
+        let profile = |name: &str, key: &str| Profile {
+            name: name.to_string(),
+            ssh_key_name: key.to_string(),
+            ..Default::default()
+        };
+        manager.add_or_update_host(&profile("work", "id_work")).unwrap();
+        manager.add_or_update_host(&profile("oss", "id_oss")).unwrap();
+        let first = fs::read_to_string(&manager.config_path).unwrap();
+
+        // Alternate between two keys so every call really rewrites the block
+        for i in 0..10 {
+            let key = if i % 2 == 0 { "id_work2" } else { "id_work" };
+            manager.add_or_update_host(&profile("work", key)).unwrap();
+        }
+
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert_eq!(content, first);
+        assert!(!content.contains("\n\n\n"));
+        assert!(content.ends_with("IdentitiesOnly yes\n"));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_parsed_config_blocks() {
+        let content = "Host a\n  User x\n\n# GitHub Profile: work\nHost github.com-work\n  User git\n\n# note\nHost b\n";
+        let parsed = ParsedConfig::parse(content);
+        assert_eq!(parsed.blocks, vec![("work".to_string(), 3..7)]);
+        assert_eq!(parsed.find("# GitHub Profile: work"), Some(3..7));
+        assert_eq!(parsed.find("# GitHub Profile: other"), None);
+
+        // No edits renders the normalized original
+        assert_eq!(parsed.render(Vec::new()), SSHConfigManager::normalize(content));
+        assert_eq!(
+            parsed.render(parsed.remove_all()),
+            "Host a\n  User x\n\n# note\nHost b\n"
+        );
+    }
+
+    #[test]
+    fn test_large_config_edits() {
+        let manager = SSHConfigManager {
+            config_path: PathBuf::from("unused"),
+            dry_run: true,
+            auto_create: false,
+            section: None,
+            identities_only: true,
+        };
+
+        // 5000 lines of user blocks with a managed block in the middle
+        let mut content = String::new();
+        for i in 0..1000 {
+            content.push_str(&format!("Host host{0}\n  HostName host{0}.example.com\n  User me\n  Port 22\n\n", i));
+            if i == 500 {
+                content.push_str("# GitHub Profile: work\nHost github.com-work\n  HostName github.com\n  User git\n  IdentityFile old\n\n");
+            }
+        }
+        let user_lines = content.lines().filter(|line| line.starts_with("Host host")).count();
+
+        for i in 0..200 {
+            let profile = Profile {
+                name: if i % 2 == 0 { "work".to_string() } else { format!("p{}", i % 10) },
+                ssh_key_name: format!("id_{}", i),
+                ..Default::default()
+            };
+            content = manager.update_config_content(&content, &profile).unwrap();
+        }
+
+        assert_eq!(content.lines().filter(|line| line.starts_with("Host host")).count(), user_lines);
+        assert_eq!(SSHConfigManager::managed_hosts_in_content(&content).len(), 6);
+        // The work block stays where it was
+        let work = content.find("# GitHub Profile: work").unwrap();
+        assert!(content[..work].contains("Host host500\n") && !content[..work].contains("Host host501\n"));
+        // The last edit to each block won, and only once
+        assert_eq!(content.matches("# GitHub Profile: work\n").count(), 1);
+        let block = &content[work..];
+        let block = &block[..block.find("\n\n").unwrap()];
+        assert!(block.contains(&format!("IdentityFile {}\n", SSHConfigManager::get_ssh_key_path("id_198").display())), "{}", block);
+        assert!(!content.contains("IdentityFile old\n"));
+    }
+
+    #[test]
+    fn test_prune_managed_hosts() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        // Nothing to prune in a missing file
+        assert!(manager.prune_managed_hosts().unwrap().is_empty());
+        assert!(!manager.config_path.exists());
+
+        fs::write(&manager.config_path, "Host myserver\n  HostName example.com\n").unwrap();
+        for name in ["work", "personal"] {
+            let profile = Profile {
+                name: name.to_string(),
+                ssh_key_name: format!("id_rsa_{}", name),
+                ..Default::default()
+            };
+            manager.add_or_update_host(&profile).unwrap();
+        }
+        let before = fs::read_to_string(&manager.config_path).unwrap();
+
+        let removed = manager.prune_managed_hosts().unwrap();
+        assert_eq!(removed, vec!["work".to_string(), "personal".to_string()]);
+        assert_eq!(
+            fs::read_to_string(&manager.config_path).unwrap(),
+            "Host myserver\n  HostName example.com\n"
+        );
+
+        // The backup holds the config as it was before pruning
+        let backup = fs::read_to_string(manager.config_path.with_extension("config.bak")).unwrap();
+        assert_eq!(backup, before);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_add_host_rejects_injected_lines() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let profile = Profile {
+            name: "evil".to_string(),
+            ssh_key_name: "id_rsa\n  ProxyCommand touch /tmp/pwned".to_string(),
+            ..Default::default()
+        };
+
+        let result = manager.add_or_update_host(&profile);
+        assert!(matches!(result, Err(ProfileError::InvalidInput(_))));
+
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(!content.contains("ProxyCommand"));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_add_host_rejects_oversized_entry() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let profile = Profile {
+            name: "big".to_string(),
+            ssh_key_name: "k".repeat(2000),
+            ..Default::default()
+        };
+
+        let result = manager.add_or_update_host(&profile);
+        assert!(matches!(result, Err(ProfileError::InvalidInput(_))));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_add_host_rejects_unmanaged_alias() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let profile = Profile {
+            name: "work".to_string(),
+            ssh_key_name: "id_rsa_work".to_string(),
+            ..Default::default()
+        };
+
+        let existing = "Host github.com-work\n  HostName github.com\n  IdentityFile ~/.ssh/other\n";
+        fs::write(&manager.config_path, existing).unwrap();
+
+        assert!(manager.check_host_conflict(&profile).is_err());
+        let result = manager.add_or_update_host(&profile);
+        assert!(matches!(result, Err(ProfileError::InvalidInput(_))));
+        assert_eq!(fs::read_to_string(&manager.config_path).unwrap(), existing);
+
+        // A gex-managed block with the same alias is not a conflict
+        fs::write(&manager.config_path, "").unwrap();
+        manager.add_or_update_host(&profile).unwrap();
+        assert!(manager.check_host_conflict(&profile).is_ok());
+        manager.add_or_update_host(&profile).unwrap();
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_config_without_trailing_newline() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+
+        let profile = Profile {
+            name: "personal".to_string(),
+            ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
+        };
+
+        // User content without a final newline must not merge with the new block
+        fs::write(&manager.config_path, "Host myserver\n  HostName example.com").unwrap();
+        manager.add_or_update_host(&profile).unwrap();
+        let content = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(content.starts_with("Host myserver\n  HostName example.com\n\n# GitHub Profile: personal\n"));
+        assert!(content.ends_with("IdentitiesOnly yes\n"));
+
+        // A managed block left last without a newline is still replaced in full
+        let truncated = content.trim_end_matches('\n').to_string();
+        fs::write(&manager.config_path, &truncated).unwrap();
+        manager.add_or_update_host(&profile).unwrap();
+        let updated = fs::read_to_string(&manager.config_path).unwrap();
+        assert_eq!(updated, content);
+
+        fs::write(&manager.config_path, &truncated).unwrap();
+        manager.remove_host("personal").unwrap();
+        let removed = fs::read_to_string(&manager.config_path).unwrap();
+        assert_eq!(removed, "Host myserver\n  HostName example.com\n");
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_dry_run_does_not_write() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        manager.set_dry_run(true);
+
+        let profile = Profile {
+            name: "personal".to_string(),
+            ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
+        };
+
+        // Nothing is created when the file doesn't exist yet
+        manager.add_or_update_host(&profile).unwrap();
+        assert!(!manager.config_path.exists());
+
+        // Existing content and backups are left untouched
+        fs::write(&manager.config_path, "Host myserver\n").unwrap();
+        manager.add_or_update_host(&profile).unwrap();
+        manager.sync_hosts(&[]).unwrap();
+        assert_eq!(fs::read_to_string(&manager.config_path).unwrap(), "Host myserver\n");
+        assert!(!manager.config_path.with_extension("config.bak").exists());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ssh_dir_is_a_file() {
+        let (mut manager, temp_dir) = create_temp_ssh_manager();
+        let ssh_file = temp_dir.join(".ssh");
+        fs::write(&ssh_file, "").unwrap();
+        manager.config_path = ssh_file.join("config");
+
+        let err = manager.ensure_ssh_config_exists().unwrap_err();
+        assert!(matches!(err, ProfileError::InvalidInput(_)));
+        assert!(err.to_string().contains("must be a directory"), "{}", err);
+
+        cleanup_temp_dir(&temp_dir);
+    }
 }
@@ -0,0 +1,188 @@
+use crate::error::{ProfileError, Result};
+use crate::ssh::config::SSHConfigManager;
+use crate::utils::validator::Validator;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Generate a new ed25519 key pair with `ssh-keygen`, letting it prompt
+/// for a passphrase. Never overwrites an existing key.
+pub fn generate_key(key_path: &Path, comment: &str) -> Result<()> {
+    if key_path.exists() {
+        return Err(ProfileError::InvalidInput(format!(
+            "{} already exists; refusing to overwrite it",
+            key_path.display()
+        )));
+    }
+
+    if let Some(parent) = key_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let status = match Command::new("ssh-keygen")
+        .args(["-t", "ed25519", "-C", comment, "-f"])
+        .arg(key_path)
+        .status()
+    {
+        Ok(status) => status,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ProfileError::InvalidInput(
+                "ssh-keygen not found; install OpenSSH to generate keys".to_string(),
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if !status.success() {
+        return Err(ProfileError::InvalidInput(format!(
+            "ssh-keygen failed for {}",
+            key_path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Default key name for a profile: `id_ed25519_<profile>`, with a numeric
+/// suffix if a key by that name already exists
+pub fn default_key_name(profile_name: &str) -> String {
+    free_key_name(&format!("id_ed25519_{}", profile_name), |name| {
+        SSHConfigManager::validate_ssh_key(name).unwrap_or(true)
+            || SSHConfigManager::validate_ssh_key(&format!("{}.pub", name)).unwrap_or(true)
+    })
+}
+
+/// First of `base`, `base_2`, `base_3`, ... for which `taken` is false
+fn free_key_name(base: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}_{}", base, n))
+        .find(|name| !taken(name))
+        .expect("unbounded range always yields a free name")
+}
+
+/// Files in `~/.ssh` that are never private keys
+const NON_KEY_FILES: &[&str] = &["config", "known_hosts", "known_hosts.old", "authorized_keys", "environment", "rc"];
+
+/// Names of the private key files in `dir`, sorted: regular files other
+/// than `.pub` files, hidden files and the usual non-key files
+pub fn list_private_keys(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut keys: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| {
+            !name.ends_with(".pub")
+                && !name.starts_with('.')
+                && !NON_KEY_FILES.contains(&name.as_str())
+                && Validator::validate_ssh_key_name(name)
+        })
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Extract the comment from a public key line ("<type> <base64> <comment>")
+pub fn public_key_comment(contents: &str) -> Option<String> {
+    let line = contents.lines().find(|line| !line.trim().is_empty())?;
+    let mut parts = line.trim().splitn(3, char::is_whitespace);
+    parts.next()?;
+    parts.next()?;
+    let comment = parts.next()?.trim();
+    (!comment.is_empty()).then(|| comment.to_string())
+}
+
+/// Read the email `ssh-keygen -C` stored in a key's `.pub` file, if the
+/// comment ends in a valid email address
+pub fn email_from_key(key_name: &str) -> Option<String> {
+    let pub_path = SSHConfigManager::get_ssh_key_path(&format!("{}.pub", key_name));
+    let contents = fs::read_to_string(pub_path).ok()?;
+    comment_email(&public_key_comment(&contents)?)
+}
+
+/// The trailing field of a key comment if it is an email, so comments
+/// like "John Doe <john@example.com>" work too
+fn comment_email(comment: &str) -> Option<String> {
+    let last = comment.split_whitespace().last()?;
+    let email = last.trim_start_matches('<').trim_end_matches('>');
+    Validator::validate_email(email).then(|| email.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_list_private_keys() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("gex_keylist_test_{}", nanos));
+        fs::create_dir_all(dir.join("sockets")).unwrap();
+        for name in ["id_work", "id_work.pub", "id_oss", "config", "known_hosts", "known_hosts.old", ".DS_Store"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(list_private_keys(&dir), vec!["id_oss", "id_work"]);
+        assert!(list_private_keys(&dir.join("missing")).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_public_key_comment() {
+        assert_eq!(
+            public_key_comment("ssh-ed25519 AAAAC3Nza me@example.com\n"),
+            Some("me@example.com".to_string())
+        );
+        assert_eq!(
+            public_key_comment("ssh-rsa AAAAB3Nza John Doe (laptop)"),
+            Some("John Doe (laptop)".to_string())
+        );
+        assert_eq!(public_key_comment("ssh-ed25519 AAAAC3Nza"), None);
+        assert_eq!(public_key_comment("ssh-ed25519 AAAAC3Nza   \n"), None);
+        assert_eq!(public_key_comment(""), None);
+    }
+
+    #[test]
+    fn test_free_key_name() {
+        assert_eq!(free_key_name("id_ed25519_work", |_| false), "id_ed25519_work");
+
+        let existing = ["id_ed25519_work", "id_ed25519_work_2"];
+        let name = free_key_name("id_ed25519_work", |name| existing.contains(&name));
+        assert_eq!(name, "id_ed25519_work_3");
+        assert!(Validator::validate_ssh_key_name(&name));
+    }
+
+    #[test]
+    fn test_comment_email() {
+        assert_eq!(comment_email("me@example.com"), Some("me@example.com".to_string()));
+        assert_eq!(
+            comment_email("John Doe <john@example.com>"),
+            Some("john@example.com".to_string())
+        );
+        assert_eq!(comment_email("john@laptop"), None);
+        assert_eq!(comment_email("John Doe (laptop)"), None);
+    }
+
+    #[test]
+    fn test_generate_key_refuses_existing_file() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let temp_dir = std::env::temp_dir().join(format!("gex_keygen_test_{}", timestamp));
+        fs::create_dir_all(&temp_dir).unwrap();
+        let key_path = temp_dir.join("id_ed25519_existing");
+        fs::write(&key_path, "original").unwrap();
+
+        assert!(generate_key(&key_path, "me@example.com").is_err());
+        assert_eq!(fs::read_to_string(&key_path).unwrap(), "original");
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+}
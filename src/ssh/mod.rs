@@ -1 +1,4 @@
+pub mod agent;
 pub mod config;
+pub mod keygen;
+pub mod verify;
@@ -0,0 +1,223 @@
+use crate::profile::Profile;
+use crate::ssh::config::SSHConfigManager;
+use regex::Regex;
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default number of probes run at the same time
+pub const DEFAULT_WORKERS: usize = 4;
+
+/// Outcome of probing GitHub with a profile's SSH key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeResult {
+    /// The key authenticated as this GitHub login
+    Authenticated(String),
+    /// The key did not authenticate, with the reason
+    Failed(String),
+}
+
+impl ProbeResult {
+    /// Whether the probe authenticated successfully
+    pub fn is_ok(&self) -> bool {
+        matches!(self, ProbeResult::Authenticated(_))
+    }
+}
+
+/// Extract the login from GitHub's `ssh -T` greeting ("Hi <login>! ...")
+pub fn parse_github_login(output: &str) -> Option<String> {
+    let greeting = Regex::new(r"Hi ([A-Za-z0-9-]+)!").unwrap();
+    greeting
+        .captures(output)
+        .map(|caps| caps[1].to_string())
+}
+
+/// Arguments for `ssh -T` against the host and port the profile's alias
+/// points at, offering only `key_path`
+fn probe_args(profile: &Profile, key_path: &Path, timeout: Duration) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["-T".into(), "-i".into(), key_path.into()];
+    for option in [
+        "IdentitiesOnly=yes".to_string(),
+        "BatchMode=yes".to_string(),
+        "StrictHostKeyChecking=accept-new".to_string(),
+        format!("ConnectTimeout={}", timeout.as_secs().max(1)),
+    ] {
+        args.push("-o".into());
+        args.push(option.into());
+    }
+    if let Some(port) = profile.ssh_port {
+        args.push("-p".into());
+        args.push(port.to_string().into());
+    }
+    args.push(format!("git@{}", profile.ssh_hostname()).into());
+    args
+}
+
+/// Run `ssh -T` against GitHub with only the profile's key, on the
+/// profile's port, killing the probe if it runs longer than `timeout`
+pub fn probe_profile(profile: &Profile, timeout: Duration) -> ProbeResult {
+    let key_path = SSHConfigManager::get_ssh_key_path(&profile.ssh_key_name);
+    if !key_path.exists() {
+        return ProbeResult::Failed(format!("key not found: {}", key_path.display()));
+    }
+
+    let child = Command::new("ssh")
+        .args(probe_args(profile, &key_path, timeout))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return ProbeResult::Failed(format!("could not run ssh: {}", e)),
+    };
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return ProbeResult::Failed(format!("timed out after {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => return ProbeResult::Failed(e.to_string()),
+        }
+    }
+
+    // GitHub replies on stderr and exits 1 even when authentication works
+    let mut output = String::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        let _ = stderr.read_to_string(&mut output);
+    }
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = stdout.read_to_string(&mut output);
+    }
+
+    match parse_github_login(&output) {
+        Some(login) => ProbeResult::Authenticated(login),
+        None => {
+            let reason = output.lines().last().unwrap_or("authentication failed").trim();
+            ProbeResult::Failed(reason.to_string())
+        }
+    }
+}
+
+/// Run `probe` for every profile using at most `workers` threads,
+/// returning results in the same order as `profiles`
+pub fn probe_all<F>(profiles: &[Profile], workers: usize, probe: F) -> Vec<ProbeResult>
+where
+    F: Fn(&Profile) -> ProbeResult + Sync,
+{
+    let next = Mutex::new(0usize);
+    let results = Mutex::new(vec![None; profiles.len()]);
+
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, profiles.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = {
+                    let mut next = next.lock().unwrap();
+                    let index = *next;
+                    *next += 1;
+                    index
+                };
+                let Some(profile) = profiles.get(index) else {
+                    break;
+                };
+                let result = probe(profile);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| ProbeResult::Failed("not probed".to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_parse_github_login() {
+        assert_eq!(
+            parse_github_login("Hi john-doe! You've successfully authenticated, but GitHub does not provide shell access."),
+            Some("john-doe".to_string())
+        );
+        assert_eq!(parse_github_login("git@github.com: Permission denied (publickey)."), None);
+    }
+
+    #[test]
+    fn test_probe_all_keeps_order_and_bounds_workers() {
+        let profiles: Vec<Profile> = (0..10)
+            .map(|i| Profile {
+                name: format!("p{}", i),
+                ..Default::default()
+            })
+            .collect();
+
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let results = probe_all(&profiles, 3, |profile| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            running.fetch_sub(1, Ordering::SeqCst);
+            ProbeResult::Authenticated(profile.name.clone())
+        });
+
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        for (profile, result) in profiles.iter().zip(&results) {
+            assert_eq!(result, &ProbeResult::Authenticated(profile.name.clone()));
+        }
+    }
+
+    #[test]
+    fn test_probe_args_use_profile_port() {
+        let mut profile = Profile {
+            name: "work".to_string(),
+            ..Default::default()
+        };
+        let key_path = Path::new("/home/john/.ssh/id_work");
+        let args = |profile: &Profile| -> Vec<String> {
+            probe_args(profile, key_path, Duration::from_secs(5))
+                .iter()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect()
+        };
+
+        let default = args(&profile);
+        assert_eq!(&default[..3], ["-T", "-i", "/home/john/.ssh/id_work"]);
+        assert!(default.contains(&"ConnectTimeout=5".to_string()));
+        assert!(!default.contains(&"-p".to_string()));
+        assert_eq!(default.last().unwrap(), "git@github.com");
+
+        profile.ssh_port = Some(443);
+        let firewalled = args(&profile);
+        assert_eq!(&firewalled[firewalled.len() - 3..], ["-p", "443", "git@ssh.github.com"]);
+    }
+
+    #[test]
+    fn test_probe_missing_key() {
+        let profile = Profile {
+            name: "missing".to_string(),
+            ssh_key_name: "gex_test_key_that_does_not_exist".to_string(),
+            ..Default::default()
+        };
+
+        let result = probe_profile(&profile, Duration::from_secs(1));
+        assert!(matches!(result, ProbeResult::Failed(reason) if reason.starts_with("key not found")));
+    }
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::PathBuf;
+use crate::error::{ProfileError, Result};
+use crate::storage::service::StorageService;
+use crate::storage::StorageData;
+use crate::utils::paths;
+use crate::utils::validator::Validator;
+
+/// Name of the built-in context backed by the original profiles file
+pub const DEFAULT_CONTEXT: &str = "default";
+
+/// Named, fully isolated profile sets ("contexts")
+pub struct ContextStore {
+    pub(crate) root: PathBuf,
+}
+
+impl ContextStore {
+    /// Create a new ContextStore rooted at `~/.config/gex`
+    pub fn new() -> Result<Self> {
+        let home_dir = paths::home_dir()
+            .ok_or_else(|| ProfileError::PermissionDenied("Could not determine home directory".to_string()))?;
+
+        Ok(Self {
+            root: home_dir.join(".config").join("gex"),
+        })
+    }
+
+    /// Path of the file holding the active context name
+    fn pointer_path(&self) -> PathBuf {
+        self.root.join("active_context")
+    }
+
+    /// Get the profiles file for a context
+    pub fn profiles_path(&self, name: &str) -> Result<PathBuf> {
+        if name == DEFAULT_CONTEXT {
+            return StorageService::get_config_path();
+        }
+        Ok(self.root.join("contexts").join(format!("{}.json", name)))
+    }
+
+    /// Get the name of the active context
+    pub fn active(&self) -> Result<String> {
+        match fs::read_to_string(self.pointer_path()) {
+            Ok(name) if !name.trim().is_empty() => Ok(name.trim().to_string()),
+            Ok(_) => Ok(DEFAULT_CONTEXT.to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DEFAULT_CONTEXT.to_string()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get the profiles file for the active context
+    pub fn active_profiles_path(&self) -> Result<PathBuf> {
+        let name = self.active()?;
+        if !self.exists(&name)? {
+            return Err(ProfileError::InvalidInput(format!(
+                "Active context '{}' no longer exists; run 'gex context use {}'",
+                name, DEFAULT_CONTEXT
+            )));
+        }
+        self.profiles_path(&name)
+    }
+
+    /// Check whether a context exists
+    pub fn exists(&self, name: &str) -> Result<bool> {
+        Ok(name == DEFAULT_CONTEXT || self.profiles_path(name)?.exists())
+    }
+
+    /// List all context names, with the default context first
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        let dir = self.root.join("contexts");
+
+        if dir.exists() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                        names.push(stem.to_string());
+                    }
+                }
+            }
+        }
+
+        names.sort();
+        names.insert(0, DEFAULT_CONTEXT.to_string());
+        Ok(names)
+    }
+
+    /// Create a new, empty context
+    pub fn create(&self, name: &str) -> Result<()> {
+        if !Validator::validate_profile_name(name) {
+            return Err(ProfileError::InvalidInput(
+                "Context name must contain only alphanumeric characters, hyphens, and underscores"
+                    .to_string(),
+            ));
+        }
+
+        if self.exists(name)? {
+            return Err(ProfileError::InvalidInput(format!("Context '{}' already exists", name)));
+        }
+
+        let storage = StorageService {
+            config_path: self.profiles_path(name)?,
+            dry_run: false,
+        };
+        storage.save(&StorageData::new())
+    }
+
+    /// Make a context the active one for all commands
+    pub fn set_active(&self, name: &str) -> Result<()> {
+        if !self.exists(name)? {
+            return Err(ProfileError::InvalidInput(format!(
+                "Context '{}' not found; create it with 'gex context create {}'",
+                name, name
+            )));
+        }
+
+        paths::ensure_dir(&self.root, "config directory")?;
+        fs::write(self.pointer_path(), name)
+            .map_err(|e| ProfileError::PermissionDenied(
+                format!("Failed to write active context: {}", e)
+            ))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Profile;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn create_temp_store() -> (ContextStore, PathBuf) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let temp_dir = std::env::temp_dir().join(format!("gex_context_test_{}", timestamp));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let store = ContextStore {
+            root: temp_dir.clone(),
+        };
+        (store, temp_dir)
+    }
+
+    fn cleanup_temp_dir(temp_dir: &PathBuf) {
+        if temp_dir.exists() {
+            let _ = fs::remove_dir_all(temp_dir);
+        }
+    }
+
+    #[test]
+    fn test_default_context() {
+        let (store, temp_dir) = create_temp_store();
+
+        assert_eq!(store.active().unwrap(), DEFAULT_CONTEXT);
+        assert_eq!(store.list().unwrap(), vec![DEFAULT_CONTEXT]);
+        assert!(store.create(DEFAULT_CONTEXT).is_err());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_create_and_use_context() {
+        let (store, temp_dir) = create_temp_store();
+
+        store.create("client-b").unwrap();
+        store.create("client-a").unwrap();
+        assert!(store.create("client-a").is_err());
+        assert!(store.create("bad name").is_err());
+        assert_eq!(store.list().unwrap(), vec!["default", "client-a", "client-b"]);
+
+        assert!(store.set_active("missing").is_err());
+        store.set_active("client-a").unwrap();
+        assert_eq!(store.active().unwrap(), "client-a");
+        assert_eq!(
+            store.active_profiles_path().unwrap(),
+            temp_dir.join("contexts").join("client-a.json")
+        );
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_contexts_are_isolated() {
+        let (store, temp_dir) = create_temp_store();
+
+        store.create("client-a").unwrap();
+        store.create("client-b").unwrap();
+
+        let open = |name: &str| StorageService {
+            config_path: store.profiles_path(name).unwrap(),
+            dry_run: false,
+        };
+
+        let mut data = open("client-a").load().unwrap();
+        data.profiles.push(Profile {
+            name: "work".to_string(),
+            ..Default::default()
+        });
+        open("client-a").save(&data).unwrap();
+
+        assert_eq!(open("client-a").load().unwrap().profiles.len(), 1);
+        assert!(open("client-b").load().unwrap().profiles.is_empty());
+
+        cleanup_temp_dir(&temp_dir);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::error::{ProfileError, Result};
+use age::armor::{ArmoredReader, ArmoredWriter, Format};
+use secrecy::Secret;
+use std::io::{Read, Write};
+
+/// Header line of an ASCII-armored age file
+const ARMOR_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// Encrypt an export blob with a passphrase, producing ASCII-armored output
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let encryptor = age::Encryptor::with_user_passphrase(Secret::new(passphrase.to_owned()));
+
+    let mut encrypted = Vec::new();
+    let armored = ArmoredWriter::wrap_output(&mut encrypted, Format::AsciiArmor)?;
+    let mut writer = encryptor
+        .wrap_output(armored)
+        .map_err(|e| ProfileError::InvalidInput(format!("Encryption failed: {}", e)))?;
+    writer.write_all(plaintext)?;
+    writer.finish().and_then(|armor| armor.finish())?;
+
+    Ok(encrypted)
+}
+
+/// Decrypt an export blob produced by [`encrypt`]
+pub fn decrypt(ciphertext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let decryptor = match age::Decryptor::new(ArmoredReader::new(ciphertext)) {
+        Ok(age::Decryptor::Passphrase(d)) => d,
+        Ok(age::Decryptor::Recipients(_)) => {
+            return Err(ProfileError::InvalidInput(
+                "Export file is encrypted to a key, not a passphrase".to_string(),
+            ))
+        }
+        Err(e) => {
+            return Err(ProfileError::InvalidInput(format!(
+                "Not a valid encrypted export: {}",
+                e
+            )))
+        }
+    };
+
+    let mut reader = decryptor
+        .decrypt(&Secret::new(passphrase.to_owned()), None)
+        .map_err(|e| match e {
+            age::DecryptError::DecryptionFailed | age::DecryptError::NoMatchingKeys => {
+                ProfileError::InvalidInput("Wrong passphrase for encrypted export".to_string())
+            }
+            e => ProfileError::InvalidInput(format!("Decryption failed: {}", e)),
+        })?;
+
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Check whether an export blob is an encrypted (armored age) file
+pub fn is_encrypted(contents: &[u8]) -> bool {
+    String::from_utf8_lossy(contents)
+        .trim_start()
+        .starts_with(ARMOR_HEADER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let plaintext = br#"{"version":"1.0.0","profiles":[]}"#;
+
+        let encrypted = encrypt(plaintext, "correct horse").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!is_encrypted(plaintext));
+
+        let decrypted = decrypt(&encrypted, "correct horse").unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_decrypt_wrong_passphrase() {
+        let encrypted = encrypt(b"secret", "right").unwrap();
+
+        match decrypt(&encrypted, "wrong") {
+            Err(ProfileError::InvalidInput(msg)) => assert!(msg.contains("Wrong passphrase")),
+            other => panic!("Expected wrong passphrase error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decrypt_plaintext_input() {
+        let result = decrypt(b"{ \"profiles\": [] }", "anything");
+        assert!(matches!(result, Err(ProfileError::InvalidInput(_))));
+    }
+}
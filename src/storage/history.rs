@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::{ProfileError, Result};
+use crate::git::ConfigScope;
+use crate::profile::Profile;
+use crate::storage::service::StorageService;
+use crate::utils::{paths, time};
+
+/// Longest note accepted by `gex switch --comment`
+pub const MAX_COMMENT_LEN: usize = 200;
+
+/// One line of the switch history
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub profile: String,
+    pub scope: String,
+    /// Repository the switch was made in (non-global scopes only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl HistoryEntry {
+    /// One-line rendering used by `gex history`
+    pub fn display(&self) -> String {
+        let mut line = format!(
+            "{}  {:<8} {}",
+            time::format_local(self.timestamp),
+            self.scope,
+            self.profile
+        );
+        if let Some(repo) = &self.repo {
+            line.push_str(&format!(" ({})", repo));
+        }
+        if let Some(comment) = &self.comment {
+            line.push_str(&format!(" — {}", comment));
+        }
+        line
+    }
+}
+
+/// Check a switch note: single line, printable, at most [`MAX_COMMENT_LEN`] characters
+pub fn validate_comment(comment: &str) -> Result<()> {
+    if comment.chars().any(char::is_control) {
+        return Err(ProfileError::InvalidInput(
+            "Switch comment must be a single line without control characters".to_string(),
+        ));
+    }
+    if comment.chars().count() > MAX_COMMENT_LEN {
+        return Err(ProfileError::InvalidInput(format!(
+            "Switch comment is longer than {} characters",
+            MAX_COMMENT_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Append-only log of profile switches, one JSON object per line
+pub struct SwitchHistory {
+    pub(crate) path: PathBuf,
+}
+
+impl SwitchHistory {
+    /// Open the history stored next to the default profiles file
+    pub fn new() -> Result<Self> {
+        let path = StorageService::get_config_path()?.with_file_name("history.jsonl");
+        Ok(Self { path })
+    }
+
+    /// Add an entry to the end of the log
+    pub fn append(&self, entry: &HistoryEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            paths::ensure_dir(parent, "config directory")?;
+        }
+
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| ProfileError::PermissionDenied(
+                format!("Failed to write switch history: {}", e)
+            ))
+    }
+
+    /// Log a switch to `profile` made just now, returning the entry written
+    pub fn record(&self, profile: &Profile, scope: ConfigScope, comment: Option<String>) -> Result<HistoryEntry> {
+        let repo = match scope {
+            ConfigScope::Global | ConfigScope::System => None,
+            _ => std::env::current_dir().ok().map(|dir| dir.display().to_string()),
+        };
+        let entry = HistoryEntry {
+            timestamp: Utc::now(),
+            profile: profile.name.clone(),
+            scope: scope.to_string(),
+            repo,
+            comment,
+        };
+        self.append(&entry)?;
+        Ok(entry)
+    }
+
+    /// Read every entry, oldest first, skipping lines that don't parse
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+/// When each profile was last switched to, according to the history
+pub fn last_used(entries: &[HistoryEntry]) -> HashMap<String, DateTime<Utc>> {
+    let mut last_used: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for entry in entries {
+        let latest = last_used.entry(entry.profile.clone()).or_insert(entry.timestamp);
+        *latest = (*latest).max(entry.timestamp);
+    }
+    last_used
+}
+
+/// Most recently used first; profiles never switched to go last, by name
+pub fn cmp_by_usage(a: &Profile, b: &Profile, last_used: &HashMap<String, DateTime<Utc>>) -> Ordering {
+    last_used
+        .get(&b.name)
+        .cmp(&last_used.get(&a.name))
+        .then_with(|| a.name.cmp(&b.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_append_and_read_entries() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("gex_history_test_{}", nanos));
+        let history = SwitchHistory { path: dir.join("history.jsonl") };
+        assert!(history.entries().unwrap().is_empty());
+
+        let entry = |profile: &str, comment: Option<&str>| HistoryEntry {
+            timestamp: Utc::now(),
+            profile: profile.to_string(),
+            scope: "global".to_string(),
+            repo: None,
+            comment: comment.map(str::to_string),
+        };
+        history.append(&entry("work", Some("onboarding ticket 1234"))).unwrap();
+        history.append(&entry("oss", None)).unwrap();
+
+        let entries = history.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].comment.as_deref(), Some("onboarding ticket 1234"));
+        assert!(entries[0].display().ends_with("work — onboarding ticket 1234"));
+        assert_eq!(entries[1].profile, "oss");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_record() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("gex_history_record_test_{}", nanos));
+        let history = SwitchHistory { path: dir.join("history.jsonl") };
+        let profile = Profile { name: "work".to_string(), ..Default::default() };
+
+        let global = history.record(&profile, ConfigScope::Global, None).unwrap();
+        let local = history.record(&profile, ConfigScope::Local, Some("pairing".to_string())).unwrap();
+        assert_eq!(global.repo, None);
+        assert!(local.repo.is_some());
+        assert_eq!(history.entries().unwrap(), vec![global, local.clone()]);
+        assert_eq!(last_used(&history.entries().unwrap())["work"], local.timestamp);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validate_comment() {
+        assert!(validate_comment("onboarding ticket 1234").is_ok());
+        assert!(validate_comment("two\nlines").is_err());
+        assert!(validate_comment(&"x".repeat(MAX_COMMENT_LEN)).is_ok());
+        assert!(validate_comment(&"x".repeat(MAX_COMMENT_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_sort_by_usage() {
+        let at = |minutes: i64| DateTime::from_timestamp(1_700_000_000 + minutes * 60, 0).unwrap();
+        let switch = |profile: &str, minutes| HistoryEntry {
+            timestamp: at(minutes),
+            profile: profile.to_string(),
+            scope: "local".to_string(),
+            repo: None,
+            comment: None,
+        };
+        let entries = vec![switch("work", 1), switch("oss", 2), switch("work", 3)];
+        let last_used = last_used(&entries);
+        assert_eq!(last_used["work"], at(3));
+
+        let mut profiles: Vec<Profile> = ["zeta", "oss", "archived", "work"]
+            .iter()
+            .map(|name| Profile { name: name.to_string(), ..Default::default() })
+            .collect();
+        profiles.sort_by(|a, b| cmp_by_usage(a, b, &last_used));
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["work", "oss", "archived", "zeta"]);
+    }
+}
@@ -1,14 +1,78 @@
+pub mod context;
+pub mod export;
+pub mod history;
+pub mod recover;
 pub mod service;
+pub mod snapshot;
 
 use serde::{Deserialize, Serialize};
 use crate::profile::Profile;
-use chrono::Utc;
+use crate::utils::time;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StorageData {
     pub version: String,
     pub profiles: Vec<Profile>,
     pub last_modified: String,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+/// User preferences stored alongside the profiles
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Settings {
+    /// Name of the TUI color theme
+    pub theme: String,
+    /// Maximum number of profiles that can be stored
+    pub max_profiles: usize,
+    /// Create `~/.ssh/config` when it's missing instead of failing
+    pub auto_create_ssh_config: bool,
+    /// Line in `~/.ssh/config` after which new host blocks are inserted;
+    /// None appends them at the end of the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_config_section: Option<String>,
+    /// Write `IdentitiesOnly yes` in generated SSH host blocks; agent-heavy
+    /// setups may want ssh to offer every agent key instead
+    pub ssh_identities_only: bool,
+}
+
+impl Settings {
+    /// Default for `max_profiles`
+    pub const DEFAULT_MAX_PROFILES: usize = 100;
+
+    /// Setting names as used by `gex config`
+    pub const KEYS: [&'static str; 5] = [
+        "theme",
+        "max-profiles",
+        "auto-create-ssh-config",
+        "ssh-config-section",
+        "ssh-identities-only",
+    ];
+
+    /// Get a setting's value by its `gex config` name
+    pub fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "theme" => Some(self.theme.clone()),
+            "max-profiles" => Some(self.max_profiles.to_string()),
+            "auto-create-ssh-config" => Some(self.auto_create_ssh_config.to_string()),
+            "ssh-config-section" => Some(self.ssh_config_section.clone().unwrap_or_default()),
+            "ssh-identities-only" => Some(self.ssh_identities_only.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: "default".to_string(),
+            max_profiles: Self::DEFAULT_MAX_PROFILES,
+            auto_create_ssh_config: true,
+            ssh_config_section: None,
+            ssh_identities_only: true,
+        }
+    }
 }
 
 impl StorageData {
@@ -17,13 +81,14 @@ impl StorageData {
         Self {
             version: "1.0.0".to_string(),
             profiles: Vec::new(),
-            last_modified: Utc::now().to_rfc3339(),
+            last_modified: time::now_rfc3339(),
+            settings: Settings::default(),
         }
     }
 
     /// Update the last modified timestamp
     pub fn touch(&mut self) {
-        self.last_modified = Utc::now().to_rfc3339();
+        self.last_modified = time::now_rfc3339();
     }
 }
 
@@ -32,3 +97,22 @@ impl Default for StorageData {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_get() {
+        let settings = Settings::default();
+        assert_eq!(settings.get("theme"), Some("default".to_string()));
+        assert_eq!(settings.get("max-profiles"), Some("100".to_string()));
+        assert_eq!(settings.get("auto-create-ssh-config"), Some("true".to_string()));
+        assert_eq!(settings.get("ssh-config-section"), Some(String::new()));
+        assert_eq!(settings.get("ssh-identities-only"), Some("true".to_string()));
+        assert_eq!(settings.get("unknown"), None);
+
+        // Every advertised key is readable
+        assert!(Settings::KEYS.iter().all(|key| settings.get(key).is_some()));
+    }
+}
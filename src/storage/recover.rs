@@ -0,0 +1,102 @@
+use crate::profile::Profile;
+
+/// Best-effort extraction of the profiles from a damaged profiles file.
+///
+/// Finds the `"profiles"` array and parses each complete `{...}` entry in
+/// it, stopping at the first truncated one. Entries that are complete but
+/// not valid profiles are skipped.
+pub fn salvage_profiles(contents: &str) -> Vec<Profile> {
+    let Some(key) = contents.find("\"profiles\"") else {
+        return Vec::new();
+    };
+    let Some(open) = contents[key..].find('[') else {
+        return Vec::new();
+    };
+
+    let mut profiles = Vec::new();
+    let mut depth = 0usize;
+    let mut start = None;
+    let mut in_string = false;
+    let mut escaped = false;
+    let array = &contents[key + open + 1..];
+
+    for (i, ch) in array.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_string = true,
+            '{' => {
+                if depth == 0 {
+                    start = Some(i);
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(start) = start.take() {
+                        if let Ok(profile) = serde_json::from_str::<Profile>(&array[start..=i]) {
+                            profiles.push(profile);
+                        }
+                    }
+                }
+            }
+            // End of the profiles array
+            ']' if depth == 0 => break,
+            _ => {}
+        }
+    }
+
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = r#"{
+  "version": "1.0",
+  "profiles": [
+    {"name": "work", "username": "john-work", "email": "john@work.com", "ssh_key_name": "id_work"},
+    {"name": "odd}name", "username": "x", "email": "x@y.io", "ssh_key_name": "id_\"x"},
+    {"name": "personal", "username": "john", "email": "john@me.com", "ssh_key_name": "id_me"}
+  ],
+  "last_modified": "2024-01-01T00:00:00Z"
+}"#;
+
+    #[test]
+    fn test_salvage_complete_file() {
+        let names: Vec<String> = salvage_profiles(FULL).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["work", "odd}name", "personal"]);
+    }
+
+    #[test]
+    fn test_salvage_truncated_file() {
+        // Cut in the middle of the last profile
+        let cut = FULL.find("\"john@me.com\"").unwrap();
+        let names: Vec<String> = salvage_profiles(&FULL[..cut])
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["work", "odd}name"]);
+    }
+
+    #[test]
+    fn test_salvage_skips_invalid_entries() {
+        let contents = r#"{"profiles": [{"name": "broken"}, {"name": "ok", "username": "u", "email": "u@x.io", "ssh_key_name": "k"}"#;
+        let profiles = salvage_profiles(contents);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "ok");
+
+        assert!(salvage_profiles("").is_empty());
+        assert!(salvage_profiles("{\"version\": \"1.0\"").is_empty());
+    }
+}
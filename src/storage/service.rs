@@ -1,113 +1,277 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
 use crate::error::{ProfileError, Result};
+use crate::storage::context::ContextStore;
 use crate::storage::StorageData;
+use crate::utils::paths;
+
+/// Just the profile names of a config file; every other field is skipped
+#[derive(Deserialize)]
+struct NamesOnly {
+    profiles: Vec<NameOnly>,
+}
+
+#[derive(Deserialize)]
+struct NameOnly {
+    name: String,
+}
+
+/// Times `load` re-reads an empty config file before calling it corrupted
+const EMPTY_READ_RETRIES: u32 = 5;
+
+/// Distinguishes temp files written by threads of the same process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Just the number of profiles of a config file
+#[derive(Deserialize)]
+struct CountOnly {
+    profiles: Vec<IgnoredAny>,
+}
 
 pub struct StorageService {
     pub(crate) config_path: PathBuf,
+    pub(crate) dry_run: bool,
 }
 
 impl StorageService {
-    /// Create a new StorageService instance
+    /// Create a new StorageService instance for the active context
     pub fn new() -> Result<Self> {
-        let config_path = Self::get_config_path()?;
-        Ok(Self { config_path })
+        let config_path = ContextStore::new()?.active_profiles_path()?;
+        paths::require_utf8(&config_path, "profiles file")?;
+        if let Some(dir) = config_path.parent() {
+            paths::ensure_config_dir(dir)?;
+        }
+        Ok(Self {
+            config_path,
+            dry_run: false,
+        })
     }
 
-    /// Get the platform-specific config file path
+    /// Log intended writes instead of touching the config file
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Get the platform-specific config file path of the default context
+    /// (under `GEX_CONFIG_DIR` when set)
     pub fn get_config_path() -> Result<PathBuf> {
-        let home_dir = dirs::home_dir()
+        let config_dir = paths::config_dir()
             .ok_or_else(|| ProfileError::PermissionDenied("Could not determine home directory".to_string()))?;
-        
-        let config_dir = home_dir.join(".github-profile-switcher");
-        let config_file = config_dir.join("profiles.json");
-        
-        Ok(config_file)
+
+        Ok(config_dir.join("profiles.json"))
     }
 
     /// Ensure the config directory and file exist
     pub fn ensure_config_exists(&self) -> Result<()> {
         // Get the parent directory (config directory)
         if let Some(parent) = self.config_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| ProfileError::PermissionDenied(
-                        format!("Failed to create config directory: {}", e)
-                    ))?;
-            }
+            paths::ensure_dir(parent, "config directory")?;
         }
 
         // Create the config file if it doesn't exist
         if !self.config_path.exists() {
-            let initial_data = StorageData::new();
-            self.save(&initial_data)?;
+            self.create_if_absent(&StorageData::new())?;
         }
 
         Ok(())
     }
 
+    /// Write `data` as the config file only if no file exists, so a
+    /// process racing another first run never replaces real profiles.
+    /// The file is written aside and hard-linked into place, so it never
+    /// appears half-written either.
+    fn create_if_absent(&self, data: &StorageData) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let temp = write_temp(&self.config_path, data)?;
+        let linked = fs::hard_link(&temp, &self.config_path);
+        let _ = fs::remove_file(&temp);
+
+        match linked {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(()),
+            // Filesystems without hard links: exclusive create still never
+            // touches an existing file
+            Err(_) => {
+                let file = match fs::OpenOptions::new().write(true).create_new(true).open(&self.config_path) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(()),
+                    Err(e) => return Err(write_error(e)),
+                };
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer_pretty(&mut writer, data)?;
+                writer.flush().map_err(write_error)
+            }
+        }
+    }
+
     /// Load profile data from the config file
     pub fn load(&self) -> Result<StorageData> {
+        // A dry run must not create the config file on first use
+        if self.dry_run && !self.config_path.exists() {
+            return Ok(StorageData::new());
+        }
+
         // Ensure config exists before loading
         self.ensure_config_exists()?;
 
-        // Read the file
-        let contents = fs::read_to_string(&self.config_path)
-            .map_err(|e| ProfileError::PermissionDenied(
-                format!("Failed to read config file: {}", e)
-            ))?;
+        // Read the file. An empty file is most likely another process
+        // mid-write with an older gex, so give it a moment before
+        // reporting corruption.
+        let mut contents = self.read_config()?;
+        for _ in 0..EMPTY_READ_RETRIES {
+            if !contents.trim().is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+            contents = self.read_config()?;
+        }
 
         // Parse JSON
         let data: StorageData = serde_json::from_str(&contents)
             .map_err(|_| ProfileError::ConfigCorrupted)?;
 
+        // A hand edit can leave an entry without a name; refuse it rather
+        // than let it break host aliases and SSH config markers later
+        for (index, profile) in data.profiles.iter().enumerate() {
+            if profile.validate_name_present().is_err() {
+                return Err(ProfileError::InvalidInput(format!(
+                    "profiles[{}] (email '{}') in {} has an empty name; give it a name or remove the entry",
+                    index,
+                    profile.email,
+                    self.config_path.display()
+                )));
+            }
+        }
+
         Ok(data)
     }
 
+    fn read_config(&self) -> Result<String> {
+        fs::read_to_string(&self.config_path)
+            .map_err(|e| ProfileError::PermissionDenied(
+                format!("Failed to read config file: {}", e)
+            ))
+    }
+
+    /// Read the raw config file, or None if it doesn't exist yet
+    fn read_raw(&self) -> Result<Option<Vec<u8>>> {
+        if !self.config_path.exists() {
+            return Ok(None);
+        }
+        fs::read(&self.config_path)
+            .map(Some)
+            .map_err(|e| ProfileError::PermissionDenied(
+                format!("Failed to read config file: {}", e)
+            ))
+    }
+
+    /// List profile names without deserializing whole profiles
+    pub fn profile_names(&self) -> Result<Vec<String>> {
+        let Some(contents) = self.read_raw()? else {
+            return Ok(Vec::new());
+        };
+        let names: NamesOnly = serde_json::from_slice(&contents)
+            .map_err(|_| ProfileError::ConfigCorrupted)?;
+        Ok(names.profiles.into_iter().map(|p| p.name).collect())
+    }
+
+    /// Count profiles without deserializing them
+    pub fn profile_count(&self) -> Result<usize> {
+        let Some(contents) = self.read_raw()? else {
+            return Ok(0);
+        };
+        let count: CountOnly = serde_json::from_slice(&contents)
+            .map_err(|_| ProfileError::ConfigCorrupted)?;
+        Ok(count.profiles.len())
+    }
+
     /// Save profile data to the config file
     pub fn save(&self, data: &StorageData) -> Result<()> {
+        if self.dry_run {
+            println!("[dry-run] Would write profiles to {}", self.config_path.display());
+            return Ok(());
+        }
+
         // Ensure parent directory exists
         if let Some(parent) = self.config_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| ProfileError::PermissionDenied(
-                        format!("Failed to create config directory: {}", e)
-                    ))?;
-            }
+            paths::ensure_dir(parent, "config directory")?;
         }
 
-        // Serialize to pretty JSON
-        let json = serde_json::to_string_pretty(data)?;
-
-        // Write to file
-        fs::write(&self.config_path, json)
-            .map_err(|e| ProfileError::PermissionDenied(
-                format!("Failed to write config file: {}", e)
-            ))?;
+        // Stream pretty JSON to a temp file and rename it over the config,
+        // so concurrent readers see the old or the new file, never a
+        // truncated one. A symlinked config is replaced at its target.
+        let target = fs::canonicalize(&self.config_path).unwrap_or_else(|_| self.config_path.clone());
+        let temp = write_temp(&target, data)?;
+        if let Ok(metadata) = fs::metadata(&target) {
+            let _ = fs::set_permissions(&temp, metadata.permissions());
+        }
+        fs::rename(&temp, &target).map_err(|e| {
+            let _ = fs::remove_file(&temp);
+            write_error(e)
+        })?;
 
         Ok(())
     }
 
-    /// Validate the config file structure
-    pub fn validate_config(&self) -> Result<bool> {
+    /// Read the raw config file, if it exists
+    pub fn load_raw(&self) -> Result<Option<String>> {
         if !self.config_path.exists() {
-            return Ok(false);
+            return Ok(None);
         }
 
-        // Try to load and parse the config
-        match self.load() {
-            Ok(_) => Ok(true),
-            Err(ProfileError::ConfigCorrupted) => Ok(false),
-            Err(e) => Err(e),
-        }
+        fs::read_to_string(&self.config_path)
+            .map(Some)
+            .map_err(|e| ProfileError::PermissionDenied(
+                format!("Failed to read config file: {}", e)
+            ))
     }
 
-    /// Get the config file path
-    pub fn config_path(&self) -> &PathBuf {
-        &self.config_path
+    /// Copy the config file aside (e.g. before overwriting a corrupted one)
+    pub fn backup_to(&self, extension: &str) -> Result<PathBuf> {
+        let backup_path = self.config_path.with_extension(extension);
+        if !self.dry_run {
+            fs::copy(&self.config_path, &backup_path)?;
+        }
+        Ok(backup_path)
     }
 }
 
+/// Write `data` to a fresh temp file next to `path`
+fn write_temp(path: &Path, data: &StorageData) -> Result<PathBuf> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    let temp = path.with_extension(format!(
+        "json.{}.{}.{}.tmp",
+        std::process::id(),
+        nanos,
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let file = fs::File::create(&temp).map_err(write_error)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, data)?;
+    writer.flush().map_err(write_error)?;
+    Ok(temp)
+}
+
+fn write_error(e: std::io::Error) -> ProfileError {
+    let hint = match e.kind() {
+        ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => format!(
+            ". Set {} to a writable directory if this location is read-only",
+            paths::CONFIG_DIR_ENV
+        ),
+        _ => String::new(),
+    };
+    ProfileError::PermissionDenied(format!("Failed to write config file: {}{}", e, hint))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,6 +297,7 @@ mod tests {
         
         let service = StorageService {
             config_path: config_path.clone(),
+            dry_run: false,
         };
         
         (service, temp_dir)
@@ -145,6 +310,80 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_config_dir_is_a_file() {
+        let (mut service, temp_dir) = create_temp_service();
+        let blocker = temp_dir.join(".github-profile-switcher");
+        fs::write(&blocker, "").unwrap();
+        service.config_path = blocker.join("profiles.json");
+
+        let err = service.load().unwrap_err();
+        assert!(matches!(err, ProfileError::InvalidInput(_)));
+        assert!(err.to_string().contains("must be a directory"), "{}", err);
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_concurrent_first_run_loads() {
+        let (service, temp_dir) = create_temp_service();
+        let config_path = service.config_path.clone();
+        let threads = 16;
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(threads + 1));
+
+        let mut data = StorageData::new();
+        data.profiles.push(Profile {
+            name: "work".to_string(),
+            username: "john-work".to_string(),
+            email: "john@company.com".to_string(),
+            ssh_key_name: "id_work".to_string(),
+            ..Default::default()
+        });
+
+        let loaders: Vec<_> = (0..threads)
+            .map(|_| {
+                let barrier = barrier.clone();
+                let config_path = config_path.clone();
+                std::thread::spawn(move || {
+                    let service = StorageService { config_path, dry_run: false };
+                    barrier.wait();
+                    (0..20).map(|_| service.load().map(|_| ())).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        // Real data saved while the other "processes" are on their first run
+        barrier.wait();
+        service.save(&data).unwrap();
+
+        for loader in loaders {
+            for result in loader.join().unwrap() {
+                assert!(result.is_ok(), "load failed: {:?}", result);
+            }
+        }
+
+        // Nobody replaced the saved profiles with an empty file
+        assert_eq!(service.load().unwrap().profiles, data.profiles);
+        let leftovers = fs::read_dir(&temp_dir).unwrap().count();
+        assert_eq!(leftovers, 1, "temp files were left behind");
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_ensure_config_exists_keeps_existing_file() {
+        let (service, temp_dir) = create_temp_service();
+
+        // Even an empty file is left alone rather than overwritten
+        fs::write(&service.config_path, "").unwrap();
+        service.ensure_config_exists().unwrap();
+        assert_eq!(fs::read_to_string(&service.config_path).unwrap(), "");
+        assert!(matches!(service.load(), Err(ProfileError::ConfigCorrupted)));
+
+        cleanup_temp_dir(&temp_dir);
+    }
+
     #[test]
     fn test_save_and_load_empty_profiles() {
         let (service, temp_dir) = create_temp_service();
@@ -178,12 +417,14 @@ mod tests {
             username: "john-doe".to_string(),
             email: "john@personal.com".to_string(),
             ssh_key_name: "id_rsa_personal".to_string(),
+            ..Default::default()
         });
         data.profiles.push(Profile {
             name: "work".to_string(),
             username: "john-work".to_string(),
             email: "john@company.com".to_string(),
             ssh_key_name: "id_ed25519_work".to_string(),
+            ..Default::default()
         });
         
         // Save it
@@ -246,48 +487,37 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_config_with_valid_file() {
+    fn test_load_file_without_settings() {
         let (service, temp_dir) = create_temp_service();
-        
-        // Create valid config
-        let data = StorageData::new();
-        service.save(&data).unwrap();
-        
-        // Validate should return true
-        let is_valid = service.validate_config().unwrap();
-        assert!(is_valid);
-        
-        cleanup_temp_dir(&temp_dir);
-    }
 
-    #[test]
-    fn test_validate_config_with_missing_file() {
-        let (service, temp_dir) = create_temp_service();
-        
-        // File doesn't exist
-        assert!(!service.config_path.exists());
-        
-        // Validate should return false
-        let is_valid = service.validate_config().unwrap();
-        assert!(!is_valid);
-        
+        // Files written before settings existed must still load
+        let legacy = r#"{"version":"1.0.0","profiles":[],"last_modified":"2024-01-01T00:00:00+00:00"}"#;
+        fs::write(&service.config_path, legacy).unwrap();
+
+        let loaded = service.load().unwrap();
+        assert_eq!(loaded.settings, crate::storage::Settings::default());
+
         cleanup_temp_dir(&temp_dir);
     }
 
     #[test]
-    fn test_validate_config_with_corrupted_file() {
+    fn test_load_rejects_blank_profile_name() {
         let (service, temp_dir) = create_temp_service();
-        
-        // Create directory and write invalid JSON
-        if let Some(parent) = service.config_path.parent() {
-            fs::create_dir_all(parent).unwrap();
+
+        let file = r#"{"version":"1.0.0","last_modified":"2024-01-01T00:00:00+00:00","profiles":[
+            {"name":"work","username":"john-work","email":"john@work.com","ssh_key_name":"id_work"},
+            {"name":"  ","username":"john-doe","email":"john@example.com","ssh_key_name":"id_home"}
+        ]}"#;
+        fs::write(&service.config_path, file).unwrap();
+
+        match service.load() {
+            Err(ProfileError::InvalidInput(message)) => {
+                assert!(message.starts_with("profiles[1] (email 'john@example.com')"), "{}", message);
+                assert!(message.contains("has an empty name"), "{}", message);
+            }
+            result => panic!("Expected InvalidInput, got: {:?}", result),
         }
-        fs::write(&service.config_path, "corrupted").unwrap();
-        
-        // Validate should return false
-        let is_valid = service.validate_config().unwrap();
-        assert!(!is_valid);
-        
+
         cleanup_temp_dir(&temp_dir);
     }
 
@@ -302,6 +532,7 @@ mod tests {
             username: "testuser".to_string(),
             email: "test@example.com".to_string(),
             ssh_key_name: "id_rsa".to_string(),
+            ..Default::default()
         });
         
         service.save(&data).unwrap();
@@ -317,4 +548,24 @@ mod tests {
         
         cleanup_temp_dir(&temp_dir);
     }
+
+    #[test]
+    fn test_dry_run_does_not_write() {
+        let (mut service, temp_dir) = create_temp_service();
+        service.set_dry_run(true);
+
+        // Loading must not create the file
+        let mut data = service.load().unwrap();
+        assert!(data.profiles.is_empty());
+        assert!(!service.config_path.exists());
+
+        data.profiles.push(Profile {
+            name: "test".to_string(),
+            ..Default::default()
+        });
+        service.save(&data).unwrap();
+        assert!(!service.config_path.exists());
+
+        cleanup_temp_dir(&temp_dir);
+    }
 }
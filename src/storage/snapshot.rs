@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::error::{ProfileError, Result};
+use crate::storage::service::StorageService;
+use crate::storage::StorageData;
+use crate::utils::{paths, time};
+
+/// A gex-managed SSH host block saved in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SshBlock {
+    /// Profile the block belongs to
+    pub name: String,
+    /// The block as written to `~/.ssh/config`, marker line included
+    pub block: String,
+}
+
+/// Full gex state written by `gex backup create`: the profiles file and
+/// every managed SSH host block
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub created_at: String,
+    pub storage: StorageData,
+    pub ssh_blocks: Vec<SshBlock>,
+}
+
+impl Snapshot {
+    /// Bundle the current profiles file and managed blocks
+    pub fn new(storage: StorageData, ssh_blocks: Vec<(String, String)>) -> Self {
+        Self {
+            created_at: time::now_rfc3339(),
+            storage,
+            ssh_blocks: ssh_blocks
+                .into_iter()
+                .map(|(name, block)| SshBlock { name, block })
+                .collect(),
+        }
+    }
+
+    /// Default archive directory: `backups/` next to the profiles file
+    pub fn default_dir() -> Result<PathBuf> {
+        let config_path = StorageService::get_config_path()?;
+        Ok(config_path.with_file_name("backups"))
+    }
+
+    /// Timestamped archive file name
+    pub fn file_name() -> String {
+        format!("gex-backup-{}.json", Utc::now().format("%Y%m%d-%H%M%S"))
+    }
+
+    /// Write the snapshot to a new archive in `dir`, returning its path
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        paths::ensure_dir(dir, "backup directory")?;
+
+        let path = dir.join(Self::file_name());
+        if path.exists() {
+            return Err(ProfileError::InvalidInput(format!(
+                "Backup {} already exists; wait a second and try again",
+                path.display()
+            )));
+        }
+
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .map_err(|e| ProfileError::PermissionDenied(
+                format!("Failed to write backup: {}", e)
+            ))?;
+        Ok(path)
+    }
+
+    /// Read an archive written by [`Snapshot::write`]
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read(path)
+            .map_err(|e| ProfileError::InvalidInput(
+                format!("Failed to read backup {}: {}", path.display(), e)
+            ))?;
+        serde_json::from_slice(&contents)
+            .map_err(|e| ProfileError::InvalidInput(
+                format!("{} is not a gex backup: {}", path.display(), e)
+            ))
+    }
+
+    /// The SSH blocks as `(profile name, block)` pairs
+    pub fn ssh_block_pairs(&self) -> Vec<(String, String)> {
+        self.ssh_blocks
+            .iter()
+            .map(|b| (b.name.clone(), b.block.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Profile;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+        let dir = std::env::temp_dir().join(format!("gex_snapshot_test_{}", nanos));
+
+        let mut storage = StorageData::new();
+        storage.profiles.push(Profile {
+            name: "work".to_string(),
+            ..Default::default()
+        });
+        let block = "# GitHub Profile: work\nHost github.com-work\n".to_string();
+        let snapshot = Snapshot::new(storage, vec![("work".to_string(), block.clone())]);
+
+        let path = snapshot.write(&dir).unwrap();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("gex-backup-"));
+
+        let read = Snapshot::read(&path).unwrap();
+        assert_eq!(read.storage.profiles.len(), 1);
+        assert_eq!(read.ssh_block_pairs(), vec![("work".to_string(), block)]);
+
+        fs::write(&path, "{}").unwrap();
+        assert!(matches!(Snapshot::read(&path), Err(ProfileError::InvalidInput(_))));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,19 +1,58 @@
 use crate::error::{ProfileError, Result};
 use crate::git::config::GitConfigManager;
-use crate::git::ConfigScope;
+use crate::git::remote;
+use crate::git::{ConfigScope, HeadState};
 use crate::profile::manager::ProfileManager;
 use crate::profile::Profile;
+use crate::ssh::agent;
 use crate::ssh::config::SSHConfigManager;
 
+/// Print a switch progress line unless the switch is quiet
+macro_rules! progress {
+    ($options:expr, $($arg:tt)*) => {
+        if !$options.quiet {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Print a line the user must see (warnings, the profile's note): on
+/// stdout with the progress lines, or on stderr when quiet so stdout stays
+/// clean for scripts
+macro_rules! notice {
+    ($options:expr, $($arg:tt)*) => {
+        if $options.quiet {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
 pub struct ProfileSwitcher {
     profile_manager: ProfileManager,
     ssh_config: SSHConfigManager,
+    git_config: GitConfigManager,
+}
+
+/// Options that adjust how a profile switch is performed
+#[derive(Debug, Default, Clone)]
+pub struct SwitchOptions {
+    /// Skip checking that the SSH key file exists on disk
+    /// (e.g. keys that only live in an agent or hardware token)
+    pub no_verify_key: bool,
+    /// Apply only user.name/user.email/signing, leaving SSH alone
+    pub commit_identity_only: bool,
+    /// Don't print progress steps or the summary; warnings go to stderr
+    pub quiet: bool,
 }
 
 #[derive(Debug)]
 pub struct ProfileStatus {
     pub global: Option<Profile>,
     pub local: Option<Profile>,
+    /// HEAD of the current repository; None outside a repository
+    pub local_head: Option<HeadState>,
 }
 
 impl ProfileSwitcher {
@@ -25,42 +64,143 @@ impl ProfileSwitcher {
         Ok(Self {
             profile_manager,
             ssh_config,
+            git_config: GitConfigManager::new(),
         })
     }
 
+    /// Log intended changes instead of writing profiles, git config or
+    /// SSH config
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.profile_manager.set_dry_run(dry_run);
+        self.ssh_config.set_dry_run(dry_run);
+        self.git_config.set_dry_run(dry_run);
+    }
+
+    /// Whether a missing SSH config may be created when switching
+    pub fn set_auto_create_ssh_config(&mut self, auto_create: bool) {
+        self.ssh_config.set_auto_create(auto_create);
+    }
+
+    /// Line in the SSH config after which new host blocks are inserted
+    pub fn set_ssh_config_section(&mut self, section: Option<String>) {
+        self.ssh_config.set_section(section);
+    }
+
+    /// Whether host blocks written on switch include `IdentitiesOnly yes`
+    pub fn set_ssh_identities_only(&mut self, identities_only: bool) {
+        self.ssh_config.set_identities_only(identities_only);
+    }
+
     /// Switch to a profile with the specified scope
     pub fn switch_profile(&mut self, profile_name: &str, scope: ConfigScope) -> Result<()> {
-        println!("Switching to profile '{}'...", profile_name);
+        self.switch_profile_with_options(profile_name, scope, &SwitchOptions::default())
+    }
+
+    /// Switch to a profile with the specified scope and options
+    pub fn switch_profile_with_options(
+        &mut self,
+        profile_name: &str,
+        scope: ConfigScope,
+        options: &SwitchOptions,
+    ) -> Result<()> {
+        progress!(options, "Switching to profile '{}'...", profile_name);
 
         // 1. Validate profile exists
-        println!("  ✓ Checking if profile exists...");
+        progress!(options, "  ✓ Checking if profile exists...");
         let profile = self
             .profile_manager
             .get_profile(profile_name)?
             .ok_or_else(|| ProfileError::ProfileNotFound(profile_name.to_string()))?;
 
+        self.apply_profile(&profile, scope, options)
+    }
+
+    /// Apply a profile that isn't stored (e.g. loaded from `--profile-file`)
+    pub fn switch_to_ephemeral(
+        &mut self,
+        profile: &Profile,
+        scope: ConfigScope,
+        options: &SwitchOptions,
+    ) -> Result<()> {
+        progress!(options, "Switching to profile '{}' (not stored)...", profile.name);
+        self.apply_profile(profile, scope, options)
+    }
+
+    /// Apply a profile's git and SSH configuration
+    fn apply_profile(
+        &mut self,
+        profile: &Profile,
+        scope: ConfigScope,
+        options: &SwitchOptions,
+    ) -> Result<()> {
+        let identity_only = options.commit_identity_only || profile.commit_identity_only;
+
         // 2. Validate SSH key exists
-        println!("  ✓ Validating SSH key...");
-        if !SSHConfigManager::validate_ssh_key(&profile.ssh_key_name)? {
-            let key_path = SSHConfigManager::get_ssh_key_path(&profile.ssh_key_name);
-            return Err(ProfileError::SshKeyNotFound(
-                key_path.to_string_lossy().to_string(),
-            ));
+        if identity_only {
+            progress!(options, "  ⚠ Commit identity only: skipping SSH key and SSH config");
+        } else if options.no_verify_key {
+            progress!(options, "  ⚠ Skipping SSH key verification (--no-verify-key)");
+        } else {
+            progress!(options, "  ✓ Validating SSH key...");
+            if !SSHConfigManager::validate_ssh_key(&profile.ssh_key_name)? {
+                let key_path = SSHConfigManager::get_ssh_key_path(&profile.ssh_key_name);
+                return Err(ProfileError::SshKeyNotFound(
+                    key_path.to_string_lossy().to_string(),
+                ));
+            }
         }
 
-        // 3. Apply git config changes
-        println!("  ✓ Updating git config ({})...", scope);
-        GitConfigManager::apply_profile(&profile, scope)?;
+        // 3. Apply git config changes, keeping the old values to put back
+        // if the switch fails part way
+        progress!(options, "  ✓ Updating git config ({})...", scope);
+        let snapshot = GitConfigManager::snapshot(profile, scope)?;
+        if let Err(e) = self.git_config.apply_profile(profile, scope) {
+            snapshot.restore(&self.git_config)?;
+            return Err(e);
+        }
 
         // 4. Update SSH config
-        println!("  ✓ Updating SSH config...");
-        self.ssh_config.add_or_update_host(&profile)?;
+        let ssh_written = if identity_only {
+            false
+        } else {
+            progress!(options, "  ✓ Updating SSH config...");
+            match self.ssh_config.add_or_update_host(profile) {
+                Ok(written) => written,
+                Err(e) => {
+                    snapshot.restore(&self.git_config)?;
+                    return Err(e);
+                }
+            }
+        };
+
+        // 5. Make sure git actually reports the new identity
+        progress!(options, "  ✓ Verifying git config...");
+        if let Err(e) = self.git_config.verify_profile(profile, scope) {
+            snapshot.restore(&self.git_config)?;
+            // Only a fresh write left a backup of the previous config
+            if ssh_written {
+                self.ssh_config.restore_backup()?;
+            }
+            return Err(e);
+        }
+
+        if !identity_only {
+            if let Some(warning) = ssh_command_conflict()? {
+                notice!(options, "  ⚠ {}", warning);
+            }
+            if let Some(warning) = agent::locked_key_warning(&profile.ssh_key_name) {
+                notice!(options, "  ⚠ {}", warning);
+            }
+        }
 
-        println!("\n✓ Successfully switched to profile '{}'", profile_name);
-        println!("  Username: {}", profile.username);
-        println!("  Email: {}", profile.email);
-        println!("  SSH Key: {}", profile.ssh_key_name);
-        println!("  Scope: {}", scope);
+        progress!(options, "\n✓ Successfully switched to profile '{}'", profile.name);
+        progress!(options, "  Username: {}", profile.username);
+        progress!(options, "  Email: {}", profile.email);
+        progress!(options, "  SSH Key: {}", profile.ssh_key_name);
+        progress!(options, "  Scope: {}", scope);
+        if let Some(note) = &profile.note {
+            notice!(options, "\n📌 Note: {}", note);
+        }
 
         Ok(())
     }
@@ -77,7 +217,8 @@ impl ProfileSwitcher {
         };
 
         // Get local profile (if in a git repo)
-        let local = if GitConfigManager::is_git_repository()? {
+        let local_head = GitConfigManager::head_state()?;
+        let local = if local_head.is_some() {
             match GitConfigManager::get_current_profile(ConfigScope::Local)? {
                 Some((username, email)) => {
                     // Try to find a matching profile
@@ -89,7 +230,7 @@ impl ProfileSwitcher {
             None
         };
 
-        Ok(ProfileStatus { global, local })
+        Ok(ProfileStatus { global, local, local_head })
     }
 
     /// Find a profile by username and email
@@ -106,11 +247,61 @@ impl ProfileSwitcher {
     }
 }
 
+/// Warn when `core.sshCommand` is set, since it can pin a key and bypass
+/// the per-profile host alias
+pub fn ssh_command_conflict() -> Result<Option<String>> {
+    let Some((command, origin)) = GitConfigManager::get_effective_config("core.sshCommand")? else {
+        return Ok(None);
+    };
+
+    Ok(Some(format!(
+        "core.sshCommand is set to '{}' ({}); it may override the profile's SSH key. \
+         Remove it with 'git config --unset core.sshCommand' in that config if gex should pick the key",
+        command, origin
+    )))
+}
+
+/// Warn when the repository's origin goes through another profile's host
+/// alias: commits would carry this profile's identity but be pushed with
+/// the other profile's key. Origins not using a gex alias are ignored.
+pub fn origin_host_mismatch(profile: &Profile) -> Result<Option<String>> {
+    Ok(remote::origin_url()?.and_then(|url| alias_mismatch(profile, &url)))
+}
+
+fn alias_mismatch(profile: &Profile, origin: &str) -> Option<String> {
+    let host = remote::parse_host(origin)?;
+    if !host.starts_with("github.com-") || host == profile.ssh_host() {
+        return None;
+    }
+
+    Some(format!(
+        "origin ({}) goes through '{}', not this profile's '{}'; commits would be made as '{}' \
+         but pushed with another key. Run 'gex switch {} --announce' for the matching remote URL",
+        origin,
+        host,
+        profile.ssh_host(),
+        profile.name,
+        profile.name
+    ))
+}
+
+/// The URL a GitHub remote should have to go through the profile's host
+/// alias, keeping its owner/repo path. None for remotes on other hosts.
+pub fn remote_url_for(profile: &Profile, url: &str) -> Option<String> {
+    let host = remote::parse_host(url)?;
+    if host != "github.com" && host != "ssh.github.com" && !host.starts_with("github.com-") {
+        return None;
+    }
+    let (owner, repo) = remote::parse_owner_repo(url)?;
+    Some(profile.ssh_clone_url(&format!("{}/{}", owner, repo)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::git::executor::is_git_installed;
     use crate::storage::service::StorageService;
+    use crate::utils::testing::lock_cwd;
     use std::fs;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -128,12 +319,17 @@ mod tests {
         let storage_path = temp_dir.join("profiles.json");
         let storage = StorageService {
             config_path: storage_path.clone(),
+            dry_run: false,
         };
 
         // Create temporary SSH config
         let ssh_config_path = temp_dir.join("ssh_config");
         let ssh_config = SSHConfigManager {
             config_path: ssh_config_path.clone(),
+            dry_run: false,
+            auto_create: true,
+            section: None,
+            identities_only: true,
         };
 
         let profile_manager = ProfileManager { storage };
@@ -141,6 +337,7 @@ mod tests {
         let switcher = ProfileSwitcher {
             profile_manager,
             ssh_config,
+            git_config: GitConfigManager::new(),
         };
 
         (switcher, temp_dir, ssh_config_path)
@@ -152,11 +349,12 @@ mod tests {
         }
     }
 
-    fn create_dummy_ssh_key(temp_dir: &std::path::PathBuf, key_name: &str) {
-        let ssh_dir = temp_dir.join(".ssh");
-        fs::create_dir_all(&ssh_dir).unwrap();
-        let key_path = ssh_dir.join(key_name);
-        fs::write(&key_path, "dummy key content").unwrap();
+    fn create_temp_git_repo(temp_dir: &std::path::Path) -> std::path::PathBuf {
+        let repo_dir = temp_dir.join("repo");
+        fs::create_dir_all(&repo_dir).unwrap();
+        std::env::set_current_dir(&repo_dir).unwrap();
+        crate::git::executor::execute_git(&["init"]).unwrap();
+        repo_dir
     }
 
     #[test]
@@ -186,6 +384,7 @@ mod tests {
             username: "testuser".to_string(),
             email: "test@example.com".to_string(),
             ssh_key_name: "nonexistent_key".to_string(),
+            ..Default::default()
         };
 
         let _ = switcher
@@ -227,6 +426,7 @@ mod tests {
             username: "testuser".to_string(),
             email: "test@example.com".to_string(),
             ssh_key_name: "id_rsa".to_string(),
+            ..Default::default()
         };
 
         let _ = switcher
@@ -252,7 +452,137 @@ mod tests {
         cleanup_temp_dir(&temp_dir);
     }
 
+    #[test]
+    fn test_switch_profile_no_verify_key() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let (mut switcher, temp_dir, ssh_config_path) = create_test_environment();
+        create_temp_git_repo(&temp_dir);
+
+        let profile = Profile {
+            name: "agent".to_string(),
+            username: "agentuser".to_string(),
+            email: "agent@example.com".to_string(),
+            ssh_key_name: "key_only_in_agent".to_string(),
+            ..Default::default()
+        };
+        switcher.profile_manager.create_profile(profile).unwrap();
+
+        let options = SwitchOptions {
+            no_verify_key: true,
+            ..Default::default()
+        };
+        let result = switcher.switch_profile_with_options("agent", ConfigScope::Local, &options);
+        assert!(result.is_ok(), "Switch failed: {:?}", result.err());
+
+        // Git config and SSH block are still written
+        let email = GitConfigManager::get_config(ConfigScope::Local, "user.email").unwrap();
+        assert_eq!(email, Some("agent@example.com".to_string()));
+        let content = fs::read_to_string(&ssh_config_path).unwrap();
+        assert!(content.contains("Host github.com-agent"));
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_switch_commit_identity_only() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let (mut switcher, temp_dir, ssh_config_path) = create_test_environment();
+        create_temp_git_repo(&temp_dir);
+
+        let profile = Profile {
+            name: "identity".to_string(),
+            username: "identityuser".to_string(),
+            email: "identity@example.com".to_string(),
+            ssh_key_name: "key_that_does_not_exist".to_string(),
+            ..Default::default()
+        };
+        switcher.profile_manager.create_profile(profile).unwrap();
+
+        let options = SwitchOptions {
+            commit_identity_only: true,
+            ..Default::default()
+        };
+        let result = switcher.switch_profile_with_options("identity", ConfigScope::Local, &options);
+        assert!(result.is_ok(), "Switch failed: {:?}", result.err());
+
+        // Git identity is set, but the missing key is ignored and no SSH block is written
+        let email = GitConfigManager::get_config(ConfigScope::Local, "user.email").unwrap();
+        assert_eq!(email, Some("identity@example.com".to_string()));
+        assert!(!fs::read_to_string(&ssh_config_path).unwrap_or_default().contains("github.com-identity"));
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
+    #[test]
+    fn test_ssh_command_conflict() {
+        if !is_git_installed() {
+            return;
+        }
+
+        let _guard = lock_cwd();
+        let original_dir = std::env::current_dir().unwrap();
+        let (_switcher, temp_dir, _) = create_test_environment();
+        create_temp_git_repo(&temp_dir);
+
+        // Only check our own setting if the machine doesn't already have one
+        if ssh_command_conflict().unwrap().is_none() {
+            GitConfigManager::new().set_config(ConfigScope::Local, "core.sshCommand", "ssh -i ~/.ssh/other")
+                .unwrap();
+            let warning = ssh_command_conflict().unwrap();
+            assert!(warning.is_some_and(|w| w.contains("ssh -i ~/.ssh/other")));
+        }
+
+        std::env::set_current_dir(&original_dir).unwrap();
+        cleanup_temp_dir(&temp_dir);
+    }
+
     // Note: Full end-to-end tests that actually switch git config are skipped
     // because they would modify the user's actual git configuration.
     // These tests verify the orchestration logic without side effects.
+
+    #[test]
+    fn test_alias_mismatch() {
+        let profile = Profile {
+            name: "personal".to_string(),
+            ..Default::default()
+        };
+
+        let warning = alias_mismatch(&profile, "git@github.com-work:acme/app.git").unwrap();
+        assert!(warning.contains("'github.com-work', not this profile's 'github.com-personal'"));
+
+        // The profile's own alias, plain GitHub and other hosts are fine
+        assert_eq!(alias_mismatch(&profile, "git@github.com-personal:me/dots.git"), None);
+        assert_eq!(alias_mismatch(&profile, "https://github.com/acme/app.git"), None);
+        assert_eq!(alias_mismatch(&profile, "git@gitlab.com:acme/app.git"), None);
+    }
+
+    #[test]
+    fn test_remote_url_for() {
+        let profile = Profile {
+            name: "work".to_string(),
+            ..Default::default()
+        };
+        let expected = Some("git@github.com-work:acme/app.git".to_string());
+
+        assert_eq!(remote_url_for(&profile, "git@github.com:acme/app.git"), expected);
+        assert_eq!(remote_url_for(&profile, "https://github.com/acme/app"), expected);
+        assert_eq!(remote_url_for(&profile, "ssh://git@ssh.github.com:443/acme/app.git"), expected);
+        assert_eq!(remote_url_for(&profile, "git@github.com-personal:acme/app.git"), expected);
+        assert_eq!(remote_url_for(&profile, "git@github.com-work:acme/app.git"), expected);
+
+        assert_eq!(remote_url_for(&profile, "git@gitlab.com:acme/app.git"), None);
+        assert_eq!(remote_url_for(&profile, "https://github.com/acme"), None);
+    }
 }
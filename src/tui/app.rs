@@ -1,21 +1,33 @@
+use crate::cli::context::Context;
 use crate::error::Result;
 use crate::git::ConfigScope;
 use crate::profile::manager::ProfileManager;
+use crate::profile::Profile;
+use crate::ssh::config::SSHConfigManager;
+use crate::storage::history::{self, SwitchHistory};
 use crate::switcher::ProfileSwitcher;
+use crate::tui::form::ProfileForm;
+use crate::tui::theme::Theme;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, BorderType, List, ListItem, ListState, Paragraph, Clear},
     Frame, Terminal,
 };
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::io;
+use std::time::{Duration, Instant};
 
 // Modern icons using Unicode
 const ICON_PROFILE: &str = "👤";
@@ -32,9 +44,15 @@ const ICON_INFO: &str = "ℹ️";
 const ICON_ARROW: &str = "➤";
 const ICON_CHECK: &str = "✓";
 const ICON_STAR: &str = "⭐";
-const ICON_SEARCH: &str = "🔍";
 const ICON_HELP: &str = "❓";
 
+/// How long a status-bar notification stays visible
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often the event loop wakes up to expire notifications
+const TICK_RATE: Duration = Duration::from_millis(250);
+/// Number of entries in the main menu
+const MENU_ITEMS: usize = 4;
+
 enum AppState {
     MainMenu,
     ListProfiles,
@@ -42,41 +60,93 @@ enum AppState {
     Status,
     Message { text: String, is_error: bool },
     ConfirmSwitch { profile_index: usize, scope: ConfigScope },
+    ProfileForm(Box<ProfileForm>),
+}
+
+/// A cursor movement in a list, from arrow or vim keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nav {
+    Up,
+    Down,
+    Top,
+    Bottom,
+}
+
+/// Short feedback shown above the footer without leaving the current screen
+struct Notification {
+    text: String,
+    is_error: bool,
+    shown_at: Instant,
 }
 
 pub struct TuiApp {
     profile_manager: ProfileManager,
     switcher: ProfileSwitcher,
+    ssh_config: SSHConfigManager,
+    /// Don't log switches to the history (`--dry-run`)
+    dry_run: bool,
     state: AppState,
     list_state: ListState,
     should_quit: bool,
     selected_menu_item: usize,
     selected_scope: ConfigScope,
+    theme: Theme,
+    notification: Option<Notification>,
+    /// Where the Global and Local segments of the scope toggle were last
+    /// drawn, for mouse clicks
+    scope_toggle: Option<(Rect, Rect)>,
+    /// A `g` was pressed and a second one jumps to the top (`gg`)
+    pending_g: bool,
+    /// When each profile was last switched to, for ordering the lists
+    last_used: HashMap<String, DateTime<Utc>>,
 }
 
 impl TuiApp {
-    pub fn new() -> Result<Self> {
-        let profile_manager = ProfileManager::new()?;
-        let switcher = ProfileSwitcher::new()?;
+    pub fn new(ctx: &Context) -> Result<Self> {
+        let profile_manager = ctx.profile_manager()?;
+        let switcher = ctx.switcher()?;
+        let ssh_config = ctx.ssh_config()?;
+        let theme = Theme::from_name(&profile_manager.get_settings()?.theme).unwrap_or_default();
         let mut list_state = ListState::default();
         list_state.select(Some(0));
+        // Without a readable history the lists fall back to name order
+        let last_used = SwitchHistory::new()
+            .and_then(|history| history.entries())
+            .map(|entries| history::last_used(&entries))
+            .unwrap_or_default();
 
         Ok(Self {
             profile_manager,
             switcher,
+            ssh_config,
+            dry_run: ctx.dry_run,
             state: AppState::MainMenu,
             list_state,
             should_quit: false,
             selected_menu_item: 0,
             selected_scope: ConfigScope::Global,
+            theme,
+            notification: None,
+            scope_toggle: None,
+            pending_g: false,
+            last_used,
         })
     }
 
+    /// Profiles in the order the lists show them: most recently used first
+    fn load_profiles(&self) -> Result<Vec<Profile>> {
+        let mut profiles = self.profile_manager.get_all_profiles()?;
+        profiles.sort_by(|a, b| history::cmp_by_usage(a, b, &self.last_used));
+        Ok(profiles)
+    }
+
     pub fn run(&mut self) -> Result<()> {
         // Setup terminal
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        // Bracketed paste delivers a paste as one event, so its newlines
+        // don't submit the form
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
 
@@ -88,7 +158,8 @@ impl TuiApp {
         execute!(
             terminal.backend_mut(),
             LeaveAlternateScreen,
-            DisableMouseCapture
+            DisableMouseCapture,
+            DisableBracketedPaste
         )?;
         terminal.show_cursor()?;
 
@@ -101,17 +172,38 @@ impl TuiApp {
 
     fn run_app<B: ratatui::backend::Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         loop {
+            if self.notification.as_ref().is_some_and(|n| n.shown_at.elapsed() >= NOTIFICATION_TIMEOUT) {
+                self.notification = None;
+            }
+
             terminal.draw(|f| self.ui(f))?;
 
-            if let Event::Key(key) = event::read()? {
-                match &self.state {
+            // Poll instead of blocking so notifications expire without a keypress
+            if !event::poll(TICK_RATE)? {
+                continue;
+            }
+
+            match event::read()? {
+                Event::Key(key) => match &self.state {
                     AppState::MainMenu => self.handle_main_menu_input(key.code, key.modifiers),
                     AppState::ListProfiles => self.handle_list_profiles_input(key.code),
                     AppState::SwitchProfile => self.handle_switch_profile_input(key.code),
                     AppState::Status => self.handle_status_input(key.code),
                     AppState::Message { .. } => self.handle_message_input(key.code),
                     AppState::ConfirmSwitch { .. } => self.handle_confirm_input(key.code),
+                    AppState::ProfileForm(_) => self.handle_form_input(key.code, key.modifiers),
+                },
+                Event::Paste(text) => {
+                    if let AppState::ProfileForm(form) = &mut self.state {
+                        form.insert(&text);
+                    }
                 }
+                Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                    if matches!(self.state, AppState::SwitchProfile) {
+                        self.handle_scope_click(mouse.column, mouse.row);
+                    }
+                }
+                _ => {}
             }
 
             if self.should_quit {
@@ -129,6 +221,7 @@ impl TuiApp {
             .constraints([
                 Constraint::Length(5),  // Header
                 Constraint::Min(0),     // Content
+                Constraint::Length(1),  // Notification
                 Constraint::Length(3),  // Footer
             ])
             .split(f.size());
@@ -144,15 +237,145 @@ impl TuiApp {
             AppState::Status => self.render_status(f, chunks[1]),
             AppState::Message { text, is_error } => self.render_message(f, chunks[1], text.clone(), *is_error),
             AppState::ConfirmSwitch { profile_index, scope } => {
-                self.render_confirm_switch(f, chunks[1], *profile_index, scope.clone())
+                self.render_confirm_switch(f, chunks[1], *profile_index, *scope)
             }
+            AppState::ProfileForm(form) => self.render_profile_form(f, chunks[1], form),
         }
 
+        self.render_notification(f, chunks[2]);
+
         // Render footer
-        self.render_footer(f, chunks[2]);
+        self.render_footer(f, chunks[3]);
+    }
+
+    /// Show a transient message above the footer
+    fn notify(&mut self, text: impl Into<String>) {
+        self.notification = Some(Notification {
+            text: text.into(),
+            is_error: false,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Show a transient error above the footer
+    fn notify_error(&mut self, text: impl Into<String>) {
+        self.notification = Some(Notification {
+            text: text.into(),
+            is_error: true,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Change the scope the next switch applies to
+    fn set_scope(&mut self, scope: ConfigScope) {
+        self.selected_scope = scope;
+        self.notify(format!("Scope set to {}", scope_label(scope)));
+    }
+
+    /// Switch scope when a segment of the scope toggle is clicked
+    fn handle_scope_click(&mut self, column: u16, row: u16) {
+        let Some((global, local)) = self.scope_toggle else {
+            return;
+        };
+        if rect_contains(global, column, row) {
+            self.set_scope(ConfigScope::Global);
+        } else if rect_contains(local, column, row) {
+            self.set_scope(ConfigScope::Local);
+        }
+    }
+
+    /// Draw the `[Global|Local]` toggle with the selected side highlighted
+    fn render_scope_toggle(&mut self, f: &mut Frame, area: Rect) {
+        let theme = self.theme;
+        let block = Block::default()
+            .title(" Scope ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(theme.primary));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let segment = |scope: ConfigScope, icon: &str| {
+            let text = format!(" {} {} ", icon, scope_label(scope));
+            let style = if self.selected_scope == scope {
+                Style::default()
+                    .fg(theme.highlight_fg)
+                    .bg(theme.highlight_bg)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            (text, style)
+        };
+        let (global_text, global_style) = segment(ConfigScope::Global, ICON_GLOBAL);
+        let (local_text, local_style) = segment(ConfigScope::Local, ICON_LOCAL);
+
+        // Emoji render two cells wide, one more than their char count
+        let width = |text: &str| text.chars().count() as u16 + 1;
+        let cells = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(width(&global_text)),
+                Constraint::Length(1),
+                Constraint::Length(width(&local_text)),
+                Constraint::Min(0),
+            ])
+            .split(inner);
+
+        f.render_widget(Paragraph::new(Span::styled(global_text, global_style)), cells[0]);
+        f.render_widget(Paragraph::new(Span::styled("|", Style::default().fg(theme.muted))), cells[1]);
+        f.render_widget(Paragraph::new(Span::styled(local_text, local_style)), cells[2]);
+        f.render_widget(
+            Paragraph::new(Span::styled("  g / l / Tab or click to change", Style::default().fg(theme.muted))),
+            cells[3],
+        );
+
+        self.scope_toggle = Some((cells[0], cells[2]));
+    }
+
+    /// Copy the selected profile's public key to the clipboard
+    fn copy_public_key(&mut self) {
+        let profiles = self.load_profiles().unwrap_or_default();
+        let Some(profile) = self.list_state.selected().and_then(|i| profiles.get(i)) else {
+            return;
+        };
+
+        let pub_path = SSHConfigManager::get_ssh_key_path(&format!("{}.pub", profile.ssh_key_name));
+        let key = match std::fs::read_to_string(&pub_path) {
+            Ok(key) => key,
+            Err(_) => {
+                self.notify_error(format!("Public key not found: {}", pub_path.display()));
+                return;
+            }
+        };
+
+        match copy_to_clipboard(key.trim()) {
+            Ok(()) => self.notify(format!("Copied {}.pub to clipboard", profile.ssh_key_name)),
+            Err(e) => self.notify_error(e),
+        }
+    }
+
+    fn render_notification(&self, f: &mut Frame, area: Rect) {
+        let Some(notification) = &self.notification else {
+            return;
+        };
+
+        let (icon, color) = if notification.is_error {
+            (ICON_ERROR, self.theme.error)
+        } else {
+            (ICON_INFO, self.theme.accent)
+        };
+        let line = Paragraph::new(Line::from(vec![
+            Span::styled(format!("{} ", icon), Style::default().fg(color)),
+            Span::styled(notification.text.as_str(), Style::default().fg(self.theme.text)),
+        ]))
+        .alignment(Alignment::Center);
+
+        f.render_widget(line, area);
     }
 
     fn render_header(&self, f: &mut Frame, area: Rect) {
+        let theme = self.theme;
         let header_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Length(3), Constraint::Length(2)])
@@ -162,20 +385,20 @@ impl TuiApp {
         let title_text = vec![
             Line::from(vec![
                 Span::styled("╔═══════════════════════════════════════════════════════════╗", 
-                    Style::default().fg(Color::Cyan)),
+                    Style::default().fg(theme.primary)),
             ]),
             Line::from(vec![
-                Span::styled("║  ", Style::default().fg(Color::Cyan)),
-                Span::styled("⚡ ", Style::default().fg(Color::Yellow)),
-                Span::styled("GEX", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
-                Span::styled(" - ", Style::default().fg(Color::White)),
-                Span::styled("Git Profile Switcher", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                Span::styled(" ⚡", Style::default().fg(Color::Yellow)),
-                Span::styled("  ║", Style::default().fg(Color::Cyan)),
+                Span::styled("║  ", Style::default().fg(theme.primary)),
+                Span::styled("⚡ ", Style::default().fg(theme.accent)),
+                Span::styled("GEX", Style::default().fg(theme.secondary).add_modifier(Modifier::BOLD)),
+                Span::styled(" - ", Style::default().fg(theme.text)),
+                Span::styled("Git Profile Switcher", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD)),
+                Span::styled(" ⚡", Style::default().fg(theme.accent)),
+                Span::styled("  ║", Style::default().fg(theme.primary)),
             ]),
             Line::from(vec![
                 Span::styled("╚═══════════════════════════════════════════════════════════╝", 
-                    Style::default().fg(Color::Cyan)),
+                    Style::default().fg(theme.primary)),
             ]),
         ];
 
@@ -191,39 +414,43 @@ impl TuiApp {
             AppState::Status => format!("{} Status", ICON_STATUS),
             AppState::Message { .. } => format!("{} Message", ICON_INFO),
             AppState::ConfirmSwitch { .. } => format!("{} Confirm", ICON_INFO),
+            AppState::ProfileForm(form) => format!("{} {}", ICON_PROFILE, form.title()),
         };
 
         let status_bar = Paragraph::new(status_text)
-            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
             .alignment(Alignment::Center);
         f.render_widget(status_bar, header_chunks[1]);
     }
 
     fn render_footer(&self, f: &mut Frame, area: Rect) {
+        let theme = self.theme;
         let help_text = match &self.state {
-            AppState::MainMenu => "↑↓: Navigate | Enter: Select | q/Esc: Quit",
-            AppState::ListProfiles => "↑↓: Scroll | Esc: Back",
-            AppState::SwitchProfile => "↑↓: Navigate | Enter: Confirm | g: Global | l: Local | Esc: Back",
+            AppState::MainMenu => "↑↓/jk: Navigate | gg/G: Top/Bottom | Enter/l: Select | q/Esc: Quit",
+            AppState::ListProfiles => "↑↓/jk: Scroll | Enter/l: Switch | a: Add | e: Edit | c: Copy key | Esc/h: Back",
+            AppState::SwitchProfile => "↑↓/jk: Navigate | Enter: Confirm | g/l/Tab: Scope | c: Copy key | Esc/h: Back",
             AppState::Status => "Esc: Back",
             AppState::Message { .. } => "Enter/Esc: Back",
             AppState::ConfirmSwitch { .. } => "y: Confirm | n/Esc: Cancel",
+            AppState::ProfileForm(_) => "Tab/↑↓: Next/Previous field | Enter: Save | Esc: Cancel",
         };
 
         let footer = Paragraph::new(Line::from(vec![
-            Span::styled(format!("{} ", ICON_HELP), Style::default().fg(Color::Yellow)),
-            Span::styled(help_text, Style::default().fg(Color::Gray)),
+            Span::styled(format!("{} ", ICON_HELP), Style::default().fg(theme.accent)),
+            Span::styled(help_text, Style::default().fg(theme.muted)),
         ]))
         .alignment(Alignment::Center)
         .block(Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .style(Style::default().fg(Color::DarkGray)));
+            .style(Style::default().fg(theme.dim)));
 
         f.render_widget(footer, area);
     }
 
     fn render_main_menu(&mut self, f: &mut Frame, area: Rect) {
-        let menu_options = vec![
+        let theme = self.theme;
+        let menu_options = [
             (ICON_PROFILE, "List Profiles", "View all configured profiles"),
             (ICON_SWITCH, "Switch Profile", "Change active profile"),
             (ICON_STATUS, "Show Status", "Display current configuration"),
@@ -237,11 +464,11 @@ impl TuiApp {
                 let is_selected = i == self.selected_menu_item;
                 let style = if is_selected {
                     Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Cyan)
+                        .fg(theme.highlight_fg)
+                        .bg(theme.highlight_bg)
                         .add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme.text)
                 };
 
                 let prefix = if is_selected { ICON_ARROW } else { " " };
@@ -255,9 +482,9 @@ impl TuiApp {
                     Line::from(vec![
                         Span::styled(format!("    {}", desc), 
                             if is_selected { 
-                                Style::default().fg(Color::Black).bg(Color::Cyan)
+                                Style::default().fg(theme.highlight_fg).bg(theme.highlight_bg)
                             } else { 
-                                Style::default().fg(Color::DarkGray) 
+                                Style::default().fg(theme.dim) 
                             }
                         ),
                     ]),
@@ -272,7 +499,7 @@ impl TuiApp {
                     .title(format!(" {} Main Menu ", ICON_STAR))
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Cyan))
+                    .border_style(Style::default().fg(theme.primary))
             )
             .highlight_style(Style::default());
 
@@ -280,35 +507,33 @@ impl TuiApp {
     }
 
     fn render_list_profiles(&mut self, f: &mut Frame, area: Rect) {
-        let profiles = match self.profile_manager.get_all_profiles() {
-            Ok(p) => p,
-            Err(_) => vec![],
-        };
+        let theme = self.theme;
+        let profiles = self.load_profiles().unwrap_or_default();
 
         if profiles.is_empty() {
             let empty_msg = vec![
                 Line::from(""),
                 Line::from(Span::styled(
                     format!("  {} No profiles found", ICON_INFO),
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                    Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
                 )),
                 Line::from(""),
                 Line::from(Span::styled(
-                    "  Add profiles using CLI:",
-                    Style::default().fg(Color::Cyan),
+                    "  Press 'a' to add one here, or use the CLI:",
+                    Style::default().fg(theme.primary),
                 )),
                 Line::from(""),
                 Line::from(Span::styled(
                     "  gex add <name> --username <user> \\",
-                    Style::default().fg(Color::Green),
+                    Style::default().fg(theme.success),
                 )),
                 Line::from(Span::styled(
                     "              --email <email> \\",
-                    Style::default().fg(Color::Green),
+                    Style::default().fg(theme.success),
                 )),
                 Line::from(Span::styled(
                     "              --ssh-key <key>",
-                    Style::default().fg(Color::Green),
+                    Style::default().fg(theme.success),
                 )),
                 Line::from(""),
             ];
@@ -319,7 +544,7 @@ impl TuiApp {
                         .title(format!(" {} Profiles ", ICON_PROFILE))
                         .borders(Borders::ALL)
                         .border_type(BorderType::Rounded)
-                        .border_style(Style::default().fg(Color::Yellow))
+                        .border_style(Style::default().fg(theme.accent))
                 )
                 .alignment(Alignment::Left);
             f.render_widget(msg, area);
@@ -334,13 +559,12 @@ impl TuiApp {
 
         let items: Vec<ListItem> = profiles
             .iter()
-            .enumerate()
-            .map(|(_i, p)| {
-                let is_active = current_global.as_ref().map_or(false, |name| name == &p.name);
+            .map(|p| {
+                let is_active = current_global.as_ref() == Some(&p.name);
                 let number_style = if is_active {
-                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.success).add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::Cyan)
+                    Style::default().fg(theme.primary)
                 };
 
                 let active_indicator = if is_active {
@@ -349,37 +573,52 @@ impl TuiApp {
                     "   ".to_string()
                 };
 
-                ListItem::new(vec![
+                let mut lines = vec![
                     Line::from(vec![
-                        Span::styled(active_indicator, Style::default().fg(Color::Green)),
+                        Span::styled(active_indicator, Style::default().fg(theme.success)),
                         Span::styled(
                             format!("{} ", ICON_PROFILE),
                             number_style,
                         ),
                         Span::styled(
                             &p.name,
-                            number_style.add_modifier(Modifier::BOLD),
+                            number_style
+                                .fg(theme.profile_color(p, theme.primary))
+                                .add_modifier(Modifier::BOLD),
                         ),
                         if is_active {
-                            Span::styled(" (Active)", Style::default().fg(Color::Green))
+                            Span::styled(" (Active)", Style::default().fg(theme.success))
                         } else {
                             Span::raw("")
                         },
                     ]),
                     Line::from(vec![
                         Span::raw("     "),
-                        Span::styled(format!("👤 {}", p.username), Style::default().fg(Color::White)),
+                        Span::styled(format!("👤 {}", p.username), Style::default().fg(theme.text)),
                     ]),
                     Line::from(vec![
                         Span::raw("     "),
-                        Span::styled(format!("{} {}", ICON_EMAIL, p.email), Style::default().fg(Color::Gray)),
+                        Span::styled(format!("{} {}", ICON_EMAIL, p.email), Style::default().fg(theme.muted)),
                     ]),
                     Line::from(vec![
                         Span::raw("     "),
-                        Span::styled(format!("{} {}", ICON_KEY, p.ssh_key_name), Style::default().fg(Color::Gray)),
+                        Span::styled(format!("{} {}", ICON_KEY, p.ssh_key_name), Style::default().fg(theme.muted)),
                     ]),
-                    Line::from(""),
-                ])
+                ];
+                if let Some(description) = &p.description {
+                    lines.push(Line::from(vec![
+                        Span::raw("     "),
+                        Span::styled(format!("📝 {}", description), Style::default().fg(theme.dim)),
+                    ]));
+                }
+                if let Some(note) = &p.note {
+                    lines.push(Line::from(vec![
+                        Span::raw("     "),
+                        Span::styled(format!("📌 {}", note), Style::default().fg(theme.accent)),
+                    ]));
+                }
+                lines.push(Line::from(""));
+                ListItem::new(lines)
             })
             .collect();
 
@@ -389,25 +628,23 @@ impl TuiApp {
                     .title(format!(" {} Profiles ({}) ", ICON_PROFILE, profiles.len()))
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Cyan))
+                    .border_style(Style::default().fg(theme.primary))
             )
-            .style(Style::default().fg(Color::White));
+            .style(Style::default().fg(theme.text));
 
         f.render_stateful_widget(list, area, &mut self.list_state);
     }
 
     fn render_switch_profile(&mut self, f: &mut Frame, area: Rect) {
-        let profiles = match self.profile_manager.get_all_profiles() {
-            Ok(p) => p,
-            Err(_) => vec![],
-        };
+        let theme = self.theme;
+        let profiles = self.load_profiles().unwrap_or_default();
 
         if profiles.is_empty() {
             let msg = Paragraph::new(vec![
                 Line::from(""),
                 Line::from(Span::styled(
                     format!("  {} No profiles available", ICON_INFO),
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(theme.accent),
                 )),
                 Line::from(""),
             ])
@@ -416,7 +653,7 @@ impl TuiApp {
                     .title(format!(" {} Switch Profile ", ICON_SWITCH))
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Yellow))
+                    .border_style(Style::default().fg(theme.accent))
             );
             f.render_widget(msg, area);
             return;
@@ -431,11 +668,11 @@ impl TuiApp {
                 let is_selected = i == selected;
                 let style = if is_selected {
                     Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Cyan)
+                        .fg(theme.highlight_fg)
+                        .bg(theme.highlight_bg)
                         .add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::White)
+                    Style::default().fg(theme.text)
                 };
 
                 let prefix = if is_selected { ICON_ARROW } else { " " };
@@ -450,9 +687,9 @@ impl TuiApp {
                         Span::styled(
                             format!("     {} {}", ICON_EMAIL, p.email),
                             if is_selected {
-                                Style::default().fg(Color::Black).bg(Color::Cyan)
+                                Style::default().fg(theme.highlight_fg).bg(theme.highlight_bg)
                             } else {
-                                Style::default().fg(Color::Gray)
+                                Style::default().fg(theme.muted)
                             },
                         ),
                     ]),
@@ -461,25 +698,27 @@ impl TuiApp {
             })
             .collect();
 
-        let scope_indicator = match self.selected_scope {
-            ConfigScope::Global => format!("{} Global", ICON_GLOBAL),
-            ConfigScope::Local => format!("{} Local", ICON_LOCAL),
-        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        self.render_scope_toggle(f, chunks[0]);
 
         let list = List::new(items)
             .block(
                 Block::default()
-                    .title(format!(" {} Switch Profile - {} ", ICON_SWITCH, scope_indicator))
+                    .title(format!(" {} Switch Profile ", ICON_SWITCH))
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Cyan))
+                    .border_style(Style::default().fg(theme.primary))
             )
             .highlight_style(Style::default());
 
-        f.render_stateful_widget(list, area, &mut self.list_state);
+        f.render_stateful_widget(list, chunks[1], &mut self.list_state);
     }
 
     fn render_status(&mut self, f: &mut Frame, area: Rect) {
+        let theme = self.theme;
         let status = match self.switcher.get_current_status() {
             Ok(s) => s,
             Err(_) => {
@@ -487,7 +726,7 @@ impl TuiApp {
                     Line::from(""),
                     Line::from(Span::styled(
                         format!("  {} Failed to get status", ICON_ERROR),
-                        Style::default().fg(Color::Red),
+                        Style::default().fg(theme.error),
                     )),
                     Line::from(""),
                 ])
@@ -496,7 +735,7 @@ impl TuiApp {
                         .title(format!(" {} Status ", ICON_STATUS))
                         .borders(Borders::ALL)
                         .border_type(BorderType::Rounded)
-                        .border_style(Style::default().fg(Color::Red))
+                        .border_style(Style::default().fg(theme.error))
                 );
                 f.render_widget(msg, area);
                 return;
@@ -507,17 +746,17 @@ impl TuiApp {
             Line::from(""),
             Line::from(vec![
                 Span::styled("  ╔══════════════════════════════════════════════╗", 
-                    Style::default().fg(Color::Cyan)),
+                    Style::default().fg(theme.primary)),
             ]),
             Line::from(vec![
-                Span::styled("  ║  ", Style::default().fg(Color::Cyan)),
+                Span::styled("  ║  ", Style::default().fg(theme.primary)),
                 Span::styled(format!("{} GLOBAL PROFILE", ICON_GLOBAL),
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                Span::styled("                      ║", Style::default().fg(Color::Cyan)),
+                    Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::styled("                      ║", Style::default().fg(theme.primary)),
             ]),
             Line::from(vec![
                 Span::styled("  ╚══════════════════════════════════════════════╝", 
-                    Style::default().fg(Color::Cyan)),
+                    Style::default().fg(theme.primary)),
             ]),
             Line::from(""),
         ];
@@ -525,83 +764,115 @@ impl TuiApp {
         if let Some(profile) = status.global {
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_PROFILE), Style::default().fg(Color::Green)),
-                Span::styled("Profile: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.name.clone(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{} ", ICON_PROFILE), Style::default().fg(theme.success)),
+                Span::styled("Profile: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.name.clone(), Style::default().fg(theme.profile_color(&profile, theme.primary)).add_modifier(Modifier::BOLD)),
             ]));
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled("👤 ", Style::default().fg(Color::Green)),
-                Span::styled("Username: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.username.clone(), Style::default().fg(Color::White)),
+                Span::styled("👤 ", Style::default().fg(theme.success)),
+                Span::styled("Username: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.username.clone(), Style::default().fg(theme.text)),
             ]));
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_EMAIL), Style::default().fg(Color::Green)),
-                Span::styled("Email: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.email.clone(), Style::default().fg(Color::White)),
+                Span::styled(format!("{} ", ICON_EMAIL), Style::default().fg(theme.success)),
+                Span::styled("Email: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.email.clone(), Style::default().fg(theme.text)),
             ]));
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_KEY), Style::default().fg(Color::Green)),
-                Span::styled("SSH Key: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.ssh_key_name.clone(), Style::default().fg(Color::White)),
+                Span::styled(format!("{} ", ICON_KEY), Style::default().fg(theme.success)),
+                Span::styled("SSH Key: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.ssh_key_name.clone(), Style::default().fg(theme.text)),
             ]));
+            if let Some(description) = &profile.description {
+                lines.push(Line::from(vec![
+                    Span::styled("    ", Style::default()),
+                    Span::styled("📝 ", Style::default().fg(theme.success)),
+                    Span::styled("Description: ", Style::default().fg(theme.muted)),
+                    Span::styled(description.clone(), Style::default().fg(theme.text)),
+                ]));
+            }
+            if let Some(note) = &profile.note {
+                lines.push(Line::from(vec![
+                    Span::styled("    ", Style::default()),
+                    Span::styled("📌 ", Style::default().fg(theme.success)),
+                    Span::styled("Note: ", Style::default().fg(theme.muted)),
+                    Span::styled(note.clone(), Style::default().fg(theme.accent)),
+                ]));
+            }
         } else {
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_INFO), Style::default().fg(Color::Yellow)),
-                Span::styled("No profile set", Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{} ", ICON_INFO), Style::default().fg(theme.accent)),
+                Span::styled("No profile set", Style::default().fg(theme.dim)),
             ]));
         }
 
         lines.push(Line::from(""));
         lines.push(Line::from(vec![
             Span::styled("  ╔══════════════════════════════════════════════╗", 
-                Style::default().fg(Color::Magenta)),
+                Style::default().fg(theme.secondary)),
         ]));
         lines.push(Line::from(vec![
-            Span::styled("  ║  ", Style::default().fg(Color::Magenta)),
+            Span::styled("  ║  ", Style::default().fg(theme.secondary)),
             Span::styled(format!("{} LOCAL PROFILE", ICON_LOCAL),
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-            Span::styled("                       ║", Style::default().fg(Color::Magenta)),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+            Span::styled("                       ║", Style::default().fg(theme.secondary)),
         ]));
         lines.push(Line::from(vec![
             Span::styled("  ╚══════════════════════════════════════════════╝", 
-                Style::default().fg(Color::Magenta)),
+                Style::default().fg(theme.secondary)),
         ]));
         lines.push(Line::from(""));
 
         if let Some(profile) = status.local {
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_PROFILE), Style::default().fg(Color::Magenta)),
-                Span::styled("Profile: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.name.clone(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{} ", ICON_PROFILE), Style::default().fg(theme.secondary)),
+                Span::styled("Profile: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.name.clone(), Style::default().fg(theme.profile_color(&profile, theme.secondary)).add_modifier(Modifier::BOLD)),
             ]));
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled("👤 ", Style::default().fg(Color::Magenta)),
-                Span::styled("Username: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.username.clone(), Style::default().fg(Color::White)),
+                Span::styled("👤 ", Style::default().fg(theme.secondary)),
+                Span::styled("Username: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.username.clone(), Style::default().fg(theme.text)),
             ]));
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_EMAIL), Style::default().fg(Color::Magenta)),
-                Span::styled("Email: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.email.clone(), Style::default().fg(Color::White)),
+                Span::styled(format!("{} ", ICON_EMAIL), Style::default().fg(theme.secondary)),
+                Span::styled("Email: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.email.clone(), Style::default().fg(theme.text)),
             ]));
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_KEY), Style::default().fg(Color::Magenta)),
-                Span::styled("SSH Key: ", Style::default().fg(Color::Gray)),
-                Span::styled(profile.ssh_key_name.clone(), Style::default().fg(Color::White)),
+                Span::styled(format!("{} ", ICON_KEY), Style::default().fg(theme.secondary)),
+                Span::styled("SSH Key: ", Style::default().fg(theme.muted)),
+                Span::styled(profile.ssh_key_name.clone(), Style::default().fg(theme.text)),
             ]));
+            if let Some(description) = &profile.description {
+                lines.push(Line::from(vec![
+                    Span::styled("    ", Style::default()),
+                    Span::styled("📝 ", Style::default().fg(theme.secondary)),
+                    Span::styled("Description: ", Style::default().fg(theme.muted)),
+                    Span::styled(description.clone(), Style::default().fg(theme.text)),
+                ]));
+            }
+            if let Some(note) = &profile.note {
+                lines.push(Line::from(vec![
+                    Span::styled("    ", Style::default()),
+                    Span::styled("📌 ", Style::default().fg(theme.secondary)),
+                    Span::styled("Note: ", Style::default().fg(theme.muted)),
+                    Span::styled(note.clone(), Style::default().fg(theme.accent)),
+                ]));
+            }
         } else {
             lines.push(Line::from(vec![
                 Span::styled("    ", Style::default()),
-                Span::styled(format!("{} ", ICON_INFO), Style::default().fg(Color::Yellow)),
-                Span::styled("No profile set or not in git repo", Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{} ", ICON_INFO), Style::default().fg(theme.accent)),
+                Span::styled("No profile set or not in git repo", Style::default().fg(theme.dim)),
             ]));
         }
 
@@ -613,17 +884,18 @@ impl TuiApp {
                     .title(format!(" {} Current Status ", ICON_STATUS))
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Cyan))
+                    .border_style(Style::default().fg(theme.primary))
             );
 
         f.render_widget(paragraph, area);
     }
 
     fn render_message(&mut self, f: &mut Frame, area: Rect, msg: String, is_error: bool) {
+        let theme = self.theme;
         let (icon, color, title) = if is_error {
-            (ICON_ERROR, Color::Red, "Error")
+            (ICON_ERROR, theme.error, "Error")
         } else {
-            (ICON_SUCCESS, Color::Green, "Success")
+            (ICON_SUCCESS, theme.success, "Success")
         };
 
         let lines = vec![
@@ -651,7 +923,8 @@ impl TuiApp {
     }
 
     fn render_confirm_switch(&mut self, f: &mut Frame, area: Rect, profile_index: usize, scope: ConfigScope) {
-        let profiles = match self.profile_manager.get_all_profiles() {
+        let theme = self.theme;
+        let profiles = match self.load_profiles() {
             Ok(p) => p,
             Err(_) => {
                 self.state = AppState::Message {
@@ -671,41 +944,43 @@ impl TuiApp {
         let scope_text = match scope {
             ConfigScope::Global => format!("{} Global", ICON_GLOBAL),
             ConfigScope::Local => format!("{} Local", ICON_LOCAL),
+            ConfigScope::Worktree => format!("{} Worktree", ICON_LOCAL),
+            ConfigScope::System => format!("{} System", ICON_GLOBAL),
         };
 
         let lines = vec![
             Line::from(""),
             Line::from(Span::styled(
                 "  Confirm Profile Switch",
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
             )),
             Line::from(""),
             Line::from(vec![
-                Span::styled("  Profile: ", Style::default().fg(Color::Gray)),
-                Span::styled(&profile.name, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::styled("  Profile: ", Style::default().fg(theme.muted)),
+                Span::styled(&profile.name, Style::default().fg(theme.primary).add_modifier(Modifier::BOLD)),
             ]),
             Line::from(vec![
-                Span::styled("  Scope: ", Style::default().fg(Color::Gray)),
-                Span::styled(&scope_text, Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+                Span::styled("  Scope: ", Style::default().fg(theme.muted)),
+                Span::styled(&scope_text, Style::default().fg(theme.secondary).add_modifier(Modifier::BOLD)),
             ]),
             Line::from(""),
             Line::from(vec![
                 Span::styled("  👤 ", Style::default()),
-                Span::styled(&profile.username, Style::default().fg(Color::White)),
+                Span::styled(&profile.username, Style::default().fg(theme.text)),
             ]),
             Line::from(vec![
                 Span::styled(format!("  {} ", ICON_EMAIL), Style::default()),
-                Span::styled(&profile.email, Style::default().fg(Color::White)),
+                Span::styled(&profile.email, Style::default().fg(theme.text)),
             ]),
             Line::from(vec![
                 Span::styled(format!("  {} ", ICON_KEY), Style::default()),
-                Span::styled(&profile.ssh_key_name, Style::default().fg(Color::White)),
+                Span::styled(&profile.ssh_key_name, Style::default().fg(theme.text)),
             ]),
             Line::from(""),
             Line::from(""),
             Line::from(Span::styled(
                 "  Press 'y' to confirm or 'n' to cancel",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(theme.dim),
             )),
         ];
 
@@ -715,7 +990,7 @@ impl TuiApp {
                     .title(format!(" {} Confirm ", ICON_INFO))
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(Color::Yellow))
+                    .border_style(Style::default().fg(theme.accent))
             )
             .alignment(Alignment::Left);
 
@@ -725,21 +1000,74 @@ impl TuiApp {
         f.render_widget(paragraph, dialog_area);
     }
 
+    /// Draw the add/edit form as a dialog. Each field shows how much of its
+    /// length cap is used, and a "max length" hint once it's full.
+    fn render_profile_form(&self, f: &mut Frame, area: Rect, form: &ProfileForm) {
+        let theme = self.theme;
+        let dialog_area = centered_rect(70, 70, area);
+        // Borders, the label column and room for the hint
+        let value_width = (dialog_area.width as usize).saturating_sub(2 + 14 + 20);
+
+        let mut lines = vec![Line::from("")];
+        for (field, input, focused) in form.fields() {
+            let (prefix, label_style) = if focused {
+                (ICON_ARROW, Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
+            } else {
+                (" ", Style::default().fg(theme.muted))
+            };
+            let (hint, hint_style) = match input.limit_hint() {
+                Some(hint) => (hint, Style::default().fg(theme.accent)),
+                None => (input.counter(), Style::default().fg(theme.dim)),
+            };
+
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {} {:<9} ", prefix, field.label()), label_style),
+                Span::styled(visible_tail(input.value(), value_width), Style::default().fg(theme.text)),
+                Span::styled(if focused { "▏" } else { " " }, Style::default().fg(theme.accent)),
+                Span::styled(format!("  {}", hint), hint_style),
+            ]));
+            lines.push(Line::from(""));
+        }
+        if let Some(error) = &form.error {
+            lines.push(Line::from(Span::styled(
+                format!("  {} {}", ICON_ERROR, error),
+                Style::default().fg(theme.error),
+            )));
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(format!(" {} {} ", ICON_PROFILE, form.title()))
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(theme.primary))
+            )
+            .alignment(Alignment::Left);
+
+        f.render_widget(Clear, dialog_area);
+        f.render_widget(paragraph, dialog_area);
+    }
+
+    /// Move the profile list's cursor
+    fn move_selection(&mut self, nav: Nav) {
+        let profiles_count = self.profile_manager.profile_count().unwrap_or(0);
+        let i = match self.list_state.selected() {
+            Some(i) => moved(i, profiles_count, nav),
+            None => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
     fn handle_main_menu_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        if let Some(nav) = nav_key(key, &mut self.pending_g) {
+            self.selected_menu_item = moved(self.selected_menu_item, MENU_ITEMS, nav);
+            self.list_state.select(Some(self.selected_menu_item));
+            return;
+        }
+
         match key {
-            KeyCode::Up => {
-                if self.selected_menu_item > 0 {
-                    self.selected_menu_item -= 1;
-                    self.list_state.select(Some(self.selected_menu_item));
-                }
-            }
-            KeyCode::Down => {
-                if self.selected_menu_item < 3 {
-                    self.selected_menu_item += 1;
-                    self.list_state.select(Some(self.selected_menu_item));
-                }
-            }
-            KeyCode::Enter => {
+            KeyCode::Enter | KeyCode::Char('l') => {
                 match self.selected_menu_item {
                     0 => {
                         self.state = AppState::ListProfiles;
@@ -771,86 +1099,116 @@ impl TuiApp {
     }
 
     fn handle_list_profiles_input(&mut self, key: KeyCode) {
+        if let Some(nav) = nav_key(key, &mut self.pending_g) {
+            self.move_selection(nav);
+            return;
+        }
+
         match key {
-            KeyCode::Esc => {
+            KeyCode::Esc | KeyCode::Char('h') => {
                 self.state = AppState::MainMenu;
                 self.list_state.select(Some(self.selected_menu_item));
             }
-            KeyCode::Up => {
-                let i = match self.list_state.selected() {
-                    Some(i) => {
-                        if i > 0 {
-                            i - 1
-                        } else {
-                            i
-                        }
-                    }
-                    None => 0,
-                };
-                self.list_state.select(Some(i));
+            // Open the switch screen on the selected profile
+            KeyCode::Enter | KeyCode::Char('l') => self.state = AppState::SwitchProfile,
+            KeyCode::Char('c') => self.copy_public_key(),
+            KeyCode::Char('a') => self.state = AppState::ProfileForm(Box::new(ProfileForm::add())),
+            KeyCode::Char('e') => {
+                let profiles = self.load_profiles().unwrap_or_default();
+                if let Some(profile) = self.list_state.selected().and_then(|i| profiles.get(i)) {
+                    self.state = AppState::ProfileForm(Box::new(ProfileForm::edit(profile)));
+                }
             }
-            KeyCode::Down => {
-                let profiles_count = self.profile_manager.get_all_profiles().map(|p| p.len()).unwrap_or(0);
-                let i = match self.list_state.selected() {
-                    Some(i) => {
-                        if i < profiles_count.saturating_sub(1) {
-                            i + 1
-                        } else {
-                            i
-                        }
-                    }
-                    None => 0,
-                };
-                self.list_state.select(Some(i));
+            _ => {}
+        }
+    }
+
+    fn handle_form_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let AppState::ProfileForm(form) = &mut self.state else {
+            return;
+        };
+
+        match key {
+            KeyCode::Esc => self.state = AppState::ListProfiles,
+            KeyCode::Enter => self.submit_form(),
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.prev_field(),
+            KeyCode::Backspace => form.backspace(),
+            // Ctrl and Alt chords are shortcuts, not text
+            KeyCode::Char(c) if !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                form.insert(&c.to_string())
             }
             _ => {}
         }
     }
 
+    /// Save the form's profile, or keep the form open with the reason it
+    /// was rejected
+    fn submit_form(&mut self) {
+        let AppState::ProfileForm(form) = &self.state else {
+            return;
+        };
+        let form = form.clone();
+
+        match form.to_profile().and_then(|profile| self.save_profile(form.original(), profile)) {
+            Ok(text) => self.state = AppState::Message { text, is_error: false },
+            Err(e) => {
+                if let AppState::ProfileForm(form) = &mut self.state {
+                    form.error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    /// Create the profile, or replace `original` with it, returning the
+    /// message to show
+    fn save_profile(&mut self, original: Option<&Profile>, profile: Profile) -> Result<String> {
+        let Some(original) = original else {
+            // An existing hand-written block for the same alias would shadow ours
+            self.ssh_config.check_host_conflict(&profile)?;
+            let profile = self.profile_manager.create_profile(profile)?;
+            return Ok(format!("Profile '{}' created", profile.name));
+        };
+
+        if !self.profile_manager.update_profile(&original.name, profile.clone())? {
+            return Ok(format!("No changes to profile '{}'", original.name));
+        }
+        // Keep an installed host block in step with the new values
+        if !profile.commit_identity_only && self.ssh_config.list_managed_hosts()?.contains(&profile.name) {
+            self.ssh_config.add_or_update_host(&profile)?;
+        }
+        Ok(format!("Profile '{}' updated", profile.name))
+    }
+
     fn handle_switch_profile_input(&mut self, key: KeyCode) {
+        // A single `g` still picks the global scope; `gg` then also jumps
+        // to the top, leaving the scope as it was just set
+        if let Some(nav) = nav_key(key, &mut self.pending_g) {
+            self.move_selection(nav);
+            return;
+        }
+
         match key {
-            KeyCode::Esc => {
+            KeyCode::Esc | KeyCode::Char('h') => {
                 self.state = AppState::MainMenu;
                 self.list_state.select(Some(self.selected_menu_item));
             }
-            KeyCode::Up => {
-                let i = match self.list_state.selected() {
-                    Some(i) => {
-                        if i > 0 {
-                            i - 1
-                        } else {
-                            i
-                        }
-                    }
-                    None => 0,
-                };
-                self.list_state.select(Some(i));
-            }
-            KeyCode::Down => {
-                let profiles_count = self.profile_manager.get_all_profiles().map(|p| p.len()).unwrap_or(0);
-                let i = match self.list_state.selected() {
-                    Some(i) => {
-                        if i < profiles_count.saturating_sub(1) {
-                            i + 1
-                        } else {
-                            i
-                        }
-                    }
-                    None => 0,
+            KeyCode::Char('g') => self.set_scope(ConfigScope::Global),
+            KeyCode::Char('l') | KeyCode::Char('L') => self.set_scope(ConfigScope::Local),
+            KeyCode::Tab => {
+                let scope = if self.selected_scope == ConfigScope::Global {
+                    ConfigScope::Local
+                } else {
+                    ConfigScope::Global
                 };
-                self.list_state.select(Some(i));
-            }
-            KeyCode::Char('g') | KeyCode::Char('G') => {
-                self.selected_scope = ConfigScope::Global;
-            }
-            KeyCode::Char('l') | KeyCode::Char('L') => {
-                self.selected_scope = ConfigScope::Local;
+                self.set_scope(scope);
             }
+            KeyCode::Char('c') => self.copy_public_key(),
             KeyCode::Enter => {
                 if let Some(index) = self.list_state.selected() {
                     self.state = AppState::ConfirmSwitch {
                         profile_index: index,
-                        scope: self.selected_scope.clone(),
+                        scope: self.selected_scope,
                     };
                 }
             }
@@ -872,21 +1230,43 @@ impl TuiApp {
         }
     }
 
+    /// Log a TUI switch like the CLI does, and move the profile to the top
+    /// of the lists
+    fn record_switch(&mut self, profile: &Profile, scope: ConfigScope) {
+        if self.dry_run {
+            return;
+        }
+        match SwitchHistory::new().and_then(|history| history.record(profile, scope, None)) {
+            Ok(entry) => {
+                self.last_used.insert(entry.profile, entry.timestamp);
+            }
+            Err(e) => self.notify_error(format!("Switch not recorded in history: {}", e)),
+        }
+    }
+
     fn handle_confirm_input(&mut self, key: KeyCode) {
         match key {
             KeyCode::Char('y') | KeyCode::Char('Y') => {
-                if let AppState::ConfirmSwitch { profile_index, scope } = &self.state {
-                    if let Ok(profiles) = self.profile_manager.get_all_profiles() {
-                        if *profile_index < profiles.len() {
-                            let profile_name = &profiles[*profile_index].name;
-                            match self.switcher.switch_profile(profile_name, scope.clone()) {
+                if let AppState::ConfirmSwitch { profile_index, scope } = self.state {
+                    if let Ok(profiles) = self.load_profiles() {
+                        if profile_index < profiles.len() {
+                            let profile_name = &profiles[profile_index].name;
+                            let note = profiles[profile_index].note.as_ref();
+                            match self.switcher.switch_profile(profile_name, scope) {
                                 Ok(_) => {
+                                    self.record_switch(&profiles[profile_index], scope);
                                     let scope_text = match scope {
                                         ConfigScope::Global => "globally",
                                         ConfigScope::Local => "locally",
+                                        ConfigScope::Worktree => "for this worktree",
+                                        ConfigScope::System => "system-wide",
                                     };
+                                    let mut text = format!("Successfully switched to '{}' {}", profile_name, scope_text);
+                                    if let Some(note) = note {
+                                        text.push_str(&format!("\n\n📌 Note: {}", note));
+                                    }
                                     self.state = AppState::Message {
-                                        text: format!("Successfully switched to '{}' {}", profile_name, scope_text),
+                                        text,
                                         is_error: false,
                                     };
                                 }
@@ -909,6 +1289,18 @@ impl TuiApp {
     }
 }
 
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> std::result::Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| format!("Clipboard unavailable: {}", e))
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str) -> std::result::Result<(), String> {
+    Err("Clipboard support not built in (enable the 'clipboard' feature)".to_string())
+}
+
 // Helper function to create centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -929,3 +1321,108 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+/// Translate arrow and vim keys into a cursor movement. `g` only moves on
+/// its second consecutive press; `pending_g` remembers the first.
+fn nav_key(key: KeyCode, pending_g: &mut bool) -> Option<Nav> {
+    let after_g = std::mem::take(pending_g);
+    match key {
+        KeyCode::Up | KeyCode::Char('k') => Some(Nav::Up),
+        KeyCode::Down | KeyCode::Char('j') => Some(Nav::Down),
+        KeyCode::Char('G') => Some(Nav::Bottom),
+        KeyCode::Char('g') if after_g => Some(Nav::Top),
+        KeyCode::Char('g') => {
+            *pending_g = true;
+            None
+        }
+        _ => None,
+    }
+}
+
+/// The index `nav` moves to in a list of `len` entries
+fn moved(index: usize, len: usize, nav: Nav) -> usize {
+    let last = len.saturating_sub(1);
+    match nav {
+        Nav::Up => index.saturating_sub(1),
+        Nav::Down => (index + 1).min(last),
+        Nav::Top => 0,
+        Nav::Bottom => last,
+    }
+}
+
+/// The end of `value` that fits in `width` columns, with a leading `…`
+/// when the start is cut off, so the cursor end of a long field stays visible
+fn visible_tail(value: &str, width: usize) -> String {
+    let len = value.chars().count();
+    if len <= width {
+        return value.to_string();
+    }
+    let skip = len - width.saturating_sub(1);
+    format!("…{}", value.chars().skip(skip).collect::<String>())
+}
+
+/// Display name of a scope
+fn scope_label(scope: ConfigScope) -> &'static str {
+    match scope {
+        ConfigScope::Global => "Global",
+        ConfigScope::Local => "Local",
+        ConfigScope::Worktree => "Worktree",
+        ConfigScope::System => "System",
+    }
+}
+
+/// Whether a terminal cell lies inside `rect`
+fn rect_contains(rect: Rect, column: u16, row: u16) -> bool {
+    column >= rect.x && column < rect.x + rect.width && row >= rect.y && row < rect.y + rect.height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_contains() {
+        let rect = Rect::new(2, 1, 10, 1);
+        assert!(rect_contains(rect, 2, 1));
+        assert!(rect_contains(rect, 11, 1));
+        assert!(!rect_contains(rect, 12, 1));
+        assert!(!rect_contains(rect, 5, 2));
+        assert!(!rect_contains(rect, 1, 1));
+    }
+
+    #[test]
+    fn test_visible_tail() {
+        assert_eq!(visible_tail("john@work.com", 20), "john@work.com");
+        assert_eq!(visible_tail("john@work.com", 5), "….com");
+        assert_eq!(visible_tail("€€€€", 3), "…€€");
+        assert_eq!(visible_tail("abc", 0), "…");
+    }
+
+    #[test]
+    fn test_nav_key() {
+        let mut pending_g = false;
+        assert_eq!(nav_key(KeyCode::Char('j'), &mut pending_g), Some(Nav::Down));
+        assert_eq!(nav_key(KeyCode::Up, &mut pending_g), Some(Nav::Up));
+        assert_eq!(nav_key(KeyCode::Char('G'), &mut pending_g), Some(Nav::Bottom));
+
+        assert_eq!(nav_key(KeyCode::Char('g'), &mut pending_g), None);
+        assert!(pending_g);
+        assert_eq!(nav_key(KeyCode::Char('g'), &mut pending_g), Some(Nav::Top));
+        assert!(!pending_g);
+
+        // Any other key in between cancels the pending `g`
+        nav_key(KeyCode::Char('g'), &mut pending_g);
+        assert_eq!(nav_key(KeyCode::Char('l'), &mut pending_g), None);
+        assert_eq!(nav_key(KeyCode::Char('g'), &mut pending_g), None);
+    }
+
+    #[test]
+    fn test_moved() {
+        assert_eq!(moved(0, 3, Nav::Up), 0);
+        assert_eq!(moved(1, 3, Nav::Down), 2);
+        assert_eq!(moved(2, 3, Nav::Down), 2);
+        assert_eq!(moved(1, 3, Nav::Top), 0);
+        assert_eq!(moved(0, 3, Nav::Bottom), 2);
+        assert_eq!(moved(0, 0, Nav::Down), 0);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::error::Result;
+use crate::profile::Profile;
+use crate::tui::input::TextInput;
+
+/// A field of the add/edit profile form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Username,
+    Email,
+    SshKey,
+}
+
+impl Field {
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Name => "Name",
+            Field::Username => "Username",
+            Field::Email => "Email",
+            Field::SshKey => "SSH Key",
+        }
+    }
+}
+
+/// The TUI form for adding or editing a profile. Every keypress and paste
+/// goes through a capped `TextInput`, so no field can grow past what
+/// `Validator` accepts.
+#[derive(Debug, Clone)]
+pub struct ProfileForm {
+    /// The profile being edited, or None when adding one
+    original: Option<Profile>,
+    fields: Vec<(Field, TextInput)>,
+    focus: usize,
+    /// Why the last submit was rejected
+    pub error: Option<String>,
+}
+
+impl ProfileForm {
+    /// Empty form for a new profile
+    pub fn add() -> Self {
+        Self {
+            original: None,
+            fields: vec![
+                (Field::Name, TextInput::profile_name()),
+                (Field::Username, TextInput::username()),
+                (Field::Email, TextInput::email()),
+                (Field::SshKey, TextInput::ssh_key_name()),
+            ],
+            focus: 0,
+            error: None,
+        }
+    }
+
+    /// Form pre-filled from an existing profile. The name is the profile's
+    /// key, so it isn't editable here.
+    pub fn edit(profile: &Profile) -> Self {
+        Self {
+            original: Some(profile.clone()),
+            fields: vec![
+                (Field::Username, TextInput::username().with_value(&profile.username)),
+                (Field::Email, TextInput::email().with_value(&profile.email)),
+                (Field::SshKey, TextInput::ssh_key_name().with_value(&profile.ssh_key_name)),
+            ],
+            focus: 0,
+            error: None,
+        }
+    }
+
+    /// The profile being edited, if any
+    pub fn original(&self) -> Option<&Profile> {
+        self.original.as_ref()
+    }
+
+    pub fn title(&self) -> String {
+        match &self.original {
+            Some(profile) => format!("Edit '{}'", profile.name),
+            None => "Add Profile".to_string(),
+        }
+    }
+
+    /// Each field with its input and whether it has the focus
+    pub fn fields(&self) -> impl Iterator<Item = (Field, &TextInput, bool)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .map(move |(i, (field, input))| (*field, input, i == self.focus))
+    }
+
+    /// Type or paste text into the focused field
+    pub fn insert(&mut self, text: &str) {
+        self.error = None;
+        self.fields[self.focus].1.insert(text);
+    }
+
+    /// Delete the last character of the focused field
+    pub fn backspace(&mut self) {
+        self.error = None;
+        self.fields[self.focus].1.backspace();
+    }
+
+    /// Move the focus to the next field, wrapping around
+    pub fn next_field(&mut self) {
+        self.focus = (self.focus + 1) % self.fields.len();
+    }
+
+    /// Move the focus to the previous field, wrapping around
+    pub fn prev_field(&mut self) {
+        self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+    }
+
+    /// Build the profile the form describes, checking every field
+    pub fn to_profile(&self) -> Result<Profile> {
+        let value = |field: Field| {
+            self.fields
+                .iter()
+                .find(|(f, _)| *f == field)
+                .map(|(_, input)| input.value().to_string())
+        };
+
+        let mut profile = match &self.original {
+            Some(profile) => profile.clone(),
+            None => Profile::new(
+                value(Field::Name).unwrap_or_default(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ),
+        };
+        profile.username = value(Field::Username).unwrap_or_default();
+        profile.email = value(Field::Email).unwrap_or_default();
+        profile.ssh_key_name = value(Field::SshKey).unwrap_or_default();
+        profile.validate()?;
+        Ok(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::validator::Validator;
+
+    fn fill_add_form(name: &str, username: &str, email: &str, ssh_key: &str) -> ProfileForm {
+        let mut form = ProfileForm::add();
+        for text in [name, username, email, ssh_key] {
+            form.insert(text);
+            form.next_field();
+        }
+        form
+    }
+
+    fn field_value(form: &ProfileForm, field: Field) -> String {
+        form.fields()
+            .find(|(f, _, _)| *f == field)
+            .map(|(_, input, _)| input.value().to_string())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_add_form_builds_profile() {
+        let form = fill_add_form("work", "johndoe", "john@work.com", "id_ed25519_work");
+        let profile = form.to_profile().unwrap();
+        assert_eq!(profile.name, "work");
+        assert_eq!(profile.username, "johndoe");
+        assert_eq!(profile.email, "john@work.com");
+        assert_eq!(profile.ssh_key_name, "id_ed25519_work");
+    }
+
+    #[test]
+    fn test_paste_is_truncated_at_cap() {
+        let form = fill_add_form(
+            &"n".repeat(500),
+            &"u".repeat(500),
+            &format!("{}@example.com", "e".repeat(500)),
+            "id_rsa",
+        );
+
+        assert_eq!(field_value(&form, Field::Name).len(), Validator::MAX_PROFILE_NAME_LEN);
+        assert_eq!(field_value(&form, Field::Username).len(), Validator::MAX_USERNAME_LEN);
+        assert_eq!(field_value(&form, Field::Email).len(), Validator::MAX_EMAIL_LEN);
+        assert!(form.fields().take(3).all(|(_, input, _)| input.at_limit()));
+
+        // The cut-off email lost its domain, so saving is refused
+        assert!(form.to_profile().is_err());
+    }
+
+    #[test]
+    fn test_typing_strips_control_chars() {
+        let mut form = ProfileForm::add();
+        for c in "wo\trk\u{1b}".chars() {
+            form.insert(&c.to_string());
+        }
+        form.next_field();
+        form.insert("john\r\ndoe");
+        form.next_field();
+        form.insert("john@\nwork.com\u{7}");
+        form.next_field();
+        form.insert("id_rsa\n");
+
+        let profile = form.to_profile().unwrap();
+        assert_eq!(profile.name, "work");
+        assert_eq!(profile.username, "johndoe");
+        assert_eq!(profile.email, "john@work.com");
+        assert_eq!(profile.ssh_key_name, "id_rsa");
+    }
+
+    #[test]
+    fn test_edit_form_keeps_name_and_other_settings() {
+        let mut original = Profile::new(
+            "work".to_string(),
+            "johndoe".to_string(),
+            "john@work.com".to_string(),
+            "id_rsa".to_string(),
+        );
+        original.color = Some("blue".to_string());
+
+        let mut form = ProfileForm::edit(&original);
+        assert_eq!(form.title(), "Edit 'work'");
+        assert!(form.fields().all(|(field, _, _)| field != Field::Name));
+
+        form.next_field();
+        for _ in 0.."john@work.com".len() {
+            form.backspace();
+        }
+        form.insert("john@new.com");
+
+        let profile = form.to_profile().unwrap();
+        assert_eq!(profile.name, "work");
+        assert_eq!(profile.email, "john@new.com");
+        assert_eq!(profile.color, Some("blue".to_string()));
+    }
+
+    #[test]
+    fn test_focus_wraps() {
+        let mut form = ProfileForm::add();
+        form.prev_field();
+        assert!(form.fields().last().unwrap().2);
+        form.next_field();
+        assert!(form.fields().next().unwrap().2);
+    }
+
+    #[test]
+    fn test_invalid_form_is_rejected() {
+        let form = fill_add_form("work", "-bad-", "john@work.com", "id_rsa");
+        assert!(form.to_profile().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::utils::validator::Validator;
+
+/// A single-line text field for the TUI forms. Typed or pasted text is
+/// capped at `max_len` bytes (the same limit `Validator` checks) and
+/// control characters are dropped, so a huge paste can't break the layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextInput {
+    value: String,
+    max_len: usize,
+}
+
+impl TextInput {
+    /// Empty field holding at most `max_len` bytes
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            value: String::new(),
+            max_len,
+        }
+    }
+
+    /// Field for a profile name
+    pub fn profile_name() -> Self {
+        Self::new(Validator::MAX_PROFILE_NAME_LEN)
+    }
+
+    /// Field for a GitHub username
+    pub fn username() -> Self {
+        Self::new(Validator::MAX_USERNAME_LEN)
+    }
+
+    /// Field for an email address
+    pub fn email() -> Self {
+        Self::new(Validator::MAX_EMAIL_LEN)
+    }
+
+    /// Field for an SSH key file name
+    pub fn ssh_key_name() -> Self {
+        Self::new(Validator::MAX_SSH_KEY_NAME_LEN)
+    }
+
+    /// Pre-fill the field (e.g. when editing), applying the same rules as typing
+    pub fn with_value(mut self, value: &str) -> Self {
+        self.value.clear();
+        self.insert(value);
+        self
+    }
+
+    /// Append typed or pasted text. Returns false if some of it was cut
+    /// off by the length cap.
+    pub fn insert(&mut self, text: &str) -> bool {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self.value.len() + c.len_utf8() > self.max_len {
+                return false;
+            }
+            self.value.push(c);
+        }
+        true
+    }
+
+    /// Remove the last character
+    pub fn backspace(&mut self) {
+        self.value.pop();
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Whether the field is full
+    pub fn at_limit(&self) -> bool {
+        self.value.len() >= self.max_len
+    }
+
+    /// Subtle indicator rendered next to a full field
+    pub fn limit_hint(&self) -> Option<String> {
+        self.at_limit().then(|| format!("max length {}", self.max_len))
+    }
+
+    /// Bytes used out of the cap, e.g. `12/39`
+    pub fn counter(&self) -> String {
+        format!("{}/{}", self.value.len(), self.max_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_caps_length() {
+        let mut input = TextInput::username();
+        assert!(!input.insert(&"a".repeat(100)));
+        assert_eq!(input.value().len(), Validator::MAX_USERNAME_LEN);
+        assert!(input.at_limit());
+        assert_eq!(input.limit_hint(), Some("max length 39".to_string()));
+
+        input.backspace();
+        assert!(!input.at_limit());
+        assert_eq!(input.limit_hint(), None);
+        assert!(input.insert("b"));
+        assert!(!input.insert("c"));
+        assert_eq!(input.counter(), "39/39");
+    }
+
+    #[test]
+    fn test_insert_strips_control_chars() {
+        let mut input = TextInput::email();
+        assert!(input.insert("john\n@exa\tmple.com\u{7}"));
+        assert_eq!(input.value(), "john@example.com");
+        assert!(Validator::validate_email(input.value()));
+    }
+
+    #[test]
+    fn test_with_value_applies_rules() {
+        let input = TextInput::profile_name().with_value(&format!("work\r{}", "x".repeat(60)));
+        assert_eq!(input.value().len(), Validator::MAX_PROFILE_NAME_LEN);
+        assert!(input.value().starts_with("workx"));
+
+        // Multi-byte characters are never split at the cap
+        let input = TextInput::new(5).with_value("ab€€");
+        assert_eq!(input.value(), "ab€");
+    }
+}
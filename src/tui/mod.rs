@@ -1 +1,4 @@
 pub mod app;
+pub mod form;
+pub mod input;
+pub mod theme;
@@ -0,0 +1,158 @@
+use crate::profile::Profile;
+use crate::utils::color::ansi_code;
+use ratatui::style::Color;
+
+/// Color palette used by the TUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Borders, titles and the global scope
+    pub primary: Color,
+    /// Local scope and secondary accents
+    pub secondary: Color,
+    /// Headings and warnings
+    pub accent: Color,
+    pub success: Color,
+    pub error: Color,
+    /// Regular text
+    pub text: Color,
+    /// Labels and less important details
+    pub muted: Color,
+    /// Hints and disabled text
+    pub dim: Color,
+    /// Foreground of the selected item
+    pub highlight_fg: Color,
+    /// Background of the selected item
+    pub highlight_bg: Color,
+    /// Whether profile names are drawn in their own colors
+    pub profile_colors: bool,
+}
+
+impl Theme {
+    /// Names of the available palettes
+    pub const NAMES: [&'static str; 3] = ["default", "mono", "solarized"];
+
+    /// Look up a palette by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default_palette()),
+            "mono" => Some(Self::mono()),
+            "solarized" => Some(Self::solarized()),
+            _ => None,
+        }
+    }
+
+    /// Get the color to render a profile's name in, falling back to the
+    /// given theme color when the profile has none or the palette ignores
+    /// profile colors
+    pub fn profile_color(&self, profile: &Profile, fallback: Color) -> Color {
+        if !self.profile_colors {
+            return fallback;
+        }
+        match profile.color.as_deref().and_then(ansi_code) {
+            // Map the ANSI foreground code onto the matching palette index
+            Some(code @ 30..=37) => Color::Indexed(code - 30),
+            Some(code @ 90..=97) => Color::Indexed(code - 90 + 8),
+            _ => fallback,
+        }
+    }
+
+    /// The original bright palette, best on dark terminals
+    fn default_palette() -> Self {
+        Self {
+            primary: Color::Cyan,
+            secondary: Color::Magenta,
+            accent: Color::Yellow,
+            success: Color::Green,
+            error: Color::Red,
+            text: Color::White,
+            muted: Color::Gray,
+            dim: Color::DarkGray,
+            highlight_fg: Color::Black,
+            highlight_bg: Color::Cyan,
+            profile_colors: true,
+        }
+    }
+
+    /// Uses the terminal's own foreground color, readable on any background
+    fn mono() -> Self {
+        Self {
+            primary: Color::Reset,
+            secondary: Color::Reset,
+            accent: Color::Reset,
+            success: Color::Reset,
+            error: Color::Reset,
+            text: Color::Reset,
+            muted: Color::Reset,
+            dim: Color::DarkGray,
+            highlight_fg: Color::Black,
+            highlight_bg: Color::Gray,
+            profile_colors: false,
+        }
+    }
+
+    /// Solarized accent colors, which work on both light and dark terminals
+    fn solarized() -> Self {
+        Self {
+            primary: Color::Rgb(38, 139, 210),
+            secondary: Color::Rgb(211, 54, 130),
+            accent: Color::Rgb(181, 137, 0),
+            success: Color::Rgb(133, 153, 0),
+            error: Color::Rgb(220, 50, 47),
+            text: Color::Rgb(101, 123, 131),
+            muted: Color::Rgb(147, 161, 161),
+            dim: Color::Rgb(88, 110, 117),
+            highlight_fg: Color::Rgb(253, 246, 227),
+            highlight_bg: Color::Rgb(38, 139, 210),
+            profile_colors: true,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::default_palette()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::color::COLOR_NAMES;
+
+    #[test]
+    fn test_from_name() {
+        for name in Theme::NAMES {
+            assert!(Theme::from_name(name).is_some(), "missing palette {}", name);
+        }
+        assert_eq!(Theme::from_name("default"), Some(Theme::default()));
+        assert!(Theme::from_name("neon").is_none());
+    }
+
+    #[test]
+    fn test_profile_color() {
+        let theme = Theme::default();
+        let mut profile = Profile::default();
+        assert_eq!(theme.profile_color(&profile, Color::Cyan), Color::Cyan);
+
+        profile.color = Some("red".to_string());
+        assert_eq!(theme.profile_color(&profile, Color::Cyan), Color::Indexed(1));
+
+        profile.color = Some("gray".to_string());
+        assert_eq!(theme.profile_color(&profile, Color::Cyan), Color::Indexed(8));
+
+        for name in COLOR_NAMES {
+            profile.color = Some(name.to_string());
+            assert_ne!(theme.profile_color(&profile, Color::Reset), Color::Reset, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_mono_ignores_profile_color() {
+        let theme = Theme::from_name("mono").unwrap();
+        let profile = Profile {
+            color: Some("red".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(theme.profile_color(&profile, Color::Reset), Color::Reset);
+    }
+}
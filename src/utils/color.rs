@@ -0,0 +1,87 @@
+use clap::ValueEnum;
+
+/// When to emit ANSI colors (the global `--color` option)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// Color when writing to a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    /// Always color, even when piped or `NO_COLOR` is set
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorMode {
+    /// Decide whether to color output going to a terminal or not
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        // https://no-color.org
+        self.resolve(is_terminal, std::env::var_os("NO_COLOR").is_some())
+    }
+
+    fn resolve(self, is_terminal: bool, no_color_env: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => is_terminal && !no_color_env,
+        }
+    }
+}
+
+/// Named colors that can be assigned to a profile
+pub const COLOR_NAMES: [&str; 9] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white", "gray",
+];
+
+/// Get the ANSI foreground code for a named color
+pub fn ansi_code(name: &str) -> Option<u8> {
+    match name {
+        "black" => Some(30),
+        "red" => Some(31),
+        "green" => Some(32),
+        "yellow" => Some(33),
+        "blue" => Some(34),
+        "magenta" => Some(35),
+        "cyan" => Some(36),
+        "white" => Some(37),
+        "gray" => Some(90),
+        _ => None,
+    }
+}
+
+/// Wrap text in ANSI escape codes for the named color
+/// Unknown color names leave the text unchanged
+pub fn paint(text: &str, color: &str) -> String {
+    match ansi_code(color) {
+        Some(code) => format!("\x1b[{}m{}\x1b[0m", code, text),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_mode_resolve() {
+        assert!(ColorMode::Always.resolve(false, true));
+        assert!(!ColorMode::Never.resolve(true, false));
+        assert!(ColorMode::Auto.resolve(true, false));
+        assert!(!ColorMode::Auto.resolve(false, false));
+        assert!(!ColorMode::Auto.resolve(true, true));
+    }
+
+    #[test]
+    fn test_every_color_has_ansi_code() {
+        for name in COLOR_NAMES {
+            assert!(ansi_code(name).is_some(), "missing code for {}", name);
+        }
+    }
+
+    #[test]
+    fn test_paint() {
+        assert_eq!(paint("work", "red"), "\x1b[31mwork\x1b[0m");
+        assert_eq!(paint("work", "gray"), "\x1b[90mwork\x1b[0m");
+        assert_eq!(paint("work", "chartreuse"), "work");
+    }
+}
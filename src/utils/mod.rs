@@ -1 +1,8 @@
+pub mod color;
+pub mod paths;
+pub mod table;
+pub mod time;
 pub mod validator;
+
+#[cfg(test)]
+pub mod testing;
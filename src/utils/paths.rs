@@ -0,0 +1,147 @@
+use crate::error::{ProfileError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that relocates every file gex manages (profiles,
+/// contexts, SSH config and keys) under a different home directory
+pub const HOME_OVERRIDE_ENV: &str = "GEX_HOME";
+
+/// Environment variable that relocates only the profiles directory
+/// (profiles.json, history, backups), e.g. when HOME is read-only
+pub const CONFIG_DIR_ENV: &str = "GEX_CONFIG_DIR";
+
+/// The home directory gex reads and writes under, honoring `GEX_HOME`
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os(HOME_OVERRIDE_ENV)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(dirs::home_dir)
+}
+
+/// Directory holding profiles.json: `GEX_CONFIG_DIR` if set, otherwise
+/// `~/.github-profile-switcher`
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os(CONFIG_DIR_ENV)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".github-profile-switcher")))
+}
+
+/// Make sure the config directory exists, turning a failure to create it
+/// (typically a read-only HOME) into an actionable error up front
+pub fn ensure_config_dir(dir: &Path) -> Result<()> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    ensure_dir(dir, "config directory").map_err(|e| match e {
+        ProfileError::PermissionDenied(_) => ProfileError::PermissionDenied(format!(
+            "Cannot create {}; the location is not writable (read-only HOME?). \
+             Set {} to a writable directory, e.g. export {}=/tmp/gex",
+            dir.display(),
+            CONFIG_DIR_ENV,
+            CONFIG_DIR_ENV
+        )),
+        e => e,
+    })
+}
+
+/// A path as text, or a clear error when it isn't valid UTF-8. Paths gex
+/// writes into the SSH config or git config must round-trip exactly, so
+/// they are never converted lossily.
+pub fn require_utf8<'a>(path: &'a Path, what: &str) -> Result<&'a str> {
+    path.to_str().ok_or_else(|| {
+        ProfileError::InvalidInput(format!(
+            "The {} path {:?} is not valid UTF-8, which gex can't write into SSH or git config. \
+             Set {} (or {}) to a UTF-8 path",
+            what, path, HOME_OVERRIDE_ENV, CONFIG_DIR_ENV
+        ))
+    })
+}
+
+/// Create `dir` (and its parents) unless it already exists, failing with a
+/// clear message when something that is not a directory is in the way
+pub fn ensure_dir(dir: &Path, what: &str) -> Result<()> {
+    if dir.exists() {
+        if dir.is_dir() {
+            return Ok(());
+        }
+        return Err(ProfileError::InvalidInput(format!(
+            "{} exists but is not a directory; the {} must be a directory. Move or remove that file and try again",
+            dir.display(),
+            what
+        )));
+    }
+
+    fs::create_dir_all(dir).map_err(|e| {
+        ProfileError::PermissionDenied(format!("Failed to create {}: {}", what, e))
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn test_ensure_dir_rejects_file() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let temp_dir = std::env::temp_dir().join(format!("gex_paths_test_{}", timestamp));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let blocker = temp_dir.join(".ssh");
+        fs::write(&blocker, "not a directory").unwrap();
+        let err = ensure_dir(&blocker, ".ssh directory").unwrap_err().to_string();
+        assert!(err.contains("is not a directory"), "{}", err);
+
+        let nested = temp_dir.join("a").join("b");
+        ensure_dir(&nested, "config directory").unwrap();
+        assert!(nested.is_dir());
+        ensure_dir(&nested, "config directory").unwrap();
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_require_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        assert_eq!(require_utf8(Path::new("/home/john/.ssh/config"), "SSH config").unwrap(), "/home/john/.ssh/config");
+
+        let path = Path::new("/home").join(OsStr::from_bytes(b"j\xffhn")).join(".ssh");
+        let err = require_utf8(&path, "SSH config").unwrap_err();
+        assert!(matches!(err, ProfileError::InvalidInput(_)));
+        let message = err.to_string();
+        assert!(message.contains("SSH config path \"/home/j\\xFFhn/.ssh\" is not valid UTF-8"), "{}", message);
+        assert!(message.contains(HOME_OVERRIDE_ENV), "{}", message);
+    }
+
+    #[test]
+    fn test_ensure_config_dir_read_only_parent() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let temp_dir = std::env::temp_dir().join(format!("gex_paths_test_{}", timestamp));
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::set_permissions(&temp_dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let config_dir = temp_dir.join(".github-profile-switcher");
+        let result = ensure_config_dir(&config_dir);
+        fs::set_permissions(&temp_dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Root ignores directory permissions, so only check when it applied
+        if !config_dir.exists() {
+            let err = result.unwrap_err();
+            assert!(matches!(err, ProfileError::PermissionDenied(_)));
+            assert!(err.to_string().contains(CONFIG_DIR_ENV), "{}", err);
+        }
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::utils::color::paint;
+
+/// A single table cell with an optional display color
+#[derive(Debug, Clone, Default)]
+pub struct Cell {
+    pub text: String,
+    pub color: Option<String>,
+}
+
+impl Cell {
+    /// Create an uncolored cell
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            color: None,
+        }
+    }
+
+    /// Create a cell painted with the named color
+    pub fn colored(text: impl Into<String>, color: Option<&str>) -> Self {
+        Self {
+            text: text.into(),
+            color: color.map(str::to_string),
+        }
+    }
+}
+
+/// A plain-text table with columns sized to fit their contents
+#[derive(Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    /// Create an empty table with the given column headers
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Append a row; missing cells are rendered empty
+    pub fn add_row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// Render the table, painting colored cells when `color` is true
+    pub fn render(&self, color: bool) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate().take(widths.len()) {
+                widths[i] = widths[i].max(cell.text.chars().count());
+            }
+        }
+
+        let mut output = String::new();
+        let headers: Vec<Cell> = self.headers.iter().map(Cell::new).collect();
+        let separator: Vec<Cell> = widths.iter().map(|w| Cell::new("-".repeat(*w))).collect();
+
+        for row in [&headers, &separator].into_iter().chain(&self.rows) {
+            let mut line = String::new();
+            for (i, width) in widths.iter().enumerate() {
+                let cell = row.get(i).cloned().unwrap_or_default();
+                let padding = " ".repeat(width - cell.text.chars().count().min(*width));
+                let text = match (&cell.color, color) {
+                    (Some(name), true) => paint(&cell.text, name),
+                    _ => cell.text,
+                };
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                line.push_str(&text);
+                line.push_str(&padding);
+            }
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+/// Shorten text to at most `max` characters, ending with an ellipsis
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut shortened: String = text.chars().take(max.saturating_sub(1)).collect();
+    shortened.push('…');
+    shortened
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_aligns_columns() {
+        let mut table = Table::new(&["NAME", "EMAIL"]);
+        table.add_row(vec![Cell::new("personal"), Cell::new("me@example.com")]);
+        table.add_row(vec![Cell::new("w"), Cell::new("w@x.io")]);
+
+        assert_eq!(
+            table.render(false),
+            "NAME      EMAIL\n\
+             --------  --------------\n\
+             personal  me@example.com\n\
+             w         w@x.io\n"
+        );
+    }
+
+    #[test]
+    fn test_render_color() {
+        let mut table = Table::new(&["NAME", "ACTIVE"]);
+        table.add_row(vec![Cell::colored("work", Some("red")), Cell::new("global")]);
+
+        let plain = table.render(false);
+        assert!(!plain.contains('\x1b'));
+
+        // Padding is computed from the text, not the escape codes
+        let colored = table.render(true);
+        assert!(colored.contains("\x1b[31mwork\x1b[0m  global"));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("exactly10!", 10), "exactly10!");
+        assert_eq!(truncate("a.very.long.address@example.com", 10), "a.very.lo…");
+    }
+}
@@ -0,0 +1,8 @@
+use std::sync::{Mutex, MutexGuard};
+
+static CWD_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serialize tests that change the process working directory
+pub fn lock_cwd() -> MutexGuard<'static, ()> {
+    CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
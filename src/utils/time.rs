@@ -0,0 +1,96 @@
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use std::fmt::Display;
+use crate::error::{ProfileError, Result};
+
+/// Current time as stored on disk: RFC 3339 in UTC
+pub fn now_rfc3339() -> String {
+    Utc::now().to_rfc3339()
+}
+
+/// Render a UTC timestamp in the user's local timezone
+pub fn format_local(timestamp: DateTime<Utc>) -> String {
+    format_in(timestamp, &Local)
+}
+
+/// Render a stored RFC 3339 timestamp in the user's local timezone,
+/// or return it unchanged if it doesn't parse
+pub fn format_stored(stored: &str) -> String {
+    match DateTime::parse_from_rfc3339(stored) {
+        Ok(timestamp) => format_local(timestamp.with_timezone(&Utc)),
+        Err(_) => stored.to_string(),
+    }
+}
+
+/// Parse a human duration such as `30m`, `24h`, `7d` or `2w`
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let invalid = || ProfileError::InvalidInput(format!(
+        "Invalid duration '{}'; use a number followed by s, m, h, d or w (e.g. 7d, 24h)",
+        input
+    ));
+
+    let input = input.trim();
+    let unit_at = input.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = input.split_at(unit_at);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    };
+    duration.ok_or_else(invalid)
+}
+
+/// Render a UTC timestamp in the given timezone, with its offset
+fn format_in<Tz: TimeZone>(timestamp: DateTime<Utc>, tz: &Tz) -> String
+where
+    Tz::Offset: Display,
+{
+    timestamp.with_timezone(tz).format("%Y-%m-%d %H:%M:%S %:z").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StorageData;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn test_storage_stays_utc() {
+        let stored = StorageData::new().last_modified;
+        let parsed = DateTime::parse_from_rfc3339(&stored).unwrap();
+        assert_eq!(parsed.offset().local_minus_utc(), 0);
+        assert!(now_rfc3339().ends_with("+00:00"));
+    }
+
+    #[test]
+    fn test_format_in_timezone() {
+        let timestamp = DateTime::parse_from_rfc3339("2024-03-01T22:30:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(format_in(timestamp, &tokyo), "2024-03-02 07:30:00 +09:00");
+
+        let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+        assert_eq!(format_in(timestamp, &new_york), "2024-03-01 17:30:00 -05:00");
+
+        assert_eq!(format_stored("not a time"), "not a time");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_duration(" 45s ").unwrap(), Duration::seconds(45));
+
+        for bad in ["", "7", "d", "7x", "-7d", "1.5h", "7 d", "99999999999999999999d"] {
+            assert!(matches!(parse_duration(bad), Err(ProfileError::InvalidInput(_))), "{}", bad);
+        }
+    }
+}
@@ -1,12 +1,22 @@
+use crate::utils::color::COLOR_NAMES;
 use regex::Regex;
 
 pub struct Validator;
 
 impl Validator {
+    /// Longest accepted profile name
+    pub const MAX_PROFILE_NAME_LEN: usize = 50;
+    /// Longest accepted GitHub username (GitHub's own limit)
+    pub const MAX_USERNAME_LEN: usize = 39;
+    /// Longest accepted email address (RFC 5321 path limit)
+    pub const MAX_EMAIL_LEN: usize = 254;
+    /// Longest accepted SSH key file name (the usual file name limit)
+    pub const MAX_SSH_KEY_NAME_LEN: usize = 255;
+
     /// Validate email address format
     /// Accepts standard email format: user@domain.tld
     pub fn validate_email(email: &str) -> bool {
-        if email.is_empty() {
+        if email.is_empty() || email.len() > Self::MAX_EMAIL_LEN {
             return false;
         }
 
@@ -23,7 +33,7 @@ impl Validator {
     /// Allows: alphanumeric characters, hyphens, and underscores
     /// Must be between 1 and 50 characters
     pub fn validate_profile_name(name: &str) -> bool {
-        if name.is_empty() || name.len() > 50 {
+        if name.is_empty() || name.len() > Self::MAX_PROFILE_NAME_LEN {
             return false;
         }
 
@@ -36,7 +46,7 @@ impl Validator {
     /// Allows valid file name characters
     /// Common SSH key names: id_rsa, id_ed25519, id_ecdsa, etc.
     pub fn validate_ssh_key_name(key_name: &str) -> bool {
-        if key_name.is_empty() || key_name.len() > 255 {
+        if key_name.is_empty() || key_name.len() > Self::MAX_SSH_KEY_NAME_LEN {
             return false;
         }
 
@@ -49,6 +59,11 @@ impl Validator {
             }
         }
 
+        // Control characters (e.g. newlines) could inject SSH config lines
+        if key_name.chars().any(|c| c.is_control()) {
+            return false;
+        }
+
         // Must not start or end with whitespace
         if key_name.trim() != key_name {
             return false;
@@ -62,7 +77,7 @@ impl Validator {
     /// Cannot start or end with a hyphen
     /// Maximum 39 characters
     pub fn validate_username(username: &str) -> bool {
-        if username.is_empty() || username.len() > 39 {
+        if username.is_empty() || username.len() > Self::MAX_USERNAME_LEN {
             return false;
         }
 
@@ -75,6 +90,48 @@ impl Validator {
         let username_regex = Regex::new(r"^[a-zA-Z0-9-]+$").unwrap();
         username_regex.is_match(username)
     }
+
+    /// Validate a branch name for `init.defaultBranch`
+    /// Follows the main `git check-ref-format` rules
+    pub fn validate_branch_name(branch: &str) -> bool {
+        if branch.is_empty() || branch.len() > 255 {
+            return false;
+        }
+
+        if branch.starts_with('-') || branch.starts_with('/') || branch.ends_with('/') {
+            return false;
+        }
+
+        if branch.ends_with('.') || branch.ends_with(".lock") || branch.contains("..") || branch.contains("@{") {
+            return false;
+        }
+
+        let invalid_chars = [' ', '~', '^', ':', '?', '*', '[', '\\'];
+        !branch
+            .chars()
+            .any(|c| c.is_control() || invalid_chars.contains(&c))
+    }
+
+    /// Validate a git config key in `section.key` or
+    /// `section.subsection.key` form
+    pub fn validate_git_config_key(key: &str) -> bool {
+        let (Some((section, rest)), Some((_, variable))) = (key.split_once('.'), key.rsplit_once('.')) else {
+            return false;
+        };
+        let subsection = rest.strip_suffix(variable).and_then(|s| s.strip_suffix('.'));
+
+        !section.is_empty()
+            && section.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && variable.starts_with(|c: char| c.is_ascii_alphabetic())
+            && variable.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && subsection.is_none_or(|s| !s.is_empty() && !s.chars().any(|c| c.is_whitespace() || c.is_control()))
+    }
+
+    /// Validate a profile color name
+    /// Must be one of the supported named ANSI colors
+    pub fn validate_color(color: &str) -> bool {
+        COLOR_NAMES.contains(&color)
+    }
 }
 
 #[cfg(test)]
@@ -96,6 +153,7 @@ mod tests {
         assert!(!Validator::validate_email("user@"));
         assert!(!Validator::validate_email("user@domain"));
         assert!(!Validator::validate_email("user @domain.com"));
+        assert!(!Validator::validate_email(&format!("{}@example.com", "a".repeat(250)))); // Too long
     }
 
     #[test]
@@ -132,6 +190,8 @@ mod tests {
         assert!(!Validator::validate_ssh_key_name(" key")); // Leading space
         assert!(!Validator::validate_ssh_key_name("key ")); // Trailing space
         assert!(!Validator::validate_ssh_key_name(&"a".repeat(256))); // Too long
+        assert!(!Validator::validate_ssh_key_name("key\n  ProxyCommand x")); // Newline
+        assert!(!Validator::validate_ssh_key_name("key\tname")); // Control char
     }
 
     #[test]
@@ -151,4 +211,45 @@ mod tests {
         assert!(!Validator::validate_username("user@name")); // Special char
         assert!(!Validator::validate_username(&"a".repeat(40))); // Too long
     }
+
+    #[test]
+    fn test_validate_branch_name() {
+        assert!(Validator::validate_branch_name("main"));
+        assert!(Validator::validate_branch_name("trunk"));
+        assert!(Validator::validate_branch_name("release/v1.0"));
+
+        assert!(!Validator::validate_branch_name(""));
+        assert!(!Validator::validate_branch_name("-main"));
+        assert!(!Validator::validate_branch_name("has space"));
+        assert!(!Validator::validate_branch_name("a..b"));
+        assert!(!Validator::validate_branch_name("main.lock"));
+        assert!(!Validator::validate_branch_name("main\n"));
+    }
+
+    #[test]
+    fn test_validate_git_config_key() {
+        assert!(Validator::validate_git_config_key("pull.rebase"));
+        assert!(Validator::validate_git_config_key("core.autocrlf"));
+        assert!(Validator::validate_git_config_key("url.git@github.com:acme/.insteadOf"));
+
+        assert!(!Validator::validate_git_config_key("badkey"));
+        assert!(!Validator::validate_git_config_key(".rebase"));
+        assert!(!Validator::validate_git_config_key("pull."));
+        assert!(!Validator::validate_git_config_key("pull.1rebase"));
+        assert!(!Validator::validate_git_config_key("pu ll.rebase"));
+        assert!(!Validator::validate_git_config_key("a..b"));
+        assert!(!Validator::validate_git_config_key("a.my branch.b"));
+    }
+
+    #[test]
+    fn test_validate_color() {
+        assert!(Validator::validate_color("red"));
+        assert!(Validator::validate_color("cyan"));
+        assert!(Validator::validate_color("gray"));
+
+        assert!(!Validator::validate_color(""));
+        assert!(!Validator::validate_color("Red")); // Case sensitive
+        assert!(!Validator::validate_color("orange"));
+        assert!(!Validator::validate_color("#ff0000"));
+    }
 }
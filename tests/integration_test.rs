@@ -130,6 +130,18 @@ fn test_status_command() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("Global") || stdout.contains("Status"));
 
+    let output = Command::new(&binary)
+        .args(["status", "--scope", "local"])
+        .env("HOME", &temp_dir)
+        .env("USERPROFILE", &temp_dir)
+        .output()
+        .expect("Failed to execute gex");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Local"));
+    assert!(!stdout.contains("Global"));
+
     cleanup_test_env(&temp_dir);
 }
 
@@ -174,11 +186,895 @@ fn test_switch_command_help() {
     assert!(stdout.contains("global"));
 }
 
-// Note: Full end-to-end tests that actually create profiles, switch them,
-// and verify git/SSH config changes are not included here because they would:
-// 1. Modify the user's actual git configuration
-// 2. Modify the user's SSH config
-// 3. Require actual SSH keys to exist
-//
-// These tests verify the CLI interface works correctly without side effects.
-// For full integration testing, use a dedicated test environment or CI/CD pipeline.
+#[test]
+fn test_split_export_and_import_dir() {
+    let binary = get_binary_path();
+    let source_home = create_test_env();
+    let target_home = create_test_env();
+    let export_dir = source_home.join("exported");
+
+    let gex = |home: &PathBuf, args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", home)
+            .env("USERPROFILE", home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    for name in ["personal", "work"] {
+        let email = format!("{}@example.com", name);
+        let key = format!("id_{}", name);
+        let output = gex(&source_home, &["add", name, "-u", name, "-e", &email, "-s", &key]);
+        assert!(output.status.success());
+    }
+
+    let export_dir_arg = export_dir.to_str().unwrap();
+    let output = gex(&source_home, &["export", "--output-dir", export_dir_arg, "--split"]);
+    assert!(output.status.success());
+    assert!(export_dir.join("personal.json").exists());
+    assert!(export_dir.join("work.json").exists());
+
+    // An invalid file is reported without blocking the others, but fails the run
+    fs::write(export_dir.join("broken.json"), "{").unwrap();
+
+    let output = gex(&target_home, &["import", "--dir", export_dir_arg]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Rejected 'broken.json'"));
+    assert!(stdout.contains("Imported 2 profile(s)"));
+    assert!(stdout.contains("2 added, 0 already present, 0 name conflict(s), 1 invalid"));
+
+    // Re-importing skips what is already there; --json carries the same summary
+    let output = gex(&target_home, &["import", "--dir", export_dir_arg, "--json"]);
+    assert!(!output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["added"], serde_json::json!([]));
+    assert_eq!(summary["unchanged"], serde_json::json!(["personal", "work"]));
+    assert_eq!(summary["invalid"][0]["source"], "broken.json");
+
+    cleanup_test_env(&source_home);
+    cleanup_test_env(&target_home);
+}
+
+#[test]
+fn test_list_output_to_file() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let output_file = home.join("profiles.txt");
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    let output = gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    assert!(output.status.success());
+
+    let output = gex(&["list", "--output", output_file.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let written = fs::read_to_string(&output_file).unwrap();
+    assert!(written.contains("Available profiles:"));
+    assert!(written.contains("● work"));
+    assert!(written.contains("Email: john@work.com"));
+
+    // The --check summary goes to the file too, not stdout
+    fs::create_dir_all(home.join(".ssh")).unwrap();
+    fs::write(home.join(".ssh").join("id_work"), "dummy key").unwrap();
+    let output = gex(&["list", "--check", "--output", output_file.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+    assert_eq!(fs::read_to_string(&output_file).unwrap(), "✓ All 1 profile(s) OK\n");
+
+    // Commands that change state refuse --output
+    let output = gex(&["delete", "work", "--output", output_file.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_self_test_stays_in_sandbox() {
+    let ssh_keygen_available = Command::new("ssh-keygen").arg("-?").output().is_ok();
+    if !ssh_keygen_available {
+        return;
+    }
+
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let output = Command::new(&binary)
+        .arg("self-test")
+        .env("HOME", &home)
+        .env("USERPROFILE", &home)
+        .output()
+        .expect("Failed to execute gex");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("All systems go"));
+
+    // Nothing was written to the invoking user's home
+    assert_eq!(fs::read_dir(&home).unwrap().count(), 0);
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_import_no_validate_legacy_profile() {
+    let binary = get_binary_path();
+    let source_home = create_test_env();
+    let target_home = create_test_env();
+    let export_file = source_home.join("export.json");
+
+    let gex = |home: &PathBuf, args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", home)
+            .env("USERPROFILE", home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    let output = gex(&source_home, &["add", "legacy", "-u", "old-user", "-e", "old@example.com", "-s", "id_old"]);
+    assert!(output.status.success());
+    let output = gex(&source_home, &["export", export_file.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    // Underscores in usernames were accepted by older versions
+    let contents = fs::read_to_string(&export_file).unwrap();
+    fs::write(&export_file, contents.replace("old-user", "old_user")).unwrap();
+
+    let output = gex(&target_home, &["import", export_file.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Imported 0 profile(s)"));
+
+    let output = gex(&target_home, &["import", export_file.to_str().unwrap(), "--no-validate"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("'legacy' fails validation"));
+    assert!(stdout.contains("Imported 1 profile(s)"));
+
+    cleanup_test_env(&source_home);
+    cleanup_test_env(&target_home);
+}
+
+// Note: Full end-to-end tests that switch profiles and check the resulting
+// git/SSH config live in sandbox_test.rs, which isolates HOME, GEX_HOME and
+// git's global/system config so the user's real setup is never touched.
+
+#[test]
+fn test_list_installed_keys() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    fs::create_dir_all(home.join(".ssh")).unwrap();
+    fs::write(home.join(".ssh").join("id_work"), "key").unwrap();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+    assert!(gex(&["add", "oss", "-u", "john-oss", "-e", "john@oss.dev", "-s", "id_oss"]).status.success());
+
+    let output = gex(&["list", "--installed-keys"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("SSH Key: id_work ✓"));
+    assert!(stdout.contains("SSH Key: id_oss ✗"));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_status_in_repo_without_commits() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let repo = home.join("repo");
+    fs::create_dir_all(&repo).unwrap();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .current_dir(&repo)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+    let git = |args: &[&str]| Command::new("git").args(args).current_dir(&repo).env("HOME", &home).output().unwrap();
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+    assert!(git(&["init", "-q"]).status.success());
+
+    let stdout = String::from_utf8_lossy(&gex(&["status", "--scope", "local"]).stdout).to_string();
+    assert!(stdout.contains("Note: repo has no commits yet"), "{}", stdout);
+
+    assert!(git(&["config", "user.name", "john-work"]).status.success());
+    assert!(git(&["config", "user.email", "john@work.com"]).status.success());
+    let stdout = String::from_utf8_lossy(&gex(&["status", "--scope", "local"]).stdout).to_string();
+    assert!(stdout.contains("Profile: work"));
+    assert!(stdout.contains("local identity set but unused"), "{}", stdout);
+
+    // The note goes away after the first commit
+    assert!(git(&["commit", "-q", "--allow-empty", "-m", "init"]).status.success());
+    let stdout = String::from_utf8_lossy(&gex(&["status", "--scope", "local"]).stdout).to_string();
+    assert!(!stdout.contains("Note:"), "{}", stdout);
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_complete_ssh_keys_and_profiles() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let ssh_dir = home.join(".ssh");
+    fs::create_dir_all(&ssh_dir).unwrap();
+    for name in ["id_work", "id_work.pub", "id_oss", "config", "known_hosts"] {
+        fs::write(ssh_dir.join(name), "").unwrap();
+    }
+
+    let gex = |args: &[&str]| {
+        let output = Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    assert_eq!(gex(&["__complete", "ssh-keys"]), "id_oss\nid_work\n");
+    assert_eq!(gex(&["__complete", "ssh-keys", "id_w"]), "id_work\n");
+
+    gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    assert_eq!(gex(&["__complete", "profiles"]), "work\n");
+
+    // Hidden from help
+    assert!(!gex(&["--help"]).contains("__complete"));
+
+    cleanup_test_env(&home);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_clone_all_from_org() {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::PermissionsExt;
+
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let bin = home.join("bin");
+    let remotes = home.join("remotes");
+    let dest = home.join("src");
+    fs::create_dir_all(&bin).unwrap();
+    fs::create_dir_all(&dest).unwrap();
+
+    // Stand-in for the GitHub CLI
+    let gh = bin.join("gh");
+    fs::write(&gh, "#!/bin/sh\necho '[{\"name\":\"web\"},{\"name\":\"api\"},{\"name\":\"docs\"}]'\n").unwrap();
+    fs::set_permissions(&gh, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let git = |args: &[&str]| Command::new("git").args(args).env("HOME", &home).output().unwrap();
+    for repo in ["web", "api"] {
+        assert!(git(&["init", "-q", "--bare", remotes.join(format!("{}.git", repo)).to_str().unwrap()]).status.success());
+    }
+    // docs is already cloned
+    fs::create_dir_all(dest.join("docs")).unwrap();
+
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap());
+    let gex = |args: &[&str], path: &str| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .env("PATH", path)
+            // Send the profile's host alias to the local bare repositories
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", format!("url.{}/.insteadOf", remotes.display()))
+            .env("GIT_CONFIG_VALUE_0", "git@github.com-work:acme/")
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"], &path).status.success());
+
+    let output = gex(&["clone", "work", "--all-from", "acme", "--dir", dest.to_str().unwrap()], &path);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Cloned 2, skipped 1 already present, 0 failed"), "{}", stdout);
+
+    let email = git(&["-C", dest.join("api").to_str().unwrap(), "config", "--local", "user.email"]);
+    assert_eq!(String::from_utf8_lossy(&email.stdout).trim(), "john@work.com");
+    let ssh_config = fs::read_to_string(home.join(".ssh").join("config")).unwrap();
+    assert!(ssh_config.contains("Host github.com-work"));
+
+    // A dry run reports what it would clone without counting it as cloned
+    let fresh = home.join("fresh");
+    let output = gex(&["--dry-run", "clone", "work", "--all-from", "acme", "--dir", fresh.to_str().unwrap()], &path);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Would clone 3, skipped 0 already present"), "{}", stdout);
+    assert!(!stdout.contains("Cloned") && !fresh.exists(), "{}", stdout);
+
+    // A non-UTF-8 target is refused rather than cloned to a mangled path
+    let bad_dir = home.join(std::ffi::OsStr::from_bytes(b"sr\xffc"));
+    let output = Command::new(&binary)
+        .args(["clone", "work", "--all-from", "acme", "--dir"])
+        .arg(&bad_dir)
+        .env("HOME", &home)
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is not valid UTF-8"));
+    assert!(!bad_dir.exists());
+
+    // Without gh on PATH the command fails with a hint
+    let no_gh = home.join("no-gh");
+    fs::create_dir_all(&no_gh).unwrap();
+    let output = gex(&["clone", "work", "--all-from", "acme"], no_gh.to_str().unwrap());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("gh (GitHub CLI) not found"));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_profile_description() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+    let add = ["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"];
+
+    let too_long = "x".repeat(201);
+    assert!(!gex(&[&add[..], &["--description", &too_long]].concat()).status.success());
+
+    let output = gex(&[&add[..], &["--description", "acme work account,\nexpires 2025"]].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&gex(&["list", "--verbose"]).stdout).to_string();
+    assert!(stdout.contains("    Description: acme work account, expires 2025\n"));
+    let stdout = String::from_utf8_lossy(&gex(&["list"]).stdout).to_string();
+    assert!(!stdout.contains("Description"));
+
+    assert!(gex(&["edit", "work", "--description", "personal GitHub"]).status.success());
+    let stdout = String::from_utf8_lossy(&gex(&["list", "-v", "--format", "table"]).stdout).to_string();
+    assert!(stdout.contains("DESCRIPTION") && stdout.contains("personal GitHub"));
+
+    assert!(gex(&["edit", "work", "--description", ""]).status.success());
+    let stdout = String::from_utf8_lossy(&gex(&["list", "-v"]).stdout).to_string();
+    assert!(!stdout.contains("Description"));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_edit_git_preferences_without_prompts() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+    let profile = || fs::read_to_string(home.join(".github-profile-switcher").join("profiles.json")).unwrap();
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+
+    let output = gex(&["edit", "work", "--pull-rebase", "true", "--default-branch", "trunk"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let exported = profile();
+    assert!(exported.contains("\"pull_rebase\": true") && exported.contains("\"default_branch\": \"trunk\""), "{}", exported);
+
+    assert!(!gex(&["edit", "work", "--pull-rebase", "maybe"]).status.success());
+    assert!(!gex(&["edit", "work", "--default-branch", "bad..branch"]).status.success());
+
+    assert!(gex(&["edit", "work", "--pull-rebase", "none", "--default-branch", "none"]).status.success());
+    let exported = profile();
+    assert!(!exported.contains("pull_rebase") && !exported.contains("default_branch"), "{}", exported);
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_color_flag_after_subcommand() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let gex = |args: &[&str]| {
+        let output = Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .env_remove("NO_COLOR")
+            .output()
+            .expect("Failed to execute gex");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work", "--profile-color", "red"]);
+
+    // Piped output is plain unless colors are forced, before or after the subcommand
+    assert!(!gex(&["list", "--format", "table"]).contains('\x1b'));
+    assert!(gex(&["list", "--format", "table", "--color", "always"]).contains('\x1b'));
+    assert!(gex(&["--color", "always", "list", "--format", "table"]).contains('\x1b'));
+    assert!(!gex(&["list", "--format", "table", "--color", "always", "--no-color"]).contains('\x1b'));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_export_select() {
+    let binary = get_binary_path();
+    let source = create_test_env();
+    let target = create_test_env();
+    let export_file = source.join("work.json");
+
+    let gex = |home: &PathBuf, args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", home)
+            .env("USERPROFILE", home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    for (name, user, email, key) in [
+        ("work", "john-work", "john@work.com", "id_work"),
+        ("work-ci", "john-ci", "ci@work.com", "id_ci"),
+        ("personal", "john-doe", "john@home.dev", "id_home"),
+    ] {
+        assert!(gex(&source, &["add", name, "-u", user, "-e", email, "-s", key]).status.success());
+    }
+
+    // An unknown name fails without writing anything
+    let output = gex(&source, &["export", export_file.to_str().unwrap(), "--select", "work,nope"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nope"));
+    assert!(!export_file.exists());
+
+    let output = gex(&source, &["export", export_file.to_str().unwrap(), "--select", "work-ci,work"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // The subset is still a regular export
+    let output = gex(&target, &["import", export_file.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&gex(&target, &["list"]).stdout).to_string();
+    assert!(stdout.contains("● work\n") && stdout.contains("● work-ci\n"));
+    assert!(!stdout.contains("personal"));
+
+    // Repeating the flag works too
+    let stdout = String::from_utf8_lossy(&gex(&source, &["export", "--select", "personal", "--select", "work"]).stdout).to_string();
+    assert!(stdout.contains("\"personal\"") && stdout.contains("\"work\"") && !stdout.contains("work-ci"));
+
+    cleanup_test_env(&source);
+    cleanup_test_env(&target);
+}
+
+#[test]
+fn test_config_dir_override() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let config_dir = home.join("writable").join("gex");
+
+    let output = Command::new(&binary)
+        .args(["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"])
+        .env("HOME", &home)
+        .env("USERPROFILE", &home)
+        .env("GEX_CONFIG_DIR", &config_dir)
+        .output()
+        .expect("Failed to execute gex");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert!(config_dir.join("profiles.json").exists());
+    assert!(!home.join(".github-profile-switcher").exists());
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_uninstall_keeps_unrelated_files() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let shared = home.join("shared");
+    let gex = |args: &[&str], config_dir: &std::path::Path| {
+        let output = Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .env("GEX_CONFIG_DIR", config_dir)
+            .output()
+            .expect("Failed to execute gex");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let add = ["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"];
+
+    // A config directory shared with other files keeps them
+    gex(&add, &shared);
+    fs::write(shared.join("notes.txt"), "not gex's").unwrap();
+    let stdout = gex(&["uninstall", "--config", "--yes"], &shared);
+    assert!(stdout.contains("kept"), "{}", stdout);
+    assert!(!shared.join("profiles.json").exists());
+    assert_eq!(fs::read_to_string(shared.join("notes.txt")).unwrap(), "not gex's");
+
+    // A directory only gex used is removed entirely
+    let own = home.join("own");
+    gex(&add, &own);
+    gex(&["uninstall", "--config", "--yes"], &own);
+    assert!(!own.exists());
+
+    cleanup_test_env(&home);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_home_is_a_clean_error() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let bad_home = home.join(OsStr::from_bytes(b"j\xffhn"));
+
+    for args in [&["list"][..], &["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]] {
+        let output = Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("GEX_HOME", &bad_home)
+            .output()
+            .expect("Failed to execute gex");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("is not valid UTF-8"), "{}", stderr);
+        assert!(!stderr.contains("panicked"), "{}", stderr);
+    }
+
+    // Nothing was written under the unrepresentable path
+    assert!(!bad_home.exists());
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_list_remote_match() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let repo = home.join("repo");
+    fs::create_dir_all(&repo).unwrap();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .current_dir(&repo)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+    assert!(gex(&["add", "oss", "-u", "john-oss", "-e", "john@oss.dev", "-s", "id_oss"]).status.success());
+
+    // Outside a repository the list is printed with a note
+    let stdout = String::from_utf8_lossy(&gex(&["list", "--remote-match"]).stdout).to_string();
+    assert!(stdout.contains("No origin remote"));
+    assert!(stdout.contains("● work\n"));
+
+    let git = |args: &[&str]| Command::new("git").args(args).current_dir(&repo).output().unwrap();
+    assert!(git(&["init", "-q"]).status.success());
+    assert!(git(&["remote", "add", "origin", "git@github.com-work:acme/app.git"]).status.success());
+
+    let stdout = String::from_utf8_lossy(&gex(&["list", "--remote-match"]).stdout).to_string();
+    assert!(stdout.contains("● work  ← matches this repo's origin"));
+    assert!(stdout.contains("● oss\n"));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_backup_create_and_restore() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let backups = home.join("backups");
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+    assert!(gex(&["ssh-sync"]).status.success());
+    let ssh_config = fs::read_to_string(home.join(".ssh").join("config")).unwrap();
+
+    let output = gex(&["backup", "create", "--dir", backups.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let archive = fs::read_dir(&backups).unwrap().next().unwrap().unwrap().path();
+
+    // Risky change: the profile is replaced by another one
+    assert!(gex(&["delete", "work", "-y"]).status.success());
+    assert!(gex(&["add", "oss", "-u", "john-oss", "-e", "john@oss.dev", "-s", "id_oss"]).status.success());
+    assert!(gex(&["ssh-sync"]).status.success());
+
+    let output = gex(&["backup", "restore", archive.to_str().unwrap(), "-y"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&gex(&["list"]).stdout).to_string();
+    assert!(stdout.contains("● work"));
+    assert!(!stdout.contains("● oss"));
+    assert_eq!(fs::read_to_string(home.join(".ssh").join("config")).unwrap(), ssh_config);
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_switch_comment_in_history() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    fs::create_dir_all(home.join(".ssh")).unwrap();
+    fs::write(home.join(".ssh").join("id_work"), "key").unwrap();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+    let stdout = String::from_utf8_lossy(&gex(&["history"]).stdout).to_string();
+    assert!(stdout.contains("No matching switches recorded"));
+
+    let output = gex(&["switch", "work", "--global", "--comment", "onboarding ticket 1234"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&gex(&["history"]).stdout).to_string();
+    assert!(stdout.contains("global   work — onboarding ticket 1234"));
+
+    let stdout = String::from_utf8_lossy(&gex(&["history", "--since", "1h", "--profile", "work"]).stdout).to_string();
+    assert!(stdout.contains("onboarding ticket 1234"));
+    let stdout = String::from_utf8_lossy(&gex(&["history", "--profile", "oss"]).stdout).to_string();
+    assert!(stdout.contains("No matching switches recorded"));
+    let output = gex(&["history", "--since", "sometime"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid duration 'sometime'"));
+
+    // Overlong notes are rejected before anything changes
+    let long = "x".repeat(201);
+    assert!(!gex(&["switch", "work", "--global", "--comment", &long]).status.success());
+    assert_eq!(String::from_utf8_lossy(&gex(&["history"]).stdout).lines().count(), 1);
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_rename_key() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    let ssh_dir = home.join(".ssh");
+    fs::create_dir_all(&ssh_dir).unwrap();
+    fs::write(ssh_dir.join("id_old"), "private").unwrap();
+    fs::write(ssh_dir.join("id_old.pub"), "public").unwrap();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_old"]).status.success());
+    assert!(gex(&["ssh-sync"]).status.success());
+
+    // An existing target aborts before anything changes
+    fs::write(ssh_dir.join("id_new.pub"), "other").unwrap();
+    assert!(!gex(&["rename-key", "id_old", "id_new"]).status.success());
+    assert!(ssh_dir.join("id_old").exists());
+    fs::remove_file(ssh_dir.join("id_new.pub")).unwrap();
+
+    let output = gex(&["rename-key", "id_old", "id_new"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!ssh_dir.join("id_old").exists());
+    assert_eq!(fs::read_to_string(ssh_dir.join("id_new")).unwrap(), "private");
+    assert_eq!(fs::read_to_string(ssh_dir.join("id_new.pub")).unwrap(), "public");
+
+    let stdout = String::from_utf8_lossy(&gex(&["list"]).stdout).to_string();
+    assert!(stdout.contains("SSH Key: id_new"));
+    let ssh_config = fs::read_to_string(ssh_dir.join("config")).unwrap();
+    assert!(ssh_config.contains("id_new"));
+    assert!(!ssh_config.contains("id_old"));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_add_derives_ssh_key_name() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+    fs::create_dir_all(home.join(".ssh")).unwrap();
+    fs::write(home.join(".ssh").join("id_ed25519_oss.pub"), "taken").unwrap();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    let output = gex(&["add", "work", "-u", "john-work", "-e", "john@work.com"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Using SSH key name 'id_ed25519_work'"));
+    // Without a terminal the key isn't generated, only suggested
+    assert!(stdout.contains("ssh-keygen -t ed25519 -C john@work.com"));
+
+    // An existing key file gets a numeric suffix instead of being reused
+    assert!(gex(&["add", "oss", "-u", "john-oss", "-e", "john@oss.dev"]).status.success());
+
+    let stdout = String::from_utf8_lossy(&gex(&["list"]).stdout).to_string();
+    assert!(stdout.contains("SSH Key: id_ed25519_work\n"));
+    assert!(stdout.contains("SSH Key: id_ed25519_oss_2\n"));
+
+    cleanup_test_env(&home);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_sets_git_environment() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .env_remove("GIT_CONFIG_COUNT")
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+
+    let output = gex(&["run", "work", "--", "env"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "GIT_AUTHOR_NAME=john-work",
+        "GIT_AUTHOR_EMAIL=john@work.com",
+        "GIT_COMMITTER_NAME=john-work",
+        "GIT_COMMITTER_EMAIL=john@work.com",
+    ] {
+        assert!(stdout.lines().any(|line| line == expected), "missing {}", expected);
+    }
+    let key_path = home.join(".ssh").join("id_work");
+    let ssh_command = format!("GIT_SSH_COMMAND=ssh -i '{}' -o IdentitiesOnly=yes", key_path.display());
+    assert!(stdout.lines().any(|line| line == ssh_command));
+
+    // Config-reading git commands see the profile too
+    let output = gex(&["run", "work", "--", "git", "config", "user.email"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "john@work.com");
+
+    // The command's exit status is passed through
+    assert_eq!(gex(&["run", "work", "--", "sh", "-c", "exit 7"]).status.code(), Some(7));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_add_ssh_port() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    for port in ["0", "70000", "ssh"] {
+        let output = gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work", "--port", port]);
+        assert!(!output.status.success(), "port {} was accepted", port);
+    }
+
+    let output = gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work", "--port", "443"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(gex(&["ssh-sync"]).status.success());
+
+    let ssh_config = fs::read_to_string(home.join(".ssh").join("config")).unwrap();
+    assert!(ssh_config.contains("Host github.com-work\n  HostName ssh.github.com\n  Port 443\n"));
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_add_force_overwrites_existing_profile() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let gex = |args: &[&str]| {
+        Command::new(&binary)
+            .args(args)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .output()
+            .expect("Failed to execute gex")
+    };
+
+    assert!(gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]).status.success());
+
+    // Without --force an existing profile is still an error
+    let output = gex(&["add", "work", "-u", "john-work", "-e", "john@acme.com", "-s", "id_work"]);
+    assert!(!output.status.success());
+
+    for _ in 0..2 {
+        let output = gex(&["add", "work", "-u", "john-work", "-e", "john@acme.com", "-s", "id_work", "--force"]);
+        assert!(output.status.success());
+    }
+
+    let stdout = String::from_utf8_lossy(&gex(&["list"]).stdout).to_string();
+    assert!(stdout.contains("Email: john@acme.com"));
+    assert_eq!(stdout.matches("● work").count(), 1);
+
+    cleanup_test_env(&home);
+}
+
+#[test]
+fn test_json_errors() {
+    let binary = get_binary_path();
+    let home = create_test_env();
+
+    let output = Command::new(&binary)
+        .args(["--json-errors", "switch", "nonexistent", "--global"])
+        .env("HOME", &home)
+        .env("USERPROFILE", &home)
+        .output()
+        .expect("Failed to execute gex");
+
+    assert_eq!(output.status.code(), Some(3));
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"], "ProfileNotFound");
+    assert_eq!(error["message"], "Profile 'nonexistent' not found");
+    assert_eq!(error["code"], 3);
+
+    cleanup_test_env(&home);
+}
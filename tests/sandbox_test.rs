@@ -0,0 +1,530 @@
+//! End-to-end tests that really switch profiles, run against a throwaway
+//! home directory so the invoking user's git and SSH config are never
+//! touched.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An isolated home (profiles, `~/.ssh`, `~/.gitconfig`) with an empty
+/// git repository inside it, removed again on drop
+struct Sandbox {
+    home: PathBuf,
+    repo: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let home = std::env::temp_dir().join(format!("gex_sandbox_test_{}", timestamp));
+        let repo = home.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+
+        let sandbox = Self { home, repo };
+        sandbox.git(&["init", "-q"]);
+        sandbox
+    }
+
+    /// Point a command at the sandbox: gex's home, git's global config
+    /// and no system config
+    fn isolate<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        command
+            .current_dir(&self.repo)
+            .env("HOME", &self.home)
+            .env("USERPROFILE", &self.home)
+            .env("GEX_HOME", &self.home)
+            .env("GIT_CONFIG_GLOBAL", self.home.join(".gitconfig"))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env_remove("GIT_CONFIG_COUNT")
+    }
+
+    fn gex(&self, args: &[&str]) -> Output {
+        self.isolate(&mut Command::new(binary_path()))
+            .args(args)
+            .output()
+            .expect("Failed to execute gex")
+    }
+
+    /// Run gex, failing the test with its output if it doesn't succeed
+    fn gex_ok(&self, args: &[&str]) -> String {
+        let output = self.gex(args);
+        assert!(
+            output.status.success(),
+            "gex {:?} failed:\n{}{}",
+            args,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    fn git(&self, args: &[&str]) -> String {
+        let output = self.isolate(&mut Command::new("git")).args(args).output().unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Write a placeholder key pair under the sandbox's `~/.ssh`, private
+    /// key readable only by its owner as ssh expects
+    fn dummy_key(&self, name: &str, comment: &str) {
+        let ssh_dir = self.home.join(".ssh");
+        fs::create_dir_all(&ssh_dir).unwrap();
+        fs::write(ssh_dir.join(name), "dummy private key\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(ssh_dir.join(name), fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        fs::write(ssh_dir.join(format!("{}.pub", name)), format!("ssh-ed25519 AAAAdummy {}\n", comment)).unwrap();
+    }
+
+    fn read(&self, path: impl AsRef<Path>) -> String {
+        fs::read_to_string(self.home.join(path)).unwrap_or_default()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}
+
+fn binary_path() -> PathBuf {
+    let mut path = std::env::current_exe().unwrap();
+    path.pop(); // Remove test binary name
+    path.pop(); // Remove 'deps' directory
+    path.push("gex");
+    if cfg!(windows) {
+        path.set_extension("exe");
+    }
+    path
+}
+
+#[test]
+fn test_add_switch_local_status() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["switch", "work"]);
+
+    // The repository's own config carries the identity...
+    assert_eq!(sandbox.git(&["config", "--local", "user.name"]), "john-work");
+    assert_eq!(sandbox.git(&["config", "--local", "user.email"]), "john@work.com");
+    // ...and the global one was left alone
+    assert_eq!(sandbox.read(".gitconfig"), "");
+
+    let ssh_config = sandbox.read(".ssh/config");
+    let key_path = sandbox.home.join(".ssh").join("id_work");
+    assert!(ssh_config.contains(&format!(
+        "# GitHub Profile: work\nHost github.com-work\n  HostName github.com\n  User git\n  IdentityFile {}\n  IdentitiesOnly yes\n",
+        key_path.display()
+    )), "{}", ssh_config);
+
+    let status = sandbox.gex_ok(&["status", "--scope", "local"]);
+    assert!(status.contains("Profile: work"), "{}", status);
+    assert!(status.contains("Email: john@work.com"));
+}
+
+#[test]
+fn test_current_color() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work", "--profile-color", "red"]);
+    sandbox.gex_ok(&["switch", "work"]);
+
+    // Piped output is plain; a bare --color forces it for shell prompts
+    assert_eq!(sandbox.gex_ok(&["current"]), "work\n");
+    assert_eq!(sandbox.gex_ok(&["current", "--color"]), "\x1b[31mwork\x1b[0m\n");
+    assert_eq!(sandbox.gex_ok(&["current", "--color", "always"]), "\x1b[31mwork\x1b[0m\n");
+    assert_eq!(sandbox.gex_ok(&["current", "--color", "--no-color"]), "work\n");
+    assert_eq!(sandbox.gex_ok(&["current", "--color", "never"]), "work\n");
+}
+
+#[test]
+fn test_switch_global_then_between_profiles() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.dummy_key("id_oss", "john@oss.dev");
+
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["add", "oss", "-u", "john-oss", "-e", "john@oss.dev", "-s", "id_oss"]);
+
+    sandbox.gex_ok(&["switch", "work", "--global"]);
+    assert_eq!(sandbox.git(&["config", "--global", "user.email"]), "john@work.com");
+    assert_eq!(sandbox.git(&["config", "--local", "user.email"]), "");
+
+    // A local switch overrides the global identity in this repository only
+    sandbox.gex_ok(&["switch", "oss"]);
+    assert_eq!(sandbox.git(&["config", "user.email"]), "john@oss.dev");
+    assert_eq!(sandbox.git(&["config", "--global", "user.email"]), "john@work.com");
+
+    let ssh_config = sandbox.read(".ssh/config");
+    assert!(ssh_config.contains("Host github.com-work\n"));
+    assert!(ssh_config.contains("Host github.com-oss\n"));
+
+    let status = sandbox.gex_ok(&["status"]);
+    let (global, local) = status.split_once("Local").unwrap();
+    assert!(global.contains("Profile: work"), "{}", status);
+    assert!(local.contains("Profile: oss"), "{}", status);
+}
+
+#[test]
+fn test_switch_local_warns_on_other_profiles_origin() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.dummy_key("id_home", "john@home.dev");
+
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["add", "personal", "-u", "john-doe", "-e", "john@home.dev", "-s", "id_home"]);
+    sandbox.git(&["remote", "add", "origin", "git@github.com-work:acme/app.git"]);
+
+    // Without a terminal to confirm on, the switch is refused
+    let output = sandbox.gex(&["switch", "personal"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("goes through 'github.com-work'"));
+    assert_eq!(sandbox.git(&["config", "--local", "user.email"]), "");
+
+    let stdout = sandbox.gex_ok(&["switch", "personal", "--yes"]);
+    assert!(stdout.contains("Warning"));
+    assert_eq!(sandbox.git(&["config", "--local", "user.email"]), "john@home.dev");
+
+    // The matching profile and global switches don't ask
+    let stdout = sandbox.gex_ok(&["switch", "work"]);
+    assert!(!stdout.contains("Warning"));
+    sandbox.gex_ok(&["switch", "personal", "--global"]);
+}
+
+#[test]
+fn test_switch_by_list_number() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.dummy_key("id_oss", "john@oss.dev");
+
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["add", "oss", "-u", "john-oss", "-e", "john@oss.dev", "-s", "id_oss"]);
+
+    let list = sandbox.gex_ok(&["list"]);
+    assert!(list.contains("  1. ● work\n") && list.contains("  2. ● oss\n"), "{}", list);
+
+    sandbox.gex_ok(&["switch", "2"]);
+    assert_eq!(sandbox.git(&["config", "--local", "user.email"]), "john@oss.dev");
+
+    let output = sandbox.gex(&["switch", "3"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No profile number 3"));
+}
+
+#[test]
+fn test_list_sort_by_usage() {
+    let sandbox = Sandbox::new();
+    for (name, email) in [("work", "john@work.com"), ("oss", "john@oss.dev"), ("archived", "john@old.dev")] {
+        let key = format!("id_{}", name);
+        sandbox.dummy_key(&key, email);
+        sandbox.gex_ok(&["add", name, "-u", "john", "-e", email, "-s", &key]);
+    }
+
+    sandbox.gex_ok(&["switch", "work"]);
+    sandbox.gex_ok(&["switch", "oss"]);
+
+    // Most recent first, never used last; numbers still follow stored order
+    let list = sandbox.gex_ok(&["list", "--sort-by-usage"]);
+    let oss = list.find("2. ● oss").unwrap();
+    let work = list.find("1. ● work").unwrap();
+    let archived = list.find("3. ● archived").unwrap();
+    assert!(oss < work && work < archived, "{}", list);
+
+    let list = sandbox.gex_ok(&["list"]);
+    assert!(list.find("1. ● work").unwrap() < list.find("2. ● oss").unwrap());
+}
+
+#[test]
+fn test_switch_porcelain() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+
+    assert_eq!(sandbox.gex_ok(&["switch", "work", "--porcelain"]), "switched\twork\tlocal\n");
+    assert_eq!(sandbox.gex_ok(&["switch", "1", "--global", "--porcelain"]), "switched\twork\tglobal\n");
+    assert_eq!(sandbox.git(&["config", "--global", "user.email"]), "john@work.com");
+    assert_eq!(sandbox.gex_ok(&["switch", "work", "--quiet"]), "");
+
+    let output = sandbox.gex(&["switch", "missing", "--porcelain"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing"));
+}
+
+#[test]
+fn test_profile_note_on_switch_and_status() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work",
+        "--note", "squash-merge only on this account"]);
+
+    let stdout = sandbox.gex_ok(&["switch", "work"]);
+    assert!(stdout.contains("📌 Note: squash-merge only on this account"), "{}", stdout);
+    let status = sandbox.gex_ok(&["status", "--scope", "local"]);
+    assert!(status.contains("  Note: squash-merge only on this account\n"), "{}", status);
+
+    // Quiet switches still surface the reminder, on stderr
+    let output = sandbox.gex(&["switch", "work", "--quiet"]);
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("squash-merge only"));
+
+    sandbox.gex_ok(&["edit", "work", "--note", ""]);
+    assert!(!sandbox.gex_ok(&["switch", "work"]).contains("Note:"));
+}
+
+#[test]
+fn test_add_git_config_applied_on_switch() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.dummy_key("id_personal", "john@home.com");
+
+    let output = sandbox.gex(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work",
+        "--git-config", "badkey"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("key=value"));
+
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work",
+        "--git-config", "pull.rebase=true", "--git-config", "core.autocrlf=input"]);
+    sandbox.gex_ok(&["add", "personal", "-u", "john", "-e", "john@home.com", "-s", "id_personal"]);
+
+    sandbox.gex_ok(&["switch", "work"]);
+    assert_eq!(sandbox.git(&["config", "--local", "pull.rebase"]), "true");
+    assert_eq!(sandbox.git(&["config", "--local", "core.autocrlf"]), "input");
+
+    sandbox.gex_ok(&["switch", "personal"]);
+    assert_eq!(sandbox.git(&["config", "--local", "pull.rebase"]), "");
+    assert_eq!(sandbox.git(&["config", "--local", "core.autocrlf"]), "");
+}
+
+#[test]
+fn test_switch_fix_remote() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.dummy_key("id_home", "john@home.dev");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["add", "personal", "-u", "john-doe", "-e", "john@home.dev", "-s", "id_home"]);
+
+    sandbox.git(&["remote", "add", "origin", "https://github.com/acme/app.git"]);
+    let stdout = sandbox.gex_ok(&["switch", "work", "--fix-remote"]);
+    assert!(stdout.contains("before: https://github.com/acme/app.git"), "{}", stdout);
+    assert!(stdout.contains("after:  git@github.com-work:acme/app.git"), "{}", stdout);
+    assert_eq!(sandbox.git(&["remote", "get-url", "origin"]), "git@github.com-work:acme/app.git");
+
+    // Another profile's alias is rewritten without asking, as the mismatch goes away
+    sandbox.gex_ok(&["switch", "personal", "--set-upstream-remote"]);
+    assert_eq!(sandbox.git(&["remote", "get-url", "origin"]), "git@github.com-personal:acme/app.git");
+    assert!(sandbox.gex_ok(&["switch", "personal", "--fix-remote"]).contains("already uses"));
+
+    sandbox.git(&["remote", "set-url", "origin", "git@gitlab.com:acme/app.git"]);
+    let stdout = sandbox.gex_ok(&["switch", "work", "--fix-remote"]);
+    assert!(stdout.contains("is not a github.com URL; left unchanged"), "{}", stdout);
+    assert_eq!(sandbox.git(&["remote", "get-url", "origin"]), "git@gitlab.com:acme/app.git");
+}
+
+#[test]
+fn test_switch_missing_key_changes_nothing() {
+    let sandbox = Sandbox::new();
+
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    assert!(!sandbox.gex(&["switch", "work"]).status.success());
+
+    assert_eq!(sandbox.git(&["config", "--local", "user.email"]), "");
+    assert!(!sandbox.read(".ssh/config").contains("Host github.com-work"));
+}
+
+#[test]
+fn test_edit_agent_toggle_rewrites_host_block() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["switch", "work"]);
+    assert!(!sandbox.read(".ssh/config").contains("AddKeysToAgent"));
+
+    let stdout = sandbox.gex_ok(&["edit", "work", "--add-keys-to-agent", "true"]);
+    assert!(stdout.contains("Updated the SSH host block"), "{}", stdout);
+    assert!(sandbox.read(".ssh/config").contains("  AddKeysToAgent yes\n"));
+
+    sandbox.gex_ok(&["edit", "work", "--add-keys-to-agent", "false"]);
+    assert!(!sandbox.read(".ssh/config").contains("AddKeysToAgent"));
+}
+
+#[test]
+fn test_edit_port_rewrites_host_block() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["switch", "work"]);
+
+    assert!(!sandbox.gex(&["edit", "work", "--port", "0"]).status.success());
+    sandbox.gex_ok(&["edit", "work", "--port", "443"]);
+    let ssh_config = sandbox.read(".ssh/config");
+    assert!(ssh_config.contains("  HostName ssh.github.com\n") && ssh_config.contains("  Port 443\n"), "{}", ssh_config);
+
+    sandbox.gex_ok(&["edit", "work", "--port", "none"]);
+    let ssh_config = sandbox.read(".ssh/config");
+    assert!(ssh_config.contains("  HostName github.com\n") && !ssh_config.contains("Port"), "{}", ssh_config);
+
+    // Without a terminal the interactive prompts fail cleanly
+    let output = sandbox.isolate(&mut Command::new(binary_path()))
+        .args(["edit", "work"])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"));
+}
+
+#[test]
+fn test_failed_verify_restores_git_config() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work",
+        "--pull-rebase", "true", "--git-config", "core.autocrlf=input"]);
+
+    // An include after the [user] section overrides whatever gex writes there
+    sandbox.git(&["config", "--local", "user.name", "old-name"]);
+    sandbox.git(&["config", "--local", "user.email", "old@example.com"]);
+    sandbox.git(&["config", "--local", "include.path", "override.inc"]);
+    fs::write(sandbox.repo.join(".git/override.inc"), "[user]\n\temail = forced@example.com\n").unwrap();
+
+    let output = sandbox.gex(&["switch", "work"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("forced@example.com"));
+
+    // Nothing gex wrote is left behind
+    assert_eq!(sandbox.git(&["config", "--local", "--no-includes", "user.name"]), "old-name");
+    assert_eq!(sandbox.git(&["config", "--local", "--no-includes", "user.email"]), "old@example.com");
+    assert_eq!(sandbox.git(&["config", "--local", "pull.rebase"]), "");
+    assert_eq!(sandbox.git(&["config", "--local", "core.autocrlf"]), "");
+    assert_eq!(sandbox.git(&["config", "--local", "gex.configKeys"]), "");
+    assert!(!sandbox.read(".ssh/config").contains("Host github.com-work"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ssh_sync_skips_commit_identity_only() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_idonly", "john@work.com");
+    sandbox.gex_ok(&[
+        "add", "idonly", "-u", "john-work", "-e", "john@work.com", "-s", "id_idonly",
+        "--commit-identity-only",
+    ]);
+
+    let check = sandbox.gex_ok(&["ssh-sync", "--check"]);
+    assert!(check.contains("in sync"), "{}", check);
+
+    sandbox.gex_ok(&["ssh-sync"]);
+    assert!(!sandbox.read(".ssh/config").contains("github.com-idonly"));
+
+    let report = sandbox.gex_ok(&["doctor"]);
+    assert!(!report.contains("idonly"), "{}", report);
+}
+
+#[test]
+fn test_doctor_fix() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    sandbox.gex_ok(&["switch", "work"]);
+
+    // Hand-edit the managed block and leave one behind for a deleted profile
+    let config_path = sandbox.home.join(".ssh").join("config");
+    let edited = sandbox.read(".ssh/config").replace("IdentitiesOnly yes", "IdentitiesOnly no");
+    fs::write(&config_path, format!("{}\n# GitHub Profile: old\nHost github.com-old\n  HostName github.com\n", edited)).unwrap();
+    let key_path = sandbox.home.join(".ssh").join("id_work");
+    fs::set_permissions(&key_path, fs::Permissions::from_mode(0o644)).unwrap();
+
+    // The loose key is a failure, so doctor exits non-zero
+    let output = sandbox.gex(&["doctor"]);
+    assert!(!output.status.success());
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(report.contains("out of date for: work"), "{}", report);
+    assert!(report.contains("no profile uses: old"), "{}", report);
+    assert!(report.contains("is mode 644"), "{}", report);
+    assert!(report.contains("gex doctor --fix"), "{}", report);
+
+    sandbox.gex_ok(&["doctor", "--fix", "--yes"]);
+    let ssh_config = sandbox.read(".ssh/config");
+    assert!(ssh_config.contains("IdentitiesOnly yes"), "{}", ssh_config);
+    assert!(!ssh_config.contains("github.com-old"), "{}", ssh_config);
+    assert_eq!(fs::metadata(&key_path).unwrap().permissions().mode() & 0o777, 0o600);
+
+    let report = sandbox.gex_ok(&["doctor"]);
+    assert!(!report.contains("out of date") && !report.contains("is mode"), "{}", report);
+
+    // A missing SSH config is created (a warning, not a failure)
+    fs::remove_file(&config_path).unwrap();
+    assert!(sandbox.gex_ok(&["doctor"]).contains("does not exist"));
+    sandbox.gex_ok(&["doctor", "--fix", "--yes"]);
+    assert!(config_path.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_doctor_json() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+    let key_path = sandbox.home.join(".ssh").join("id_work");
+
+    fs::set_permissions(&key_path, fs::Permissions::from_mode(0o644)).unwrap();
+    let output = sandbox.gex(&["doctor", "--json"]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["ok"], false);
+    let checks = report["checks"].as_array().unwrap();
+    assert_eq!(checks[0]["name"], "git");
+    assert_eq!(checks[0]["status"], "ok");
+    let key_check = checks.iter().find(|c| c["name"] == "key permissions").unwrap();
+    assert_eq!(key_check["status"], "fail");
+    assert!(key_check["detail"].as_str().unwrap().contains("mode 644"));
+
+    // Warnings alone (e.g. no ssh-agent) still count as healthy
+    fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600)).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&sandbox.gex_ok(&["doctor", "--json"])).unwrap();
+    assert_eq!(report["ok"], true);
+    assert!(report["checks"].as_array().unwrap().iter().all(|c| c["status"] != "fail"));
+}
+
+#[test]
+fn test_global_switch_honors_git_config_global() {
+    let sandbox = Sandbox::new();
+    sandbox.dummy_key("id_work", "john@work.com");
+    sandbox.gex_ok(&["add", "work", "-u", "john-work", "-e", "john@work.com", "-s", "id_work"]);
+
+    let ci_config = sandbox.home.join("ci").join("gitconfig");
+    fs::create_dir_all(ci_config.parent().unwrap()).unwrap();
+    let gex_ci = |args: &[&str]| {
+        let output = sandbox
+            .isolate(&mut Command::new(binary_path()))
+            .env("GIT_CONFIG_GLOBAL", &ci_config)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    gex_ci(&["switch", "work", "--global"]);
+    let written = fs::read_to_string(&ci_config).unwrap();
+    assert!(written.contains("email = john@work.com"), "{}", written);
+    assert_eq!(sandbox.read(".gitconfig"), "");
+
+    let status = gex_ci(&["status", "--scope", "global"]);
+    assert!(status.contains(&format!("Config: {} (GIT_CONFIG_GLOBAL)", ci_config.display())), "{}", status);
+    assert!(status.contains("Profile: work"), "{}", status);
+
+    let doctor = gex_ci(&["doctor"]);
+    assert!(doctor.contains(&format!("global config: {} (from GIT_CONFIG_GLOBAL)", ci_config.display())), "{}", doctor);
+}